@@ -0,0 +1,40 @@
+/* glibc headers ask for single definitions with __need_size_t and the like */
+#if !defined __need_size_t && !defined __need_ptrdiff_t && !defined __need_wchar_t \
+    && !defined __need_NULL
+#define __need_size_t
+#define __need_ptrdiff_t
+#define __need_wchar_t
+#define __need_NULL
+#define __STDDEF_ALL
+#endif
+
+#if defined __need_size_t && !defined __SIZE_T_DEFINED
+#define __SIZE_T_DEFINED
+typedef unsigned long size_t;
+#endif
+
+#if defined __need_ptrdiff_t && !defined __PTRDIFF_T_DEFINED
+#define __PTRDIFF_T_DEFINED
+typedef long ptrdiff_t;
+#endif
+
+#if defined __need_wchar_t && !defined __WCHAR_T_DEFINED
+#define __WCHAR_T_DEFINED
+typedef int wchar_t;
+#endif
+
+#if defined __need_NULL && !defined NULL
+#define NULL ((void *)0)
+#endif
+
+#if defined __STDDEF_ALL && !defined __STDDEF_H
+#define __STDDEF_H
+typedef long max_align_t;
+#define offsetof(type, member) ((size_t)&(((type *)0)->member))
+#endif
+
+#undef __need_size_t
+#undef __need_ptrdiff_t
+#undef __need_wchar_t
+#undef __need_NULL
+#undef __STDDEF_ALL
@@ -1,6 +1,63 @@
+use crate::parser::Function;
+use crate::types::Type;
+use crate::Span;
+
 #[derive(Clone, Debug, Default)]
 pub struct Program {
-    pub stmt: CompoundStatement,
+    pub functions: Vec<Function>,
+    pub globals: Vec<Global>,
+    pub strings: Vec<Vec<u8>>,
+}
+
+/// What the declarations of a function ask of inlining the calls to it, the weakest first.
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Inlining {
+    Default,
+    // `inline`
+    Hint,
+    // `__attribute__((always_inline))`
+    Always,
+    // `__attribute__((noinline))`, which wins over the others
+    Never,
+}
+
+impl Default for Inlining {
+    fn default() -> Self {
+        Inlining::Default
+    }
+}
+
+/// The symbol of the string literal `Program::strings[id]`.
+pub fn string_label(id: usize) -> String {
+    format!(".L.str.{}", id)
+}
+
+/// A variable with static storage: at file scope, or declared `static` in a function.
+#[derive(Clone, Debug)]
+pub struct Global {
+    // function-local statics get a unique symbol, as in `count.3`
+    pub name: String,
+    pub ty: Type,
+    // internal linkage: the symbol is not exported
+    pub is_static: bool,
+    // tentative definitions, as `int x;`, have none and are zero-initialized
+    pub init: Option<StaticInit>,
+}
+
+/// The contents of an initialized global, as emitted in `.data`.
+#[derive(Clone, Debug)]
+pub struct StaticInit {
+    pub bytes: Vec<u8>,
+    // pointers to other symbols, which the linker resolves
+    pub relocs: Vec<Reloc>,
+}
+
+/// A pointer-sized slot at `offset` holding the address of `label` plus `addend`.
+#[derive(Clone, Debug)]
+pub struct Reloc {
+    pub offset: usize,
+    pub label: String,
+    pub addend: i64,
 }
 
 // pub struct BlockItemList {
@@ -14,7 +71,8 @@ pub struct Program {
 
 #[derive(Clone, Debug, Default)]
 pub struct CompoundStatement {
-    pub stmts: Vec<Statement>,
+    // each with where it starts in the sources, for warnings
+    pub stmts: Vec<(Statement, Span)>,
 }
 
 // non_case_statement
@@ -22,25 +80,68 @@ pub struct CompoundStatement {
 pub enum Statement {
     Compound(CompoundStatement),
     Expr(Expression),
-    Return(Expression),
+    Return(Option<Expression>),
     If(IfStatement),
     For(ForStatement),
     While(WhileStatement),
+    // the body runs before the condition is first checked
+    DoWhile(WhileStatement),
+    Switch(SwitchStatement),
+    Case(CaseStatement),
+    Break,
+    Continue,
+    Goto(String),
+    Label(String, Box<Statement>),
+    Declaration(Vec<Initialization>),
     Empty,
     // Block(BlockNode),
     // If(IfStmt),
     // For(ForStmt),
 }
 
+/// The initializer of a local, flattened into the stores it performs.
+#[derive(Clone, Debug)]
+pub struct Initialization {
+    pub local: LocalId,
+    // the local is zeroed before the stores, as they do not cover it entirely
+    pub zero_fill: bool,
+    pub stores: Vec<InitStore>,
+}
+
+#[derive(Clone, Debug)]
+pub struct InitStore {
+    // from the start of the local
+    pub offset: usize,
+    pub ty: Type,
+    pub value: Expression,
+}
+
 #[derive(Clone, Debug)]
 pub struct WhileStatement {
     pub condition: Option<Expression>,
     pub body: Box<Statement>,
 }
 
+/// `switch` jumps to the `Case` of its value, or to its default.
+#[derive(Clone, Debug)]
+pub struct SwitchStatement {
+    pub condition: Expression,
+    pub body: Box<Statement>,
+    // the value of each label in the body, in order, with `None` for `default`
+    pub cases: Vec<Option<i64>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct CaseStatement {
+    // into `SwitchStatement::cases` of the enclosing switch
+    pub index: usize,
+    pub body: Box<Statement>,
+}
+
 #[derive(Clone, Debug)]
 pub struct ForStatement {
-    pub init: Option<Expression>, // TODO(chrde): this should be a Declaration
+    // an expression or a declaration, scoped to the loop
+    pub init: Option<Box<Statement>>,
     pub condition: Option<Expression>,
     pub update: Option<Expression>,
     pub body: Box<Statement>,
@@ -64,6 +165,15 @@ pub struct BinaryExpr {
     pub lhs: Box<Expression>,
     pub rhs: Box<Expression>,
     pub op: BinOp,
+    // the operator in the sources, for warnings; none for the operations the parser adds
+    pub span: Option<Span>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ConditionalExpr {
+    pub condition: Box<Expression>,
+    pub then_expr: Box<Expression>,
+    pub else_expr: Box<Expression>,
 }
 
 #[derive(Clone, Debug)]
@@ -86,13 +196,51 @@ pub enum PointerOp {
 }
 
 #[derive(Clone, Debug)]
-pub enum Expression {
+pub struct MemberExpr {
+    pub base: Box<Expression>,
+    pub offset: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct CallExpr {
+    // a function, or a pointer to one
+    pub callee: Box<Expression>,
+    pub args: Vec<Expression>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub ty: Type,
+}
+
+impl Expression {
+    pub fn new(kind: ExpressionKind, ty: Type) -> Self {
+        Self { kind, ty }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum ExpressionKind {
     NumberLiteral(usize),
+    // index into `Program::strings`
+    StringLiteral(usize),
     Identifier(LocalId),
+    // a function or a variable with static storage, by its symbol
+    Symbol(String),
+    Member(MemberExpr),
     Binary(BinaryExpr),
     Unary(UnaryExpr),
     Assignment(AssignmentExpr),
+    Conditional(ConditionalExpr),
     Pointer(PointerExpr),
+    Call(CallExpr),
+    // converts its argument to the type of the expression
+    Cast(Box<Expression>),
+    // `va_start(ap, last)`, with the `va_list` as argument
+    VaStart(Box<Expression>),
+    // `va_arg(ap, type)`, with the `va_list` as argument and `type` as the type of the expression
+    VaArg(Box<Expression>),
 }
 
 // old
@@ -133,6 +281,10 @@ pub enum Expression {
 #[derive(Copy, Clone, Debug)]
 pub enum UnaryOp {
     Neg,
+    // `!`
+    Not,
+    // `~`
+    BitNot,
     Addr,
     Deref,
     // TODO(chrde): this is a hack...
@@ -186,31 +338,38 @@ pub enum BinOp {
     Sub,
     Mul,
     Div,
+    Mod,
     EqCmp,
     NeqCmp,
     LowerCmp,
     LowerEqCmp,
     GreaterCmp,
     GreaterEqCmp,
+    Shl,
+    Shr,
+    BitAnd,
+    BitOr,
+    BitXor,
+    // `&&` and `||` only evaluate their right operand when it decides the result
+    LogAnd,
+    LogOr,
+    // the value of the right operand, after evaluating the left one
+    Comma,
 }
 
 #[derive(Clone, Debug)]
 pub struct Local {
     pub name: String,
-    pub offset: usize,
+    pub ty: Type,
 }
 
 impl Local {
-    pub fn new(name: String) -> Self {
-        Self { name, offset: 0 }
-    }
-
-    pub fn name(&self) -> &str {
-        &self.name
+    pub fn new(name: String, ty: Type) -> Self {
+        Self { name, ty }
     }
 
-    pub fn offset(&self) -> usize {
-        self.offset
+    pub fn ty(&self) -> &Type {
+        &self.ty
     }
 }
 
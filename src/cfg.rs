@@ -0,0 +1,254 @@
+//! Analyses of the control flow graph of a function: the order of its blocks, which of them
+//! dominate which, and the loops they make.
+
+use crate::ir::{BlockId, Function, Op, Terminator};
+use std::mem;
+
+/// The blocks reachable from the entry, each one before its successors but along back edges.
+pub fn reverse_postorder(func: &Function) -> Vec<BlockId> {
+    let mut visited = vec![false; func.blocks.len()];
+    let mut postorder = vec![];
+    // each block with the index of the next successor to visit
+    let mut stack = vec![(BlockId(0), 0)];
+    visited[0] = true;
+    while let Some(&(block, next)) = stack.last() {
+        match func.blocks[block.0].term.successors().get(next) {
+            Some(succ) => {
+                stack.last_mut().unwrap().1 += 1;
+                if !visited[succ.0] {
+                    visited[succ.0] = true;
+                    stack.push((*succ, 0));
+                }
+            }
+            None => {
+                postorder.push(block);
+                stack.pop();
+            }
+        }
+    }
+    postorder.reverse();
+    postorder
+}
+
+/// Drops the blocks that cannot be reached from the entry, keeping the others in order.
+pub fn remove_unreachable(func: &mut Function) {
+    let mut reachable = vec![false; func.blocks.len()];
+    for block in reverse_postorder(func) {
+        reachable[block.0] = true;
+    }
+    for block in &mut func.blocks {
+        for inst in &mut block.insts {
+            if let Op::Phi(incoming) = &mut inst.op {
+                incoming.retain(|(pred, _)| reachable[pred.0]);
+            }
+        }
+    }
+    let order: Vec<_> = (0..func.blocks.len())
+        .filter(|&b| reachable[b])
+        .map(BlockId)
+        .collect();
+    func.reorder(&order);
+}
+
+/// Appends each block to the one jumping to it, when that is its only predecessor, as the
+/// lowering starts a block for each statement that may be jumped to.
+pub fn merge_blocks(func: &mut Function) {
+    let preds = func.predecessors();
+    let mut merged = false;
+    for b in 0..func.blocks.len() {
+        // a block appended to another is not jumped to any more, and stays empty
+        while let Terminator::Jump(succ) = func.blocks[b].term {
+            let phis = func.blocks[succ.0]
+                .insts
+                .iter()
+                .any(|inst| matches!(inst.op, Op::Phi(_)));
+            if succ.0 == b || succ.0 == 0 || preds[succ.0].len() != 1 || phis {
+                break;
+            }
+            let insts = mem::take(&mut func.blocks[succ.0].insts);
+            let term = mem::replace(&mut func.blocks[succ.0].term, Terminator::Return(None));
+            for next in term.successors() {
+                for inst in &mut func.blocks[next.0].insts {
+                    if let Op::Phi(incoming) = &mut inst.op {
+                        incoming
+                            .iter_mut()
+                            .filter(|(pred, _)| *pred == succ)
+                            .for_each(|(pred, _)| *pred = BlockId(b));
+                    }
+                }
+            }
+            func.blocks[b].insts.extend(insts);
+            func.blocks[b].term = term;
+            merged = true;
+        }
+    }
+    if merged {
+        remove_unreachable(func);
+    }
+}
+
+/// A block dominates another when every path from the entry to the other goes through it.
+/// Only the blocks reachable from the entry are in the tree.
+pub struct DomTree {
+    // the closest strict dominator of each block, none for the entry
+    idom: Vec<Option<BlockId>>,
+    // the blocks each one is the immediate dominator of, in reverse postorder
+    children: Vec<Vec<BlockId>>,
+}
+
+impl DomTree {
+    // as in "A Simple, Fast Dominance Algorithm", by Cooper, Harvey and Kennedy
+    pub fn new(func: &Function) -> Self {
+        let order = reverse_postorder(func);
+        let mut rank = vec![usize::MAX; func.blocks.len()];
+        for (i, block) in order.iter().enumerate() {
+            rank[block.0] = i;
+        }
+        let preds = func.predecessors();
+        // the entry is its own dominator while they are computed
+        let mut idom = vec![None; func.blocks.len()];
+        idom[0] = Some(BlockId(0));
+        let intersect = |idom: &[Option<BlockId>], mut a: BlockId, mut b: BlockId| {
+            while a != b {
+                while rank[a.0] > rank[b.0] {
+                    a = idom[a.0].unwrap();
+                }
+                while rank[b.0] > rank[a.0] {
+                    b = idom[b.0].unwrap();
+                }
+            }
+            a
+        };
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for block in &order[1..] {
+                let mut new_idom = None;
+                for pred in &preds[block.0] {
+                    if idom[pred.0].is_none() {
+                        continue;
+                    }
+                    new_idom = Some(match new_idom {
+                        Some(other) => intersect(&idom, *pred, other),
+                        None => *pred,
+                    });
+                }
+                if idom[block.0] != new_idom {
+                    idom[block.0] = new_idom;
+                    changed = true;
+                }
+            }
+        }
+        idom[0] = None;
+        let mut children = vec![vec![]; func.blocks.len()];
+        for block in &order[1..] {
+            children[idom[block.0].unwrap().0].push(*block);
+        }
+        Self { idom, children }
+    }
+
+    pub fn children(&self, block: BlockId) -> &[BlockId] {
+        &self.children[block.0]
+    }
+
+    /// Whether `a` dominates `b`, which it does if they are the same.
+    pub fn dominates(&self, a: BlockId, mut b: BlockId) -> bool {
+        loop {
+            if a == b {
+                return true;
+            }
+            match self.idom[b.0] {
+                Some(idom) => b = idom,
+                None => return false,
+            }
+        }
+    }
+
+    /// The dominance frontier of each block: the blocks it does not strictly dominate, but
+    /// one of whose predecessors it dominates. That is where the paths from it meet others.
+    pub fn frontiers(&self, func: &Function) -> Vec<Vec<BlockId>> {
+        let mut frontiers = vec![vec![]; func.blocks.len()];
+        for (b, preds) in func.predecessors().iter().enumerate() {
+            let idom = match self.idom[b] {
+                Some(idom) if preds.len() > 1 => idom,
+                _ => continue,
+            };
+            for pred in preds {
+                let mut runner = *pred;
+                // unreachable predecessors are not in the tree
+                if runner.0 != 0 && self.idom[runner.0].is_none() {
+                    continue;
+                }
+                while runner != idom {
+                    let frontier: &mut Vec<_> = &mut frontiers[runner.0];
+                    if !frontier.contains(&BlockId(b)) {
+                        frontier.push(BlockId(b));
+                    }
+                    runner = self.idom[runner.0].unwrap();
+                }
+            }
+        }
+        frontiers
+    }
+}
+
+/// A natural loop: a header, and the blocks that reach a jump back to it without going
+/// through it. The header dominates them all, so it is the only way into the loop.
+pub struct Loop {
+    pub header: BlockId,
+    // the blocks jumping back to the header
+    pub latches: Vec<BlockId>,
+    // whether each block of the function is in the loop
+    body: Vec<bool>,
+}
+
+impl Loop {
+    pub fn contains(&self, block: BlockId) -> bool {
+        self.body[block.0]
+    }
+
+    /// The blocks of the loop, in the order of the function.
+    pub fn blocks(&self) -> impl Iterator<Item = BlockId> + '_ {
+        let body = self.body.iter().enumerate();
+        body.filter(|(_, in_loop)| **in_loop)
+            .map(|(b, _)| BlockId(b))
+    }
+}
+
+/// The natural loops of a function, the inner ones before those around them. The loops with
+/// the same header are taken as one.
+pub fn natural_loops(func: &Function, dom: &DomTree) -> Vec<Loop> {
+    let mut loops: Vec<Loop> = vec![];
+    for (b, block) in func.blocks.iter().enumerate() {
+        let latch = BlockId(b);
+        for header in block.term.successors() {
+            if !dom.dominates(header, latch) {
+                continue;
+            }
+            match loops.iter_mut().find(|l| l.header == header) {
+                Some(l) if l.latches.contains(&latch) => {}
+                Some(l) => l.latches.push(latch),
+                None => loops.push(Loop {
+                    header,
+                    latches: vec![latch],
+                    body: vec![],
+                }),
+            }
+        }
+    }
+    let preds = func.predecessors();
+    for l in &mut loops {
+        l.body = vec![false; func.blocks.len()];
+        l.body[l.header.0] = true;
+        let mut work = l.latches.clone();
+        while let Some(block) = work.pop() {
+            // but from the blocks that cannot be reached
+            if !l.body[block.0] && dom.dominates(l.header, block) {
+                l.body[block.0] = true;
+                work.extend(&preds[block.0]);
+            }
+        }
+    }
+    loops.sort_by_key(|l| l.blocks().count());
+    loops
+}
@@ -1,272 +1,580 @@
-use crate::{ast::*, parser::Function};
+use crate::ast::{string_label, Global};
+use crate::ir::{BinOp, BlockId, Callee, Cond, Function, Op, Program, Terminator, Ty, UnOp, Value};
+use crate::regalloc::{self, Location};
+use crate::types::align_to;
+use crate::x86::Listing;
+use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::fmt::{self, Write};
 
+const ARG_REGS_64: [&str; 6] = ["%rdi", "%rsi", "%rdx", "%rcx", "%r8", "%r9"];
+
+// zeroing anything larger than this takes a `rep stosb` rather than a run of stores
+const ZERO_FILL_STORES_MAX: usize = 64;
+
 pub struct Assembly<'a> {
-    counter: usize,
-    depth: usize,
+    // all but the code of the functions, which goes at the offsets in `code`
     content: String,
-    func: &'a Function,
+    code: Vec<(usize, Listing)>,
+    program: &'a Program,
+    func: Option<&'a Function>,
+    frame: Frame,
+}
+
+// Where the slots and the values of a function are. Below %rbp are the callee-saved
+// registers the function uses, then the slots, then the values spilled by the register
+// allocation, 8 bytes each.
+#[derive(Default)]
+struct Frame {
+    // offsets below %rbp
+    slots: Vec<usize>,
+    spills: Vec<usize>,
+    locations: Vec<Option<Location>>,
+    callee_saved: Vec<&'static str>,
+    // what the prologue allocates past the saved registers
+    size: usize,
+}
+
+impl Frame {
+    fn new(func: &Function) -> Self {
+        let allocation = regalloc::allocate(func);
+        let saved = allocation.callee_saved.len() * 8;
+        let mut size = saved;
+        let mut slots = vec![0; func.slots.len()];
+        for (i, slot) in func.slots.iter().enumerate().rev() {
+            size = align_to(size + slot.size, slot.align);
+            slots[i] = size;
+        }
+        let spills = (0..allocation.spills)
+            .map(|_| {
+                size = align_to(size + 8, 8);
+                size
+            })
+            .collect();
+        Self {
+            slots,
+            spills,
+            locations: allocation.locations,
+            callee_saved: allocation.callee_saved,
+            size: align_to(size, 16) - saved,
+        }
+    }
+
+    // the operand holding a value: a register, or its spill slot
+    fn value(&self, v: Value) -> String {
+        match self.locations[v.0].expect("a value never defined") {
+            Location::Reg(reg) => reg.to_string(),
+            Location::Spill(i) => format!("-{}(%rbp)", self.spills[i]),
+        }
+    }
+
+    fn reg(&self, v: Value) -> Option<&'static str> {
+        match self.locations[v.0] {
+            Some(Location::Reg(reg)) => Some(reg),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for Assembly<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.content)
+        let mut pos = 0;
+        for (offset, listing) in &self.code {
+            write!(f, "{}{}", &self.content[pos..*offset], listing)?;
+            pos = *offset;
+        }
+        write!(f, "{}", &self.content[pos..])
     }
 }
 
 impl<'a> Assembly<'a> {
-    pub fn new(func: &'a Function) -> Self {
+    pub fn new(program: &'a Program) -> Self {
         Self {
-            counter: 0,
-            depth: 0,
             content: String::new(),
-            func,
+            code: vec![],
+            program,
+            func: None,
+            frame: Frame::default(),
         }
     }
 
+    fn func(&self) -> &'a Function {
+        self.func.expect("not inside a function")
+    }
+
     pub fn finish(self) -> String {
-        assert_eq!(0, self.depth);
-        self.content
+        self.to_string()
+    }
+
+    /// The code of each function, to be rewritten before it is finished.
+    pub fn code_mut(&mut self) -> impl Iterator<Item = &mut Listing> {
+        self.code.iter_mut().map(|(_, listing)| listing)
     }
 
-    pub fn push(&mut self) {
-        self.writeln("  push %rax");
-        self.depth += 1;
+    pub fn gen(&mut self) {
+        let program = self.program;
+        for func in &program.functions {
+            self.func = Some(func);
+            self.frame = Frame::new(func);
+            self.gen_function(func);
+        }
+        self.func = None;
+        for global in &program.globals {
+            self.gen_global(global);
+        }
+        self.gen_data(&program.strings);
+        // without it, the linker warns and makes the stack executable
+        self.writeln("  .section .note.GNU-stack,\"\",@progbits");
     }
 
-    pub fn pop(&mut self, s: &str) {
-        writeln!(self.content, "  pop {}", s).unwrap();
-        self.depth -= 1;
+    // initialized globals go in `.data`, the others in `.bss`
+    fn gen_global(&mut self, global: &Global) {
+        if !global.is_static {
+            writeln!(self.content, "  .globl {}", global.name).unwrap();
+        }
+        let init = match &global.init {
+            Some(init) => init,
+            None => {
+                self.writeln("  .bss");
+                writeln!(self.content, "  .align {}", global.ty.align()).unwrap();
+                writeln!(self.content, "{}:", global.name).unwrap();
+                writeln!(self.content, "  .zero {}", global.ty.size()).unwrap();
+                return;
+            }
+        };
+        self.writeln("  .data");
+        writeln!(self.content, "  .align {}", global.ty.align()).unwrap();
+        writeln!(self.content, "{}:", global.name).unwrap();
+        let mut pos = 0;
+        for reloc in &init.relocs {
+            self.gen_bytes(&init.bytes[pos..reloc.offset]);
+            writeln!(self.content, "  .quad {}{:+}", reloc.label, reloc.addend).unwrap();
+            pos = reloc.offset + 8;
+        }
+        self.gen_bytes(&init.bytes[pos..]);
     }
 
-    fn count_inc(&mut self) -> usize {
-        self.counter += 1;
-        self.counter
+    fn gen_bytes(&mut self, bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+        let bytes: Vec<_> = bytes.iter().map(|b| b.to_string()).collect();
+        writeln!(self.content, "  .byte {}", bytes.join(",")).unwrap();
     }
 
-    pub fn gen(&mut self) {
-        self.writeln("  .globl main");
-        self.writeln("main:");
+    fn gen_data(&mut self, strings: &[Vec<u8>]) {
+        if strings.is_empty() {
+            return;
+        }
+        self.writeln("  .data");
+        for (i, bytes) in strings.iter().enumerate() {
+            writeln!(self.content, "{}:", string_label(i)).unwrap();
+            self.write("  .byte ");
+            for b in bytes {
+                write!(self.content, "{},", b).unwrap();
+            }
+            self.writeln("0");
+        }
+    }
+
+    fn gen_function(&mut self, func: &Function) {
+        if !func.is_static {
+            writeln!(self.content, "  .globl {}", func.name).unwrap();
+        }
+        self.writeln("  .text");
+        let start = self.content.len();
+        writeln!(self.content, "{}:", func.name).unwrap();
 
         self.writeln("  push %rbp");
         self.writeln("  mov %rsp, %rbp");
-        writeln!(self.content, "  sub ${}, %rsp", self.func.stack_size()).unwrap();
-        for stmt in &self.func.body().stmts {
-            self.gen_stmt(stmt);
+        for reg in &self.frame.callee_saved {
+            writeln!(self.content, "  push {}", reg).unwrap();
+        }
+        if self.frame.size > 0 {
+            writeln!(self.content, "  sub ${}, %rsp", self.frame.size).unwrap();
+        }
+        if let Some(va_area) = func.va_area {
+            let offset = self.frame.slots[va_area.0];
+            for (i, reg) in ARG_REGS_64.iter().enumerate() {
+                writeln!(self.content, "  mov {}, -{}(%rbp)", reg, offset - i * 8).unwrap();
+            }
+            for i in 0..8 {
+                let slot = offset - ARG_REGS_64.len() * 8 - i * 16;
+                writeln!(self.content, "  movsd %xmm{}, -{}(%rbp)", i, slot).unwrap();
+            }
+        }
+        // the parameters start the entry block, and their registers may hold others
+        let params: Vec<_> = func.blocks[0]
+            .insts
+            .iter()
+            .take_while(|inst| matches!(inst.op, Op::Param(_)))
+            .map(|inst| match inst.op {
+                Op::Param(i) => (self.frame.value(inst.result.unwrap()), param(i)),
+                _ => unreachable!(),
+            })
+            .collect();
+        let param_count = params.len();
+        self.parallel_move(params, "%r11");
+
+        let uses = use_counts(func);
+        for (i, block) in func.blocks.iter().enumerate() {
+            // the entry block is never jumped to
+            if i > 0 {
+                writeln!(self.content, "{}:", self.label(BlockId(i))).unwrap();
+            }
+            // a comparison only the branch after it reads is left in the flags
+            let fused = match (block.insts.last(), &block.term) {
+                (Some(inst), Terminator::Branch { cond, .. })
+                    if inst.result == Some(*cond) && uses[cond] == 1 =>
+                {
+                    match inst.op {
+                        Op::Cmp(cond, ..) => Some(cond),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+            let skipped = if i == 0 { param_count } else { 0 };
+            for (j, inst) in block.insts.iter().enumerate().skip(skipped) {
+                match (&inst.op, inst.result) {
+                    (Op::Cmp(_, lhs, rhs), _) if fused.is_some() && j == block.insts.len() - 1 => {
+                        self.compare(*lhs, *rhs)
+                    }
+                    (Op::Const(n), Some(result)) => self.gen_const(*n, result),
+                    (Op::Move(arg), Some(result)) => {
+                        let home = self.frame.value(result);
+                        self.mov(*arg, &home);
+                    }
+                    (op, result) => {
+                        self.gen_inst(op);
+                        if let Some(result) = result {
+                            let home = self.frame.value(result);
+                            self.mov_loc("%rax", &home);
+                        }
+                    }
+                }
+            }
+            self.gen_terminator(&block.term, fused, BlockId(i + 1));
+        }
+        writeln!(self.content, ".L.return.{}:", func.name).unwrap();
+        if self.frame.callee_saved.is_empty() {
+            self.writeln("  mov %rbp, %rsp");
+        } else {
+            let saved = self.frame.callee_saved.len() * 8;
+            writeln!(self.content, "  lea -{}(%rbp), %rsp", saved).unwrap();
+            for reg in self.frame.callee_saved.clone().iter().rev() {
+                writeln!(self.content, "  pop {}", reg).unwrap();
+            }
         }
-        self.writeln(".L.return:");
-        self.writeln("  mov %rbp, %rsp");
         self.writeln("  pop %rbp");
 
         self.writeln("  ret");
+        let code = self.content.split_off(start);
+        self.code.push((start, Listing::parse(&code)));
+    }
+
+    fn label(&self, block: BlockId) -> String {
+        format!(".L.block.{}.{}", self.func().name, block.0)
+    }
+
+    // copies a value to a register, or to the place of another value
+    fn mov(&mut self, v: Value, dst: &str) {
+        let src = self.frame.value(v);
+        self.mov_loc(&src, dst);
+    }
+
+    // a move from memory to memory goes through %rax
+    fn mov_loc(&mut self, src: &str, dst: &str) {
+        if src == dst {
+            return;
+        }
+        if is_memory(src) && is_memory(dst) {
+            writeln!(self.content, "  mov {}, %rax", src).unwrap();
+            writeln!(self.content, "  mov %rax, {}", dst).unwrap();
+        } else {
+            writeln!(self.content, "  mov {}, {}", src, dst).unwrap();
+        }
     }
 
-    pub fn gen_stmt(&mut self, stmt: &Statement) {
-        match stmt {
-            Statement::Expr(e) => self.gen_expr(&e),
-            Statement::Return(r) => {
-                self.gen_expr(&r);
-                self.writeln("  jmp .L.return");
+    // The register holding a value, or `scratch` once the value is loaded into it.
+    fn reg(&mut self, v: Value, scratch: &'static str) -> &'static str {
+        match self.frame.reg(v) {
+            Some(reg) => reg,
+            None => {
+                self.mov(v, scratch);
+                scratch
             }
-            Statement::Compound(c) => {
-                for stmt in &c.stmts {
-                    self.gen_stmt(stmt);
+        }
+    }
+
+    // Moves `(dst, src)` that happen at once: none overwrites a place before the moves
+    // reading it are done. A cycle of registers, as in a swap, goes through `temp`.
+    fn parallel_move(&mut self, mut moves: Vec<(String, String)>, temp: &str) {
+        moves.retain(|(dst, src)| dst != src);
+        while !moves.is_empty() {
+            let ready = moves
+                .iter()
+                .position(|(dst, _)| moves.iter().all(|(_, src)| src != dst));
+            match ready {
+                Some(i) => {
+                    let (dst, src) = moves.remove(i);
+                    self.mov_loc(&src, &dst);
                 }
-            }
-            Statement::Empty => {}
-            // Statement::Block(b) => {
-            //     for stmt in &b.stmts {
-            //         self.gen_stmt(&stmt);
-            //     }
-            // }
-            Statement::If(i) => {
-                self.gen_expr(&i.condition);
-                self.writeln("  cmp $0, %rax");
-                let count = self.count_inc();
-                writeln!(self.content, "  je .L.else.{}", count).unwrap();
-                self.gen_stmt(&i.then_branch);
-                writeln!(self.content, "  jmp .L.end.{}", count).unwrap();
-                writeln!(self.content, ".L.else.{}:", count).unwrap();
-                if let Some(e) = &i.else_branch {
-                    self.gen_stmt(e);
+                None => {
+                    let saved = moves[0].0.clone();
+                    self.mov_loc(&saved, temp);
+                    for (_, src) in &mut moves {
+                        if *src == saved {
+                            *src = temp.to_string();
+                        }
+                    }
                 }
-                writeln!(self.content, ".L.end.{}:", count).unwrap();
             }
-            Statement::While(w) => {
-                let count = self.count_inc();
-                writeln!(self.content, ".L.begin.{}:", count).unwrap();
-                if let Some(e) = &w.condition {
-                    self.gen_expr(e);
-                    self.writeln("  cmp $0, %rax");
-                    writeln!(self.content, "  je .L.end.{}", count).unwrap();
+        }
+    }
+
+    fn gen_const(&mut self, n: i64, result: Value) {
+        let home = self.frame.value(result);
+        // only a register takes a 64-bit immediate
+        if is_memory(&home) && i32::try_from(n).is_err() {
+            writeln!(self.content, "  mov ${}, %rax", n).unwrap();
+            self.mov_loc("%rax", &home);
+        } else if is_memory(&home) {
+            writeln!(self.content, "  movq ${}, {}", n, home).unwrap();
+        } else {
+            writeln!(self.content, "  mov ${}, {}", n, home).unwrap();
+        }
+    }
+
+    // Jumps to the next block, which is laid out right after this one, are left out. A branch
+    // on a comparison already in the flags jumps on its condition, or on the inverse one to
+    // fall through to `then`.
+    fn gen_terminator(&mut self, term: &Terminator, flags: Option<Cond>, next: BlockId) {
+        match term {
+            Terminator::Jump(target) => self.jump(*target, next),
+            Terminator::Branch { cond, then, else_ } => {
+                let cc = match flags {
+                    Some(cc) => cc,
+                    None => {
+                        let reg = self.reg(*cond, "%rax");
+                        writeln!(self.content, "  cmp $0, {}", reg).unwrap();
+                        Cond::Ne
+                    }
+                };
+                if *then == next {
+                    let suffix = cond_suffix(cc.inverse());
+                    writeln!(self.content, "  j{} {}", suffix, self.label(*else_)).unwrap();
+                } else {
+                    let suffix = cond_suffix(cc);
+                    writeln!(self.content, "  j{} {}", suffix, self.label(*then)).unwrap();
+                    self.jump(*else_, next);
                 }
-                self.gen_stmt(&w.body);
-                writeln!(self.content, "  jmp .L.begin.{}", count).unwrap();
-                writeln!(self.content, ".L.end.{}:", count).unwrap();
             }
-            Statement::For(f) => {
-                if let Some(e) = &f.init {
-                    self.gen_expr(e);
+            Terminator::Return(value) => {
+                if let Some(value) = value {
+                    self.mov(*value, "%rax");
                 }
-                let count = self.count_inc();
-                writeln!(self.content, ".L.begin.{}:", count).unwrap();
-                if let Some(e) = &f.condition {
-                    self.gen_expr(e);
-                    self.writeln("  cmp $0, %rax");
-                    writeln!(self.content, "  je .L.end.{}", count).unwrap();
+                if next.0 < self.func().blocks.len() {
+                    writeln!(self.content, "  jmp .L.return.{}", self.func().name).unwrap();
                 }
-                self.gen_stmt(&f.body);
-                if let Some(e) = &f.update {
-                    self.gen_expr(e);
-                }
-                writeln!(self.content, "  jmp .L.begin.{}", count).unwrap();
-                writeln!(self.content, ".L.end.{}:", count).unwrap();
             }
-            k => unimplemented!("{:?}", k),
         }
     }
 
-    pub fn gen_addr(&mut self, local: LocalId) {
-        let l = self.func.local(local);
-        writeln!(self.content, "  lea -{}(%rbp), %rax", l.offset()).unwrap();
-    }
-
-    pub fn recurse_binary(&mut self, bin: &BinaryExpr) {
-        self.gen_expr(&bin.rhs);
-        self.push();
-        self.gen_expr(&bin.lhs);
-        self.pop("%rdi");
+    fn jump(&mut self, target: BlockId, next: BlockId) {
+        if target != next {
+            writeln!(self.content, "  jmp {}", self.label(target)).unwrap();
+        }
     }
 
-    pub fn gen_unary(&mut self, u: &UnaryExpr) {
-        match u.op {
-            //     Unary::Num(n) => writeln!(self.content, "  mov ${}, %rax", n).unwrap(),
-            //     Unary::Ident(local) => {
-            //         self.gen_addr(*local);
-            //         self.writeln("  mov (%rax), %rax");
-            //     }
-            //     Unary::Expr(stmt) => self.gen_expr_stmt(stmt),
-            UnaryOp::Neg => {
-                self.gen_expr(&u.lhs);
-                self.writeln("  neg %rax");
+    // leaves the result, if any, in %rax
+    fn gen_inst(&mut self, op: &Op) {
+        match op {
+            Op::SlotAddr(slot) => {
+                let offset = self.frame.slots[slot.0];
+                writeln!(self.content, "  lea -{}(%rbp), %rax", offset).unwrap();
             }
-            UnaryOp::NoOp => {
-                self.gen_expr(&u.lhs);
+            Op::SymbolAddr(name) => writeln!(self.content, "  lea {}(%rip), %rax", name).unwrap(),
+            Op::StackArgs => self.writeln("  lea 16(%rbp), %rax"),
+            Op::Unary(op, arg) => {
+                self.mov(*arg, "%rax");
+                match op {
+                    UnOp::Neg => self.writeln("  neg %rax"),
+                    UnOp::Not => self.writeln("  not %rax"),
+                }
             }
-            k => unimplemented!("{:?}", k),
-        }
-    }
-
-    pub fn gen_binary(&mut self, node: &BinaryExpr) {
-        match node.op {
-            BinOp::Add => {
-                self.recurse_binary(node);
-                self.writeln("  add %rdi, %rax")
+            Op::Binary(op, lhs, rhs) => {
+                self.mov(*lhs, "%rax");
+                let rhs = self.frame.value(*rhs);
+                self.gen_binary(*op, &rhs);
             }
-            BinOp::Sub => {
-                self.recurse_binary(node);
-                self.writeln("  sub %rdi, %rax")
+            Op::Cmp(cond, lhs, rhs) => {
+                self.compare(*lhs, *rhs);
+                writeln!(self.content, "  set{} %al", cond_suffix(*cond)).unwrap();
+                self.writeln("  movzb %al, %rax");
             }
-            BinOp::Mul => {
-                self.recurse_binary(node);
-                self.writeln("  imul %rdi, %rax")
+            Op::Extend { ty, signed, arg } => {
+                self.mov(*arg, "%rax");
+                self.extend(*ty, *signed);
             }
-            BinOp::Div => {
-                self.recurse_binary(node);
-                self.writeln("  cqo");
-                self.writeln("  idiv %rdi");
+            Op::Load { ty, signed, addr } => {
+                let addr = self.reg(*addr, "%rax");
+                self.load(*ty, *signed, addr);
             }
-            BinOp::GreaterEqCmp => {
-                self.recurse_binary(node);
-                self.writeln("  cmp %rdi, %rax");
-                self.writeln("  setge %al");
-                self.writeln("  movzb %al, %rax");
+            Op::Store { ty, addr, value } => {
+                let addr = self.reg(*addr, "%rdi");
+                self.mov(*value, "%rax");
+                writeln!(self.content, "  mov {}, ({})", ax(ty.size()), addr).unwrap();
             }
-            BinOp::LowerEqCmp => {
-                self.recurse_binary(node);
-                self.writeln("  cmp %rdi, %rax");
-                self.writeln("  setle %al");
-                self.writeln("  movzb %al, %rax");
+            Op::Copy { dst, src, size } => {
+                self.mov(*dst, "%rdi");
+                self.mov(*src, "%rax");
+                for i in 0..*size {
+                    writeln!(self.content, "  mov {}(%rax), %cl", i).unwrap();
+                    writeln!(self.content, "  mov %cl, {}(%rdi)", i).unwrap();
+                }
             }
-            BinOp::GreaterCmp => {
-                self.recurse_binary(node);
-                self.writeln("  cmp %rdi, %rax");
-                self.writeln("  setg %al");
-                self.writeln("  movzb %al, %rax");
+            Op::Zero { addr, size } => {
+                self.mov(*addr, "%rdi");
+                self.gen_zero_fill(*size);
             }
-            BinOp::LowerCmp => {
-                self.recurse_binary(node);
-                self.writeln("  cmp %rdi, %rax");
-                self.writeln("  setl %al");
-                self.writeln("  movzb %al, %rax");
+            Op::Call { callee, args } => self.gen_call(callee, args),
+            Op::Const(_) | Op::Param(_) | Op::Move(_) => {
+                unreachable!("`{}` is emitted by gen_function", op)
             }
-            BinOp::EqCmp => {
-                self.recurse_binary(node);
-                self.writeln("  cmp %rdi, %rax");
-                self.writeln("  sete %al");
-                self.writeln("  movzb %al, %rax");
+            Op::Phi(_) => unreachable!("a phi in code generation, out of SSA form"),
+        }
+    }
+
+    // sets the flags to `lhs` compared to `rhs`
+    fn compare(&mut self, lhs: Value, rhs: Value) {
+        let lhs = self.reg(lhs, "%rax");
+        let rhs = self.frame.value(rhs);
+        writeln!(self.content, "  cmp {}, {}", rhs, lhs).unwrap();
+    }
+
+    // `rhs` is a register or memory
+    fn gen_binary(&mut self, op: BinOp, rhs: &str) {
+        match op {
+            BinOp::Add => writeln!(self.content, "  add {}, %rax", rhs).unwrap(),
+            BinOp::Sub => writeln!(self.content, "  sub {}, %rax", rhs).unwrap(),
+            BinOp::Mul => writeln!(self.content, "  imul {}, %rax", rhs).unwrap(),
+            BinOp::SDiv | BinOp::SRem => {
+                self.mov_loc(rhs, "%rdi");
+                self.writeln("  cqo");
+                self.writeln("  idiv %rdi");
             }
-            BinOp::NeqCmp => {
-                self.recurse_binary(node);
-                self.writeln("  cmp %rdi, %rax");
-                self.writeln("  setne %al");
-                self.writeln("  movzb %al, %rax");
+            BinOp::UDiv | BinOp::URem => {
+                self.mov_loc(rhs, "%rdi");
+                self.writeln("  xor %edx, %edx");
+                self.writeln("  div %rdi");
             }
-            k => unimplemented!("{:?}", k),
-        }
-    }
-
-    // pub fn gen_expr_stmt(&mut self, e: &ExprStmt) {
-    //     match e {
-    //         ExprStmt::Unary(u) => match u.op {
-    //             UnaryOp::Neg => {
-    //                 self.gen_unary(&u.lhs);
-    //                 self.writeln("  neg %rax");
-    //             }
-    //             UnaryOp::NoOp => self.gen_unary(&u.lhs),
-    //             UnaryOp::Addr => {}
-    //             UnaryOp::Deref => {}
-    //         },
-    //         ExprStmt::Primary(u) => {},//self.gen_unary(u),
-    //         ExprStmt::Binary(b) => self.gen_binary(b),
-    //     }
-    // }
-
-    pub fn gen_expr(&mut self, stmt: &Expression) {
-        match stmt {
-            Expression::Unary(e) => self.gen_unary(e),
-            Expression::Assignment(a) => match a.lhs.as_ref() {
-                Expression::Identifier(local) => {
-                    self.gen_addr(*local);
-                    self.push();
-                    self.gen_expr(&a.rhs);
-                    self.pop("%rdi");
-                    self.writeln("  mov %rax, (%rdi)");
-                }
-                k => unimplemented!("{:?}", k),
-            },
-            Expression::NumberLiteral(n) => {
-                writeln!(self.content, "  mov ${}, %rax", n).unwrap();
+            BinOp::Shl | BinOp::LShr | BinOp::AShr => {
+                self.mov_loc(rhs, "%rcx");
+                let insn = match op {
+                    BinOp::Shl => "shl",
+                    BinOp::LShr => "shr",
+                    _ => "sar",
+                };
+                writeln!(self.content, "  {} %cl, %rax", insn).unwrap();
             }
-            Expression::Identifier(local) => {
-                self.gen_addr(*local);
-                self.writeln("  mov (%rax), %rax");
+            BinOp::And => writeln!(self.content, "  and {}, %rax", rhs).unwrap(),
+            BinOp::Or => writeln!(self.content, "  or {}, %rax", rhs).unwrap(),
+            BinOp::Xor => writeln!(self.content, "  xor {}, %rax", rhs).unwrap(),
+        }
+        // the remainder is left in %rdx
+        if let BinOp::SRem | BinOp::URem = op {
+            self.writeln("  mov %rdx, %rax");
+        }
+    }
+
+    // zeroes `size` bytes at the address in %rdi
+    fn gen_zero_fill(&mut self, size: usize) {
+        if size > ZERO_FILL_STORES_MAX {
+            writeln!(self.content, "  mov ${}, %rcx", size).unwrap();
+            self.writeln("  xor %eax, %eax");
+            self.writeln("  rep stosb");
+            return;
+        }
+        let mut done = 0;
+        while done < size {
+            let chunk = [8, 4, 2, 1]
+                .iter()
+                .copied()
+                .find(|chunk| done + chunk <= size)
+                .unwrap();
+            let suffix = size_suffix(chunk);
+            writeln!(self.content, "  mov{} $0, {}(%rdi)", suffix, done).unwrap();
+            done += chunk;
+        }
+    }
+
+    // loads the integer at the address in `addr` into %rax
+    fn load(&mut self, ty: Ty, signed: bool, addr: &str) {
+        let insn = match (ty, signed) {
+            (Ty::I8, false) => "movzbq",
+            (Ty::I16, false) => "movzwq",
+            (Ty::I32, false) => "movl",
+            (Ty::I8, true) => "movsbq",
+            (Ty::I16, true) => "movswq",
+            (Ty::I32, true) => "movslq",
+            (Ty::I64, _) => "mov",
+        };
+        let dst = if ty == Ty::I32 && !signed {
+            "%eax"
+        } else {
+            "%rax"
+        };
+        writeln!(self.content, "  {} ({}), {}", insn, addr, dst).unwrap();
+    }
+
+    // sign or zero extends the low bytes of %rax
+    fn extend(&mut self, ty: Ty, signed: bool) {
+        let insn = match (ty, signed) {
+            (Ty::I8, false) => "movzbq %al, %rax",
+            (Ty::I16, false) => "movzwq %ax, %rax",
+            (Ty::I32, false) => "mov %eax, %eax",
+            (Ty::I8, true) => "movsbq %al, %rax",
+            (Ty::I16, true) => "movswq %ax, %rax",
+            (Ty::I32, true) => "movslq %eax, %rax",
+            (Ty::I64, _) => return,
+        };
+        writeln!(self.content, "  {}", insn).unwrap();
+    }
+
+    // The first six arguments go in registers, the others on the stack in order.
+    fn gen_call(&mut self, callee: &Callee, args: &[Value]) {
+        let stack_args = args.len().saturating_sub(ARG_REGS_64.len());
+        // the stack must be 16-byte aligned at the call, as it is in the body
+        let padding = stack_args % 2 == 1;
+        if padding {
+            self.writeln("  sub $8, %rsp");
+        }
+        for arg in args.iter().skip(ARG_REGS_64.len()).rev() {
+            let home = self.frame.value(*arg);
+            writeln!(self.content, "  push {}", home).unwrap();
+        }
+        // the registers of the arguments may hold other arguments, or the callee
+        let mut moves: Vec<_> = args
+            .iter()
+            .zip(ARG_REGS_64.iter())
+            .map(|(arg, reg)| (reg.to_string(), self.frame.value(*arg)))
+            .collect();
+        // functions known by name are called directly, anything else through a pointer
+        let target = match callee {
+            Callee::Direct(name) => name.clone(),
+            Callee::Indirect(target) => {
+                moves.push(("%r11".to_string(), self.frame.value(*target)));
+                "*%r11".to_string()
             }
-            Expression::Binary(b) => self.gen_binary(b),
-            Expression::Pointer(p) => match p.op {
-                PointerOp::Ref => match p.arg.as_ref() {
-                    Expression::Identifier(local) => {
-                        self.gen_addr(*local);
-                        // self.writeln("  mov %rax, (%rdi)");
-                    }
-                    k => unimplemented!("{:?}", k),
-                },
-                PointerOp::Deref => {
-                    self.gen_expr(&p.arg);
-                    self.writeln("  mov (%rax), %rax");
-                }
-            },
-            k => unimplemented!("{:?}", k),
+        };
+        self.parallel_move(moves, "%rax");
+        // %al holds the number of vector registers used by a variadic call, there are none
+        self.writeln("  mov $0, %rax");
+        writeln!(self.content, "  call {}", target).unwrap();
+        let pushed = stack_args + padding as usize;
+        if pushed > 0 {
+            writeln!(self.content, "  add ${}, %rsp", pushed * 8).unwrap();
         }
     }
 
@@ -279,3 +587,64 @@ impl<'a> Assembly<'a> {
         self.content.push_str(s)
     }
 }
+
+// where the n-th argument is on entry to the function
+fn param(i: usize) -> String {
+    match ARG_REGS_64.get(i) {
+        Some(reg) => reg.to_string(),
+        // past the sixth, arguments are above the return address
+        None => format!("{}(%rbp)", 16 + (i - ARG_REGS_64.len()) * 8),
+    }
+}
+
+// an operand that is not a register
+fn is_memory(operand: &str) -> bool {
+    !operand.starts_with('%')
+}
+
+// the condition code of `setcc` and `jcc`
+// how many times each value is read
+fn use_counts(func: &Function) -> HashMap<Value, usize> {
+    let mut uses = HashMap::new();
+    for block in &func.blocks {
+        let operands = block.insts.iter().flat_map(|inst| inst.op.operands());
+        for v in operands.chain(block.term.operands()) {
+            *uses.entry(v).or_insert(0) += 1;
+        }
+    }
+    uses
+}
+
+fn cond_suffix(cond: Cond) -> &'static str {
+    match cond {
+        Cond::Eq => "e",
+        Cond::Ne => "ne",
+        Cond::Lt => "l",
+        Cond::Le => "le",
+        Cond::Gt => "g",
+        Cond::Ge => "ge",
+        Cond::ULt => "b",
+        Cond::ULe => "be",
+        Cond::UGt => "a",
+        Cond::UGe => "ae",
+    }
+}
+
+// %rax, sized to hold a value of `size` bytes
+fn ax(size: usize) -> &'static str {
+    match size {
+        1 => "%al",
+        2 => "%ax",
+        4 => "%eax",
+        _ => "%rax",
+    }
+}
+
+fn size_suffix(size: usize) -> &'static str {
+    match size {
+        1 => "b",
+        2 => "w",
+        4 => "l",
+        _ => "q",
+    }
+}
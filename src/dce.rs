@@ -0,0 +1,171 @@
+//! Dead code: the code that cannot run, and the instructions whose results nothing needs.
+//!
+//! The code after a `return`, `break`, `continue` or `goto` is in blocks nothing jumps to, and
+//! is warned about before the SSA construction drops it. Constant conditions are only folded
+//! afterwards, so the code under an `if (0)` is taken as meant. In SSA form, an instruction
+//! without side effects is dead when nothing reads its result, as in `x + 1;` or for a value
+//! stored to a local and never loaded again. A store to a slot still in memory is dead when
+//! the slot is stored to again before anything may read it, or is never read at all.
+
+use crate::cfg;
+use crate::ir::{Function, Op, Slot, Value};
+use crate::Diagnostic;
+use std::collections::{HashMap, HashSet};
+
+/// Warns about the code that cannot be reached, once for each part of the function nothing
+/// jumps into. The unreachable blocks must still be there.
+pub fn unreachable_code(func: &Function) -> Vec<Diagnostic> {
+    let count = func.blocks.len();
+    let mut reachable = vec![false; count];
+    for block in cfg::reverse_postorder(func) {
+        reachable[block.0] = true;
+    }
+    // the unreachable blocks that jump to one another are one part, with the first as root
+    let mut root: Vec<_> = (0..count).collect();
+    fn find(root: &mut [usize], mut b: usize) -> usize {
+        while root[b] != b {
+            root[b] = root[root[b]];
+            b = root[b];
+        }
+        b
+    }
+    for (b, block) in func.blocks.iter().enumerate() {
+        for succ in block.term.successors() {
+            if !reachable[b] && !reachable[succ.0] {
+                let (x, y) = (find(&mut root, b), find(&mut root, succ.0));
+                root[x.max(y)] = x.min(y);
+            }
+        }
+    }
+    // the first statement of each part with any code, as the blocks are in the order of the
+    // sources
+    let mut warned = HashSet::new();
+    let mut warnings = vec![];
+    for (b, block) in func.blocks.iter().enumerate() {
+        if reachable[b] || warned.contains(&find(&mut root, b)) {
+            continue;
+        }
+        if let Some(span) = block.insts.iter().find_map(|inst| inst.span.as_ref()) {
+            warned.insert(find(&mut root, b));
+            warnings.push(Diagnostic::warning(
+                "code will never be executed".to_string(),
+                Some(span),
+            ));
+        }
+    }
+    warnings
+}
+
+/// Removes the dead stores to slots, then the instructions whose results are not needed.
+pub fn eliminate(func: &mut Function) {
+    remove_overwritten_stores(func);
+    remove_unread_slots(func);
+    remove_unused(func);
+}
+
+// the slots whose addresses the values are
+fn slot_addrs(func: &Function) -> HashMap<Value, Slot> {
+    let mut addrs = HashMap::new();
+    for inst in func.blocks.iter().flat_map(|block| &block.insts) {
+        if let Op::SlotAddr(slot) = inst.op {
+            addrs.insert(inst.result.unwrap(), slot);
+        }
+    }
+    addrs
+}
+
+// Going backward through each block, a store to the start of a slot is dead when the slot is
+// stored to again, as widely, before anything may read it. Only the loads from another slot
+// cannot read it.
+fn remove_overwritten_stores(func: &mut Function) {
+    let addrs = slot_addrs(func);
+    for block in &mut func.blocks {
+        // the slots stored to later, with the size stored
+        let mut overwritten: HashMap<Slot, usize> = HashMap::new();
+        let mut dead = vec![false; block.insts.len()];
+        for (i, inst) in block.insts.iter().enumerate().rev() {
+            match inst.op {
+                Op::Store { ty, addr, .. } => {
+                    if let Some(slot) = addrs.get(&addr) {
+                        let size = overwritten.entry(*slot).or_insert(0);
+                        dead[i] = *size >= ty.size();
+                        *size = (*size).max(ty.size());
+                    }
+                }
+                Op::Load { addr, .. } => match addrs.get(&addr) {
+                    Some(slot) => {
+                        overwritten.remove(slot);
+                    }
+                    None => overwritten.clear(),
+                },
+                Op::Copy { .. } | Op::Zero { .. } | Op::Call { .. } => overwritten.clear(),
+                _ => {}
+            }
+        }
+        let mut dead = dead.into_iter();
+        block.insts.retain(|_| !dead.next().unwrap());
+    }
+}
+
+// A slot whose address is only ever stored to is never read: the stores to it are dead.
+fn remove_unread_slots(func: &mut Function) {
+    let addrs = slot_addrs(func);
+    let mut read: HashSet<Slot> = func.va_area.into_iter().collect();
+    for block in &func.blocks {
+        for inst in &block.insts {
+            let stored_to = match inst.op {
+                Op::Store { addr, .. } => Some(addr),
+                _ => None,
+            };
+            let operands = inst.op.operands().into_iter();
+            // in the ops of a store, the address comes first
+            for v in operands.skip(stored_to.is_some() as usize) {
+                read.extend(addrs.get(&v));
+            }
+        }
+        for v in block.term.operands() {
+            read.extend(addrs.get(&v));
+        }
+    }
+    for block in &mut func.blocks {
+        block.insts.retain(|inst| match inst.op {
+            Op::Store { addr, .. } => addrs.get(&addr).map_or(true, |slot| read.contains(slot)),
+            _ => true,
+        });
+    }
+}
+
+// Marks the operands of the instructions with side effects and of the terminators as needed,
+// then those of the instructions they are defined by, and so on. The others are removed.
+fn remove_unused(func: &mut Function) {
+    let has_effects = |op: &Op| {
+        matches!(
+            op,
+            Op::Store { .. } | Op::Copy { .. } | Op::Zero { .. } | Op::Call { .. }
+        )
+    };
+    let mut defs = HashMap::new();
+    let mut needed = HashSet::new();
+    let mut work = vec![];
+    for block in &func.blocks {
+        for inst in &block.insts {
+            if let Some(v) = inst.result {
+                defs.insert(v, &inst.op);
+            }
+            if has_effects(&inst.op) {
+                work.extend(inst.op.operands());
+            }
+        }
+        work.extend(block.term.operands());
+    }
+    while let Some(v) = work.pop() {
+        if needed.insert(v) {
+            work.extend(defs[&v].operands());
+        }
+    }
+    for block in &mut func.blocks {
+        block.insts.retain(|inst| {
+            has_effects(&inst.op) || inst.result.map_or(false, |v| needed.contains(&v))
+        });
+    }
+}
@@ -0,0 +1,314 @@
+//! Constant folding and algebraic simplification, in SSA form.
+//!
+//! An instruction whose operands are constants is evaluated as the processor would run it,
+//! which is what C says wherever it says anything: unsigned arithmetic wraps, as the lowering
+//! truncates it. What C leaves undefined is left to happen at run time: a division by zero,
+//! or of the most negative value by -1, and a shift by more than the width. A value already
+//! extended from as few bits is not extended again. A branch on a constant becomes a jump,
+//! and the blocks no longer reached are dropped.
+
+use crate::cfg;
+use crate::ir::{BinOp, Cond, Function, Inst, Op, Terminator, Ty, UnOp, Value};
+use crate::Diagnostic;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::mem;
+
+pub fn fold(func: &mut Function) {
+    while fold_once(func) {}
+    remove_unused_constants(func);
+}
+
+// what an instruction simplifies to
+enum Folded {
+    Const(i64),
+    // one of its operands
+    Value(Value),
+    // `x * 2^n`, as `x << n`
+    Shl(Value, i64),
+}
+
+// Goes through the blocks once, each before its successors but along back edges, so that
+// most operands are folded before they are read. Whether anything changed.
+fn fold_once(func: &mut Function) -> bool {
+    let mut changed = false;
+    let mut consts = HashMap::new();
+    // the results of the instructions folded to one of their operands
+    let mut replaced = HashMap::new();
+    // the values extended from fewer bits, and how
+    let mut extended = HashMap::new();
+    for block in cfg::reverse_postorder(func) {
+        let insts = mem::take(&mut func.blocks[block.0].insts);
+        let mut kept = Vec::with_capacity(insts.len());
+        for mut inst in insts {
+            inst.op
+                .operands_mut()
+                .into_iter()
+                .for_each(|v| resolve(&replaced, v));
+            match simplify(&inst.op, inst.result, &consts, &extended) {
+                Some(Folded::Const(n)) => {
+                    inst.op = Op::Const(n);
+                    changed = true;
+                }
+                Some(Folded::Value(v)) => {
+                    replaced.insert(inst.result.unwrap(), v);
+                    changed = true;
+                    continue;
+                }
+                Some(Folded::Shl(v, n)) => {
+                    let shift = Value(func.value_count);
+                    func.value_count += 1;
+                    kept.push(Inst {
+                        result: Some(shift),
+                        op: Op::Const(n),
+                        span: None,
+                    });
+                    consts.insert(shift, n);
+                    inst.op = Op::Binary(BinOp::Shl, v, shift);
+                    changed = true;
+                }
+                None => {}
+            }
+            match (&inst.op, inst.result) {
+                (Op::Const(n), Some(v)) => {
+                    consts.insert(v, *n);
+                }
+                (Op::Extend { ty, signed, .. }, Some(v))
+                | (Op::Load { ty, signed, .. }, Some(v))
+                    if *ty != Ty::I64 =>
+                {
+                    extended.insert(v, (*ty, *signed));
+                }
+                // 0 or 1
+                (Op::Cmp(..), Some(v)) => {
+                    extended.insert(v, (Ty::I8, false));
+                }
+                _ => {}
+            }
+            kept.push(inst);
+        }
+        // a phi folded to a constant goes after the phis left
+        kept.sort_by_key(|inst| !matches!(inst.op, Op::Phi(_)));
+        func.blocks[block.0].insts = kept;
+    }
+    // phis and terminators may read values replaced after them
+    for block in &mut func.blocks {
+        for inst in &mut block.insts {
+            inst.op
+                .operands_mut()
+                .into_iter()
+                .for_each(|v| resolve(&replaced, v));
+        }
+        let operands = block.term.operands_mut().into_iter();
+        operands.for_each(|v| resolve(&replaced, v));
+    }
+    fold_branches(func, &consts) || changed
+}
+
+// a value replaced by another, which may have been replaced in turn
+fn resolve(replaced: &HashMap<Value, Value>, v: &mut Value) {
+    while let Some(other) = replaced.get(v) {
+        *v = *other;
+    }
+}
+
+fn simplify(
+    op: &Op,
+    result: Option<Value>,
+    consts: &HashMap<Value, i64>,
+    extended: &HashMap<Value, (Ty, bool)>,
+) -> Option<Folded> {
+    let c = |v: Value| consts.get(&v).copied();
+    match *op {
+        Op::Unary(op, arg) => {
+            let arg = c(arg)?;
+            Some(Folded::Const(match op {
+                UnOp::Neg => arg.wrapping_neg(),
+                UnOp::Not => !arg,
+            }))
+        }
+        Op::Binary(op, lhs, rhs) => match (c(lhs), c(rhs)) {
+            (Some(x), Some(y)) => binary(op, x, y).map(Folded::Const),
+            (x, y) => identity(op, lhs, x, rhs, y),
+        },
+        Op::Cmp(cond, lhs, rhs) => Some(Folded::Const(compare(cond, c(lhs)?, c(rhs)?) as i64)),
+        Op::Extend { ty, signed, arg } => match (c(arg), extended.get(&arg)) {
+            (Some(x), _) => Some(Folded::Const(extend(ty, signed, x))),
+            (None, Some(from)) if fits(*from, (ty, signed)) => Some(Folded::Value(arg)),
+            _ => None,
+        },
+        Op::Phi(ref incoming) => {
+            // the same value on every edge, but those from the phi itself
+            let mut values = incoming
+                .iter()
+                .map(|(_, v)| *v)
+                .filter(|v| Some(*v) != result);
+            let first = values.next()?;
+            let rest: Vec<_> = values.collect();
+            if rest.iter().all(|v| *v == first) {
+                return Some(Folded::Value(first));
+            }
+            let n = c(first)?;
+            if rest.iter().all(|v| c(*v) == Some(n)) {
+                return Some(Folded::Const(n));
+            }
+            None
+        }
+        _ => None,
+    }
+}
+
+// `x op y`, unless C leaves it undefined
+fn binary(op: BinOp, x: i64, y: i64) -> Option<i64> {
+    let (ux, uy) = (x as u64, y as u64);
+    // the count of a shift is within the width
+    let count = || {
+        if (0..64).contains(&y) {
+            Some(y as u32)
+        } else {
+            None
+        }
+    };
+    Some(match op {
+        BinOp::Add => x.wrapping_add(y),
+        BinOp::Sub => x.wrapping_sub(y),
+        BinOp::Mul => x.wrapping_mul(y),
+        BinOp::SDiv => x.checked_div(y)?,
+        BinOp::SRem => x.checked_rem(y)?,
+        BinOp::UDiv => ux.checked_div(uy)? as i64,
+        BinOp::URem => ux.checked_rem(uy)? as i64,
+        BinOp::Shl => x << count()?,
+        BinOp::LShr => (ux >> count()?) as i64,
+        BinOp::AShr => x >> count()?,
+        BinOp::And => x & y,
+        BinOp::Or => x | y,
+        BinOp::Xor => x ^ y,
+    })
+}
+
+// `x + 0`, `x * 1` and the like, with `x` and `y` the operands that are constants
+fn identity(op: BinOp, lhs: Value, x: Option<i64>, rhs: Value, y: Option<i64>) -> Option<Folded> {
+    use BinOp::*;
+    let power_of_two = |n: i64| n > 1 && n & (n - 1) == 0;
+    match (op, x, y) {
+        (Add, Some(0), _) | (Or, Some(0), _) | (Xor, Some(0), _) => Some(Folded::Value(rhs)),
+        (op, _, Some(0)) if matches!(op, Add | Sub | Or | Xor | Shl | LShr | AShr) => {
+            Some(Folded::Value(lhs))
+        }
+        (Mul, Some(0), _) | (Mul, _, Some(0)) | (And, Some(0), _) | (And, _, Some(0)) => {
+            Some(Folded::Const(0))
+        }
+        (Mul, Some(1), _) | (And, Some(-1), _) => Some(Folded::Value(rhs)),
+        (op, _, Some(1)) if matches!(op, Mul | SDiv | UDiv) => Some(Folded::Value(lhs)),
+        (And, _, Some(-1)) => Some(Folded::Value(lhs)),
+        (Mul, Some(n), _) if power_of_two(n) => Some(Folded::Shl(rhs, n.trailing_zeros() as i64)),
+        (Mul, _, Some(n)) if power_of_two(n) => Some(Folded::Shl(lhs, n.trailing_zeros() as i64)),
+        _ => None,
+    }
+}
+
+fn compare(cond: Cond, x: i64, y: i64) -> bool {
+    let (ux, uy) = (x as u64, y as u64);
+    match cond {
+        Cond::Eq => x == y,
+        Cond::Ne => x != y,
+        Cond::Lt => x < y,
+        Cond::Le => x <= y,
+        Cond::Gt => x > y,
+        Cond::Ge => x >= y,
+        Cond::ULt => ux < uy,
+        Cond::ULe => ux <= uy,
+        Cond::UGt => ux > uy,
+        Cond::UGe => ux >= uy,
+    }
+}
+
+fn extend(ty: Ty, signed: bool, x: i64) -> i64 {
+    match (ty, signed) {
+        (Ty::I8, true) => x as i8 as i64,
+        (Ty::I8, false) => x as u8 as i64,
+        (Ty::I16, true) => x as i16 as i64,
+        (Ty::I16, false) => x as u16 as i64,
+        (Ty::I32, true) => x as i32 as i64,
+        (Ty::I32, false) => x as u32 as i64,
+        (Ty::I64, _) => x,
+    }
+}
+
+// whether a value extended from `from` is left unchanged by extending it as `to`
+fn fits(from: (Ty, bool), to: (Ty, bool)) -> bool {
+    let ((from, from_signed), (to, to_signed)) = (from, to);
+    match from.size().cmp(&to.size()) {
+        Ordering::Less => to_signed || !from_signed,
+        Ordering::Equal => to_signed == from_signed,
+        Ordering::Greater => false,
+    }
+}
+
+// A branch on a constant jumps to the side it takes, and the other no longer gets a value
+// from it for its phis. Whether any branch changed.
+fn fold_branches(func: &mut Function, consts: &HashMap<Value, i64>) -> bool {
+    let mut changed = false;
+    for b in 0..func.blocks.len() {
+        let (taken, not_taken) = match func.blocks[b].term {
+            Terminator::Branch { cond, then, else_ } => match consts.get(&cond) {
+                Some(0) => (else_, then),
+                Some(_) => (then, else_),
+                None => continue,
+            },
+            _ => continue,
+        };
+        func.blocks[b].term = Terminator::Jump(taken);
+        for inst in &mut func.blocks[not_taken.0].insts {
+            if let Op::Phi(incoming) = &mut inst.op {
+                let edge = incoming.iter().position(|(pred, _)| pred.0 == b);
+                incoming.remove(edge.expect("a phi without a value for an edge"));
+            }
+        }
+        changed = true;
+    }
+    if changed {
+        cfg::remove_unreachable(func);
+    }
+    changed
+}
+
+// the constants nothing reads any more, once what read them is folded
+fn remove_unused_constants(func: &mut Function) {
+    let mut used = HashSet::new();
+    for block in &func.blocks {
+        for inst in &block.insts {
+            used.extend(inst.op.operands());
+        }
+        used.extend(block.term.operands());
+    }
+    for block in &mut func.blocks {
+        block.insts.retain(|inst| {
+            !matches!(inst.op, Op::Const(_)) || used.contains(&inst.result.unwrap())
+        });
+    }
+}
+
+/// Warns about the divisions by a constant zero, which are left to fail at run time. The more
+/// is folded, the more of those are seen, but in the code inlined from elsewhere.
+pub fn divisions_by_zero(func: &Function) -> Vec<Diagnostic> {
+    let zeros: HashSet<_> = func
+        .blocks
+        .iter()
+        .flat_map(|block| &block.insts)
+        .filter(|inst| inst.op == Op::Const(0))
+        .map(|inst| inst.result.unwrap())
+        .collect();
+    func.blocks
+        .iter()
+        .flat_map(|block| &block.insts)
+        .filter(|inst| match inst.op {
+            Op::Binary(op, _, rhs) if inst.span.is_some() => {
+                matches!(op, BinOp::SDiv | BinOp::UDiv | BinOp::SRem | BinOp::URem)
+                    && zeros.contains(&rhs)
+            }
+            _ => false,
+        })
+        .map(|inst| Diagnostic::warning("division by zero".to_string(), inst.span.as_ref()))
+        .collect()
+}
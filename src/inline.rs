@@ -0,0 +1,345 @@
+//! Inlining: a call to a function defined in the same unit becomes a copy of its body, where
+//! the function is small, or declared `inline` and not too large.
+//!
+//! It runs on the IR as lowered, before the passes over each function, which then optimise
+//! each copy with the arguments it gets. The callees are inlined into first, so that a copy
+//! has their calls inlined already. A recursive function is never inlined, nor a variadic one,
+//! whose `va_arg` reads the registers it is called with, nor one whose address is taken.
+//! `__attribute__((always_inline))` inlines a function whatever its size, and `noinline`
+//! never does. A static function nothing refers to any more is left out.
+
+use crate::ast::Inlining;
+use crate::ir::{Block, BlockId, Callee, Function, Inst, Op, Program, Slot, SlotData};
+use crate::ir::{Terminator, Ty, Value};
+use std::collections::{HashMap, HashSet};
+use std::mem;
+
+// the most instructions of a function inlined for being small, and of one declared `inline`
+const SMALL_MAX: usize = 24;
+const HINTED_MAX: usize = 96;
+// how large a function may grow by inlining into it, but for the `always_inline` callees
+const GROWTH_MAX: usize = 2000;
+
+pub fn inline(program: &mut Program) {
+    let index: HashMap<String, usize> = program
+        .functions
+        .iter()
+        .enumerate()
+        .map(|(i, func)| (func.name.clone(), i))
+        .collect();
+    let calls: Vec<Vec<usize>> = program
+        .functions
+        .iter()
+        .map(|func| direct_calls(func, &index))
+        .collect();
+    let never = never_inlined(program, &calls);
+    for f in bottom_up(&calls) {
+        // the blocks move with each call inlined, so the next one is looked for again
+        while let Some((block, i, callee)) = next_call(program, f, &index, &never) {
+            let callee = program.functions[callee].clone();
+            inline_call(&mut program.functions[f], block, i, &callee);
+        }
+    }
+    remove_unreferenced(program);
+}
+
+// the functions a function calls by name, of those defined
+fn direct_calls(func: &Function, index: &HashMap<String, usize>) -> Vec<usize> {
+    let mut calls = vec![];
+    for inst in func.blocks.iter().flat_map(|block| &block.insts) {
+        if let Op::Call {
+            callee: Callee::Direct(name),
+            ..
+        } = &inst.op
+        {
+            calls.extend(index.get(name));
+        }
+    }
+    calls
+}
+
+// The functions never inlined whatever their size: those that may call themselves, the
+// variadic ones, and those whose address is taken, in code or in the data.
+fn never_inlined(program: &Program, calls: &[Vec<usize>]) -> HashSet<usize> {
+    let mut never = HashSet::new();
+    for (f, func) in program.functions.iter().enumerate() {
+        if func.va_area.is_some() || func.inlining == Inlining::Never || calls_back(calls, f) {
+            never.insert(f);
+        }
+    }
+    let taken = referenced_by_address(program);
+    for (f, func) in program.functions.iter().enumerate() {
+        if taken.contains(func.name.as_str()) {
+            never.insert(f);
+        }
+    }
+    never
+}
+
+// whether a function calls itself, through others or not
+fn calls_back(calls: &[Vec<usize>], f: usize) -> bool {
+    let mut seen = HashSet::new();
+    let mut work = calls[f].clone();
+    while let Some(g) = work.pop() {
+        if g == f {
+            return true;
+        }
+        if seen.insert(g) {
+            work.extend(&calls[g]);
+        }
+    }
+    false
+}
+
+fn referenced_by_address(program: &Program) -> HashSet<&str> {
+    let mut names = HashSet::new();
+    for inst in program
+        .functions
+        .iter()
+        .flat_map(|func| &func.blocks)
+        .flat_map(|block| &block.insts)
+    {
+        if let Op::SymbolAddr(name) = &inst.op {
+            names.insert(name.as_str());
+        }
+    }
+    let relocs = program
+        .globals
+        .iter()
+        .flat_map(|global| &global.init)
+        .flat_map(|init| &init.relocs);
+    names.extend(relocs.map(|reloc| reloc.label.as_str()));
+    names
+}
+
+// the functions, each after those it calls, but along the cycles of calls
+fn bottom_up(calls: &[Vec<usize>]) -> Vec<usize> {
+    fn visit(f: usize, calls: &[Vec<usize>], seen: &mut [bool], order: &mut Vec<usize>) {
+        seen[f] = true;
+        for &g in &calls[f] {
+            if !seen[g] {
+                visit(g, calls, seen, order);
+            }
+        }
+        order.push(f);
+    }
+    let mut seen = vec![false; calls.len()];
+    let mut order = vec![];
+    for f in 0..calls.len() {
+        if !seen[f] {
+            visit(f, calls, &mut seen, &mut order);
+        }
+    }
+    order
+}
+
+// the first call in a function to inline, by its block and place in it, and the callee
+fn next_call(
+    program: &Program,
+    f: usize,
+    index: &HashMap<String, usize>,
+    never: &HashSet<usize>,
+) -> Option<(usize, usize, usize)> {
+    let func = &program.functions[f];
+    let grown = size(func);
+    for (b, block) in func.blocks.iter().enumerate() {
+        for (i, inst) in block.insts.iter().enumerate() {
+            let (name, args) = match &inst.op {
+                Op::Call {
+                    callee: Callee::Direct(name),
+                    args,
+                } => (name, args),
+                _ => continue,
+            };
+            let callee = match index.get(name) {
+                Some(&g) if g != f && !never.contains(&g) => g,
+                _ => continue,
+            };
+            let callee_func = &program.functions[callee];
+            let wanted = match callee_func.inlining {
+                Inlining::Always => true,
+                _ if grown > GROWTH_MAX => false,
+                Inlining::Hint => size(callee_func) <= HINTED_MAX,
+                _ => size(callee_func) <= SMALL_MAX,
+            };
+            // a call with other arguments than the parameters is left as it is
+            if wanted && args.len() == callee_func.params {
+                return Some((b, i, callee));
+            }
+        }
+    }
+    None
+}
+
+fn size(func: &Function) -> usize {
+    func.blocks.iter().map(|block| block.insts.len()).sum()
+}
+
+// Replaces the `i`-th instruction of block `b`, a call to `callee`, with a copy of its body.
+// The call's block jumps to the copy of the callee's entry block, and the returns to a new
+// block with the rest of the call's block, which gets the result through a new slot. The
+// parameters are the arguments.
+fn inline_call(func: &mut Function, b: usize, i: usize, callee: &Function) {
+    let (value_base, block_base, slot_base) =
+        (func.value_count, func.blocks.len(), func.slots.len());
+    func.value_count += callee.value_count;
+    func.slots.extend(callee.slots.iter().cloned());
+    let result_slot = Slot(func.slots.len());
+    func.slots.push(SlotData {
+        name: format!("{}.result", callee.name),
+        size: 8,
+        align: 8,
+    });
+    let rest = BlockId(block_base + callee.blocks.len());
+
+    let mut insts = func.blocks[b].insts.split_off(i);
+    let call = insts.remove(0);
+    let args = match call.op {
+        Op::Call { args, .. } => args,
+        _ => unreachable!(),
+    };
+    let mut values = HashMap::new();
+    for inst in &callee.blocks[0].insts {
+        if let Op::Param(n) = inst.op {
+            values.insert(inst.result.unwrap(), args[n]);
+        }
+    }
+    let value = |v: Value| values.get(&v).copied().unwrap_or(Value(value_base + v.0));
+    let block = |b: &mut BlockId| b.0 += block_base;
+
+    let mut new_value = || {
+        func.value_count += 1;
+        Value(func.value_count - 1)
+    };
+    let mut copies = vec![];
+    for callee_block in &callee.blocks {
+        let mut block_insts = vec![];
+        for inst in &callee_block.insts {
+            if let Op::Param(_) = inst.op {
+                continue;
+            }
+            let mut op = inst.op.clone();
+            op.operands_mut().into_iter().for_each(|v| *v = value(*v));
+            match &mut op {
+                Op::SlotAddr(slot) => slot.0 += slot_base,
+                Op::Phi(incoming) => incoming.iter_mut().for_each(|(pred, _)| block(pred)),
+                _ => {}
+            }
+            block_insts.push(Inst {
+                result: inst.result.map(|v| Value(value_base + v.0)),
+                op,
+                // the warnings are about the code where it is written
+                span: None,
+            });
+        }
+        let term = match &callee_block.term {
+            Terminator::Return(returned) => {
+                if let (Some(returned), Some(_)) = (returned, call.result) {
+                    let addr = new_value();
+                    block_insts.push(Inst {
+                        result: Some(addr),
+                        op: Op::SlotAddr(result_slot),
+                        span: None,
+                    });
+                    block_insts.push(Inst {
+                        result: None,
+                        op: Op::Store {
+                            ty: Ty::I64,
+                            addr,
+                            value: value(*returned),
+                        },
+                        span: None,
+                    });
+                }
+                Terminator::Jump(rest)
+            }
+            term => {
+                let mut term = term.clone();
+                term.operands_mut().into_iter().for_each(|v| *v = value(*v));
+                term.successors_mut().into_iter().for_each(block);
+                term
+            }
+        };
+        copies.push(Block {
+            insts: block_insts,
+            term,
+        });
+    }
+
+    let mut rest_insts = vec![];
+    if let Some(result) = call.result {
+        let addr = new_value();
+        rest_insts.push(Inst {
+            result: Some(addr),
+            op: Op::SlotAddr(result_slot),
+            span: None,
+        });
+        rest_insts.push(Inst {
+            result: Some(result),
+            op: Op::Load {
+                ty: Ty::I64,
+                signed: true,
+                addr,
+            },
+            span: call.span,
+        });
+    }
+    rest_insts.extend(insts);
+    let term = mem::replace(
+        &mut func.blocks[b].term,
+        Terminator::Jump(BlockId(block_base)),
+    );
+    // the successors are now reached from the rest of the block
+    for succ in term.successors() {
+        for inst in &mut func.blocks[succ.0].insts {
+            if let Op::Phi(incoming) = &mut inst.op {
+                for (pred, _) in incoming.iter_mut().filter(|(pred, _)| pred.0 == b) {
+                    *pred = rest;
+                }
+            }
+        }
+    }
+    func.blocks.extend(copies);
+    func.blocks.push(Block {
+        insts: rest_insts,
+        term,
+    });
+    // the copy goes right after the call
+    let count = func.blocks.len();
+    let order: Vec<_> = (0..=b)
+        .chain(block_base..count)
+        .chain(b + 1..block_base)
+        .map(BlockId)
+        .collect();
+    func.reorder(&order);
+}
+
+// Leaves out the static functions nothing calls or takes the address of, until there are none.
+fn remove_unreferenced(program: &mut Program) {
+    loop {
+        let mut referenced: HashSet<String> = referenced_by_address(program)
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        for func in &program.functions {
+            for inst in func.blocks.iter().flat_map(|block| &block.insts) {
+                if let Op::Call {
+                    callee: Callee::Direct(name),
+                    ..
+                } = &inst.op
+                {
+                    if *name != func.name {
+                        referenced.insert(name.clone());
+                    }
+                }
+            }
+        }
+        let count = program.functions.len();
+        program
+            .functions
+            .retain(|func| !func.is_static || referenced.contains(&func.name));
+        if program.functions.len() == count {
+            return;
+        }
+    }
+}
@@ -0,0 +1,597 @@
+//! A three-address representation of function bodies, between the AST and assembly.
+//!
+//! A function is a list of basic blocks, each a run of instructions ended by a jump, a
+//! branch or a return. Instructions define values: each one is defined exactly once, by the
+//! instruction that computes it, but for the moves that replace phis out of SSA form. Values
+//! are 64 bits wide; narrower integers only exist in memory, and are sign or zero extended as
+//! they are loaded. Locals live in stack slots, read and written through their address, until
+//! `ssa` promotes those whose address is not taken to values.
+
+use crate::ast::{Global, Inlining};
+use crate::Span;
+use std::collections::HashSet;
+use std::fmt;
+
+#[derive(Clone, Debug, Default)]
+pub struct Program {
+    pub functions: Vec<Function>,
+    pub globals: Vec<Global>,
+    pub strings: Vec<Vec<u8>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Function {
+    pub name: String,
+    // internal linkage: the symbol is not exported
+    pub is_static: bool,
+    // what the sources ask of inlining the calls to it
+    pub inlining: Inlining,
+    // the number of parameters, read by `Op::Param`
+    pub params: usize,
+    pub slots: Vec<SlotData>,
+    // the entry block comes first
+    pub blocks: Vec<Block>,
+    // values are numbered from 0
+    pub value_count: usize,
+    // where the argument registers of a variadic function are saved for `va_arg`
+    pub va_area: Option<Slot>,
+}
+
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Value(pub usize);
+
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Slot(pub usize);
+
+#[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct BlockId(pub usize);
+
+/// Stack memory of a function: a local, or a temporary of the lowering.
+#[derive(Clone, Debug)]
+pub struct SlotData {
+    // for dumps only
+    pub name: String,
+    pub size: usize,
+    pub align: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct Block {
+    pub insts: Vec<Inst>,
+    pub term: Terminator,
+}
+
+#[derive(Clone, Debug)]
+pub struct Inst {
+    // stores, copies and zero fills have none
+    pub result: Option<Value>,
+    pub op: Op,
+    // where in the sources, for the instructions a warning may be about
+    pub span: Option<Span>,
+}
+
+/// The size of an integer in memory.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Ty {
+    I8,
+    I16,
+    I32,
+    I64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Op {
+    Const(i64),
+    // the n-th argument of the function
+    Param(usize),
+    // the address of a stack slot
+    SlotAddr(Slot),
+    // the address of a function, a global or a string literal
+    SymbolAddr(String),
+    // the address of the arguments passed on the stack by the caller
+    StackArgs,
+    Unary(UnOp, Value),
+    Binary(BinOp, Value, Value),
+    // 1 if the comparison holds, else 0
+    Cmp(Cond, Value, Value),
+    // sign or zero extends the low bits of a value, as for a `ty` in memory
+    Extend { ty: Ty, signed: bool, arg: Value },
+    Load { ty: Ty, signed: bool, addr: Value },
+    Store { ty: Ty, addr: Value, value: Value },
+    // copies `size` bytes, as for a struct assignment
+    Copy { dst: Value, src: Value, size: usize },
+    Zero { addr: Value, size: usize },
+    Call { callee: Callee, args: Vec<Value> },
+    // the value coming from the predecessor the block was entered from, in SSA form
+    Phi(Vec<(BlockId, Value)>),
+    // out of SSA form, a phi is a value defined by a move in each predecessor
+    Move(Value),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Callee {
+    Direct(String),
+    // through a pointer
+    Indirect(Value),
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum UnOp {
+    Neg,
+    // bitwise
+    Not,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    SDiv,
+    UDiv,
+    SRem,
+    URem,
+    Shl,
+    // logical and arithmetic
+    LShr,
+    AShr,
+    And,
+    Or,
+    Xor,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Cond {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    // unsigned
+    ULt,
+    ULe,
+    UGt,
+    UGe,
+}
+
+impl Cond {
+    /// The condition that holds when this one does not.
+    pub fn inverse(self) -> Cond {
+        match self {
+            Cond::Eq => Cond::Ne,
+            Cond::Ne => Cond::Eq,
+            Cond::Lt => Cond::Ge,
+            Cond::Le => Cond::Gt,
+            Cond::Gt => Cond::Le,
+            Cond::Ge => Cond::Lt,
+            Cond::ULt => Cond::UGe,
+            Cond::ULe => Cond::UGt,
+            Cond::UGt => Cond::ULe,
+            Cond::UGe => Cond::ULt,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Terminator {
+    Jump(BlockId),
+    // to `then` if `cond` is not 0
+    Branch {
+        cond: Value,
+        then: BlockId,
+        else_: BlockId,
+    },
+    Return(Option<Value>),
+}
+
+impl Op {
+    /// The values read by the instruction.
+    pub fn operands(&self) -> Vec<Value> {
+        match self {
+            Op::Const(_) | Op::Param(_) | Op::SlotAddr(_) | Op::SymbolAddr(_) | Op::StackArgs => {
+                vec![]
+            }
+            Op::Unary(_, arg)
+            | Op::Extend { arg, .. }
+            | Op::Load { addr: arg, .. }
+            | Op::Move(arg) => vec![*arg],
+            Op::Binary(_, lhs, rhs) | Op::Cmp(_, lhs, rhs) => vec![*lhs, *rhs],
+            Op::Store { addr, value, .. } => vec![*addr, *value],
+            Op::Copy { dst, src, .. } => vec![*dst, *src],
+            Op::Zero { addr, .. } => vec![*addr],
+            Op::Call { callee, args } => {
+                let mut values = args.clone();
+                if let Callee::Indirect(target) = callee {
+                    values.push(*target);
+                }
+                values
+            }
+            Op::Phi(incoming) => incoming.iter().map(|(_, value)| *value).collect(),
+        }
+    }
+
+    /// The values read by the instruction, to be replaced.
+    pub fn operands_mut(&mut self) -> Vec<&mut Value> {
+        match self {
+            Op::Const(_) | Op::Param(_) | Op::SlotAddr(_) | Op::SymbolAddr(_) | Op::StackArgs => {
+                vec![]
+            }
+            Op::Unary(_, arg)
+            | Op::Extend { arg, .. }
+            | Op::Load { addr: arg, .. }
+            | Op::Move(arg) => vec![arg],
+            Op::Binary(_, lhs, rhs) | Op::Cmp(_, lhs, rhs) => vec![lhs, rhs],
+            Op::Store { addr, value, .. } => vec![addr, value],
+            Op::Copy { dst, src, .. } => vec![dst, src],
+            Op::Zero { addr, .. } => vec![addr],
+            Op::Call { callee, args } => {
+                let mut values: Vec<_> = args.iter_mut().collect();
+                if let Callee::Indirect(target) = callee {
+                    values.push(target);
+                }
+                values
+            }
+            Op::Phi(incoming) => incoming.iter_mut().map(|(_, value)| value).collect(),
+        }
+    }
+
+    // whether the instruction defines a value
+    fn has_result(&self) -> bool {
+        !matches!(self, Op::Store { .. } | Op::Copy { .. } | Op::Zero { .. })
+    }
+}
+
+impl Terminator {
+    pub fn successors(&self) -> Vec<BlockId> {
+        match self {
+            Terminator::Jump(target) => vec![*target],
+            Terminator::Branch { then, else_, .. } => vec![*then, *else_],
+            Terminator::Return(_) => vec![],
+        }
+    }
+
+    pub fn operands(&self) -> Vec<Value> {
+        match self {
+            Terminator::Branch { cond, .. } => vec![*cond],
+            Terminator::Return(Some(value)) => vec![*value],
+            _ => vec![],
+        }
+    }
+
+    pub fn operands_mut(&mut self) -> Vec<&mut Value> {
+        match self {
+            Terminator::Branch { cond, .. } => vec![cond],
+            Terminator::Return(Some(value)) => vec![value],
+            _ => vec![],
+        }
+    }
+
+    // the successors, in the order of `successors`, to be replaced
+    pub fn successors_mut(&mut self) -> Vec<&mut BlockId> {
+        match self {
+            Terminator::Jump(target) => vec![target],
+            Terminator::Branch { then, else_, .. } => vec![then, else_],
+            Terminator::Return(_) => vec![],
+        }
+    }
+}
+
+impl Ty {
+    pub fn size(self) -> usize {
+        match self {
+            Ty::I8 => 1,
+            Ty::I16 => 2,
+            Ty::I32 => 4,
+            Ty::I64 => 8,
+        }
+    }
+}
+
+impl Function {
+    /// The predecessors of each block, once for each edge: a branch to the same block either
+    /// way counts twice.
+    pub fn predecessors(&self) -> Vec<Vec<BlockId>> {
+        let mut preds = vec![vec![]; self.blocks.len()];
+        for (b, block) in self.blocks.iter().enumerate() {
+            for target in block.term.successors() {
+                preds[target.0].push(BlockId(b));
+            }
+        }
+        preds
+    }
+
+    /// Keeps the blocks in `order` only, in that order, and numbers them accordingly. The
+    /// blocks left out must not be jumped to by the others.
+    pub fn reorder(&mut self, order: &[BlockId]) {
+        let mut number = vec![None; self.blocks.len()];
+        for (i, block) in order.iter().enumerate() {
+            number[block.0] = Some(BlockId(i));
+        }
+        let number = |block: &mut BlockId| {
+            *block = number[block.0].expect("a jump to a block left out");
+        };
+        let mut blocks: Vec<_> = self.blocks.drain(..).map(Some).collect();
+        for id in order {
+            let mut block = blocks[id.0].take().expect("a block kept twice");
+            block.term.successors_mut().into_iter().for_each(number);
+            for inst in &mut block.insts {
+                if let Op::Phi(incoming) = &mut inst.op {
+                    incoming.iter_mut().for_each(|(pred, _)| number(pred));
+                }
+            }
+            self.blocks.push(block);
+        }
+    }
+
+    /// Checks the invariants the passes and the emitter rely on.
+    pub fn verify(&self) -> Result<(), String> {
+        let err = |block: usize, msg: String| Err(format!("{}: bb{}: {}", self.name, block, msg));
+        let mut defined = HashSet::new();
+        // values defined by moves only, which may be defined more than once
+        let mut moved = HashSet::new();
+        for (b, block) in self.blocks.iter().enumerate() {
+            for inst in &block.insts {
+                let is_move = matches!(inst.op, Op::Move(_));
+                match (inst.result, inst.op.has_result()) {
+                    (Some(v), true) if v.0 >= self.value_count => {
+                        return err(b, format!("{} is out of range", v));
+                    }
+                    (Some(v), true) if is_move && defined.contains(&v) && !moved.contains(&v) => {
+                        return err(b, format!("{} is defined twice", v));
+                    }
+                    (Some(v), true) if is_move => {
+                        defined.insert(v);
+                        moved.insert(v);
+                    }
+                    (Some(v), true) if !defined.insert(v) => {
+                        return err(b, format!("{} is defined twice", v));
+                    }
+                    (Some(_), true) | (None, false) => {}
+                    (_, true) => return err(b, format!("`{}` needs a result", inst.op)),
+                    (_, false) => return err(b, format!("`{}` has no result", inst.op)),
+                }
+            }
+        }
+        let preds = self.predecessors();
+        let mut entry_params = true;
+        for (b, block) in self.blocks.iter().enumerate() {
+            let mut leading_phis = true;
+            for inst in &block.insts {
+                if let Op::Phi(incoming) = &inst.op {
+                    // one incoming value for each edge into the block
+                    let mut from: Vec<_> = incoming.iter().map(|(pred, _)| *pred).collect();
+                    let mut expected = preds[b].clone();
+                    from.sort();
+                    expected.sort();
+                    if !leading_phis || from != expected {
+                        return err(b, format!("misplaced `{}`", inst.op));
+                    }
+                } else {
+                    leading_phis = false;
+                }
+                for v in inst.op.operands() {
+                    if !defined.contains(&v) {
+                        return err(b, format!("{} is used but never defined", v));
+                    }
+                }
+                match &inst.op {
+                    // so that the emitter reads the argument registers before anything else
+                    Op::Param(i) if b != 0 || !entry_params || *i >= self.params => {
+                        return err(b, format!("misplaced `{}`", inst.op));
+                    }
+                    Op::Param(_) => {}
+                    Op::SlotAddr(slot) if slot.0 >= self.slots.len() => {
+                        return err(b, format!("{} is out of range", slot));
+                    }
+                    _ => entry_params = false,
+                }
+            }
+            for v in block.term.operands() {
+                if !defined.contains(&v) {
+                    return err(b, format!("{} is used but never defined", v));
+                }
+            }
+            for target in block.term.successors() {
+                if target.0 >= self.blocks.len() {
+                    return err(b, format!("jump to {}, which does not exist", target));
+                }
+                // the entry block runs once, on entry
+                if target.0 == 0 {
+                    return err(b, "jump to the entry block".to_string());
+                }
+            }
+        }
+        match self.va_area {
+            Some(slot) if slot.0 >= self.slots.len() => Err(format!(
+                "{}: the va_area {} is out of range",
+                self.name, slot
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, func) in self.functions.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", func)?;
+        }
+        Ok(())
+    }
+}
+
+// function add(2) {
+//   $0 a: 4, align 4
+// bb0:
+//   %0 = param 0
+//   ...
+// }
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let linkage = if self.is_static { "static " } else { "" };
+        writeln!(f, "{}function {}({}) {{", linkage, self.name, self.params)?;
+        for (i, slot) in self.slots.iter().enumerate() {
+            let va_area = if self.va_area == Some(Slot(i)) {
+                ", va_area"
+            } else {
+                ""
+            };
+            writeln!(
+                f,
+                "  {} {}: {}, align {}{}",
+                Slot(i),
+                slot.name,
+                slot.size,
+                slot.align,
+                va_area
+            )?;
+        }
+        for (i, block) in self.blocks.iter().enumerate() {
+            writeln!(f, "{}:", BlockId(i))?;
+            for inst in &block.insts {
+                match inst.result {
+                    Some(v) => writeln!(f, "  {} = {}", v, inst.op)?,
+                    None => writeln!(f, "  {}", inst.op)?,
+                }
+            }
+            writeln!(f, "  {}", block.term)?;
+        }
+        writeln!(f, "}}")
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "%{}", self.0)
+    }
+}
+
+impl fmt::Display for Slot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "${}", self.0)
+    }
+}
+
+impl fmt::Display for BlockId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bb{}", self.0)
+    }
+}
+
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "i{}", self.size() * 8)
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = |signed: bool| if signed { "s" } else { "z" };
+        match self {
+            Op::Const(n) => write!(f, "const {}", n),
+            Op::Param(i) => write!(f, "param {}", i),
+            Op::SlotAddr(slot) => write!(f, "slot {}", slot),
+            Op::SymbolAddr(name) => write!(f, "symbol @{}", name),
+            Op::StackArgs => write!(f, "stackargs"),
+            Op::Unary(op, arg) => write!(f, "{} {}", op, arg),
+            Op::Binary(op, lhs, rhs) => write!(f, "{} {}, {}", op, lhs, rhs),
+            Op::Cmp(cond, lhs, rhs) => write!(f, "{} {}, {}", cond, lhs, rhs),
+            Op::Extend { ty, signed, arg } => write!(f, "{}ext.{} {}", sign(*signed), ty, arg),
+            Op::Load { ty, signed, addr } if *ty == Ty::I64 || !signed => {
+                write!(f, "load.{} {}", ty, addr)
+            }
+            Op::Load { ty, addr, .. } => write!(f, "sload.{} {}", ty, addr),
+            Op::Store { ty, addr, value } => write!(f, "store.{} {}, {}", ty, addr, value),
+            Op::Copy { dst, src, size } => write!(f, "copy {}, {}, {}", dst, src, size),
+            Op::Zero { addr, size } => write!(f, "zero {}, {}", addr, size),
+            Op::Call { callee, args } => {
+                match callee {
+                    Callee::Direct(name) => write!(f, "call @{}(", name)?,
+                    Callee::Indirect(target) => write!(f, "call {}(", target)?,
+                }
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                write!(f, ")")
+            }
+            Op::Phi(incoming) => {
+                write!(f, "phi")?;
+                for (i, (pred, value)) in incoming.iter().enumerate() {
+                    let sep = if i > 0 { "," } else { "" };
+                    write!(f, "{} [{}, {}]", sep, value, pred)?;
+                }
+                Ok(())
+            }
+            Op::Move(arg) => write!(f, "move {}", arg),
+        }
+    }
+}
+
+impl fmt::Display for UnOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            UnOp::Neg => "neg",
+            UnOp::Not => "not",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for BinOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            BinOp::Add => "add",
+            BinOp::Sub => "sub",
+            BinOp::Mul => "mul",
+            BinOp::SDiv => "sdiv",
+            BinOp::UDiv => "udiv",
+            BinOp::SRem => "srem",
+            BinOp::URem => "urem",
+            BinOp::Shl => "shl",
+            BinOp::LShr => "lshr",
+            BinOp::AShr => "ashr",
+            BinOp::And => "and",
+            BinOp::Or => "or",
+            BinOp::Xor => "xor",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for Cond {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Cond::Eq => "eq",
+            Cond::Ne => "ne",
+            Cond::Lt => "lt",
+            Cond::Le => "le",
+            Cond::Gt => "gt",
+            Cond::Ge => "ge",
+            Cond::ULt => "ult",
+            Cond::ULe => "ule",
+            Cond::UGt => "ugt",
+            Cond::UGe => "uge",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl fmt::Display for Terminator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Terminator::Jump(target) => write!(f, "jmp {}", target),
+            Terminator::Branch { cond, then, else_ } => {
+                write!(f, "br {}, {}, {}", cond, then, else_)
+            }
+            Terminator::Return(Some(value)) => write!(f, "ret {}", value),
+            Terminator::Return(None) => write!(f, "ret"),
+        }
+    }
+}
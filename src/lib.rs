@@ -1,13 +1,33 @@
 use codegen::Assembly;
-use parser::{Function, Parser};
-use std::{fmt, mem};
+use parser::Parser;
+use preprocessor::Preprocessor;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 use thiserror::Error;
+pub use passes::names as pass_names;
+pub use tokenizer::SourceFile;
 use tokenizer::Tokenizer;
 
 mod ast;
+mod cfg;
 mod codegen;
+mod dce;
+mod fold;
+mod inline;
+mod ir;
+mod loops;
+mod lower;
 mod parser;
+mod passes;
+mod peephole;
+mod preprocessor;
+mod regalloc;
+mod ssa;
 mod tokenizer;
+mod types;
+mod x86;
 
 #[derive(Error, Debug)]
 pub enum ErrorKind {
@@ -22,12 +42,100 @@ pub struct Error {
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.kind)?;
-        if let Some(span) = &self.span {
-            // write!(f, "\n{}\n", span.code)?;
-            write!(f, "{:>width$}", "^", width = span.pos)?;
+        write!(f, "{}", Diagnostic::from(self))
+    }
+}
+
+/// An error in a translation unit, or a warning, as reported to the user of the library.
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    // where in the sources, unless the error is about the translation unit as a whole
+    pub location: Option<Location>,
+    // the source line of `location`
+    source_line: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Location {
+    pub file: String,
+    // both counted from 1
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Severity {
+    Error,
+    // the program still compiles
+    Warning,
+}
+
+impl Diagnostic {
+    pub fn new(message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            message,
+            location: None,
+            source_line: String::new(),
+        }
+    }
+
+    pub(crate) fn warning(message: String, span: Option<&Span>) -> Self {
+        Self::at(Severity::Warning, message, span)
+    }
+
+    fn at(severity: Severity, message: String, span: Option<&Span>) -> Self {
+        let span = match span {
+            Some(span) => span,
+            None => {
+                return Self {
+                    severity,
+                    ..Diagnostic::new(message)
+                }
+            }
+        };
+        let contents = &span.file.contents;
+        let start = contents[..span.pos].rfind('\n').map_or(0, |i| i + 1);
+        let end = contents[span.pos..]
+            .find('\n')
+            .map_or(contents.len(), |i| span.pos + i);
+        Self {
+            severity,
+            message,
+            location: Some(Location {
+                file: span.file.name.clone(),
+                line: contents[..start].matches('\n').count() + 1,
+                column: span.pos - start + 1,
+            }),
+            source_line: contents[start..end].to_string(),
         }
-        Ok(())
+    }
+}
+
+impl From<&Error> for Diagnostic {
+    fn from(e: &Error) -> Self {
+        let ErrorKind::Generic(message) = &e.kind;
+        Self::at(Severity::Error, message.clone(), e.span.as_ref())
+    }
+}
+
+// `file:line:column: error: `message``, or `warning:`, followed by the source line and a caret
+// under the column
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.severity {
+            Severity::Error => ErrorKind::Generic(self.message.clone()).to_string(),
+            Severity::Warning => format!("warning: `{}`", self.message),
+        };
+        let loc = match &self.location {
+            Some(loc) => loc,
+            None => return writeln!(f, "{}", kind),
+        };
+        writeln!(f, "{}:{}:{}: {}", loc.file, loc.line, loc.column, kind)?;
+        writeln!(f, "{}", self.source_line)?;
+        writeln!(f, "{:>width$}", "^", width = loc.column)
     }
 }
 
@@ -37,30 +145,156 @@ impl From<fmt::Error> for ErrorKind {
     }
 }
 
+#[derive(Clone, Debug)]
 pub struct Span {
+    file: Rc<SourceFile>,
     pos: usize,
 }
 
 impl Span {
-    pub fn new(pos: usize) -> Self {
-        Self { pos }
+    pub fn new(file: Rc<SourceFile>, pos: usize) -> Self {
+        Self { file, pos }
     }
 }
 
-pub fn run<'a>(code: &'a str) -> Result<String, Error> {
-    let t = Tokenizer::new(code);
-    let tokens = t.run();
-    let mut parser = Parser::new(code, tokens);
+#[derive(Clone, Debug)]
+pub struct Options {
+    // `-I`, searched by `#include "..."` after the directory of the including file
+    pub include_paths: Vec<PathBuf>,
+    // `-isystem` and the defaults, searched after `include_paths`
+    pub system_include_paths: Vec<PathBuf>,
+    // `-D name=value` and `-U name`, in order: `None` undefines
+    pub macros: Vec<(String, Option<String>)>,
+    // `-Wunreachable-code`
+    pub warn_unreachable_code: bool,
+    // `-O<level>`: the passes of the levels up to it run
+    pub opt_level: u32,
+    // `-fno-<pass>`: passes left out whatever the level
+    pub disabled_passes: Vec<String>,
+    // `-fdump-<pass>`: the IR or code before and after them, written to the standard error
+    pub dumped_passes: Vec<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        // toycc's own headers come first, as they replace the compiler-specific ones of the host
+        let own = PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/include"));
+        let mut system = vec![own];
+        system.extend(host_include_paths());
+        Self {
+            include_paths: vec![],
+            system_include_paths: system,
+            macros: vec![],
+            warn_unreachable_code: false,
+            opt_level: 0,
+            disabled_passes: vec![],
+            dumped_passes: vec![],
+        }
+    }
+}
+
+// The host's C library headers, in the order gcc searches them: the multiarch directory
+// holds the headers specific to x86-64, as `bits/` and `sys/`.
+fn host_include_paths() -> Vec<PathBuf> {
+    let candidates = [
+        "/usr/local/include",
+        "/usr/include/x86_64-linux-gnu",
+        "/usr/include",
+    ];
+    candidates
+        .iter()
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_dir())
+        .collect()
+}
+
+/// A compiled translation unit.
+#[derive(Clone, Debug)]
+pub struct Output {
+    // AT&T syntax, for the system assembler
+    pub assembly: String,
+    // the functions in the intermediate representation, after the passes: in SSA form from
+    // `mem2reg` on
+    pub ir: String,
+    pub warnings: Vec<Diagnostic>,
+}
+
+/// Runs the preprocessor only, as `-E` does.
+pub fn preprocess(file: SourceFile, options: &Options) -> Result<String, Vec<Diagnostic>> {
+    let tokens = Tokenizer::new(Rc::new(file))
+        .run()
+        .and_then(|tokens| Preprocessor::new(options)?.run(tokens))
+        .map_err(|e| vec![Diagnostic::from(&e)])?;
+    Ok(preprocessor::print(&tokens))
+}
+
+/// Runs the peephole optimiser over the assembly of a function, as written by the code
+/// generator, as `-O1` does. For the tests of its rewrites only.
+#[doc(hidden)]
+pub fn optimize_assembly(code: &str) -> String {
+    let mut listing = x86::Listing::parse(code);
+    peephole::optimize(&mut listing);
+    listing.to_string()
+}
+
+/// Compiles one translation unit to assembly. Each one is compiled on its own: the symbols
+/// of others are resolved by the linker.
+pub fn compile(file: SourceFile, options: &Options) -> Result<Output, Vec<Diagnostic>> {
+    compile_unit(file, options).map_err(|e| vec![Diagnostic::from(&e)])
+}
+
+/// Compiles the C file at `path`, as [`compile`] does.
+pub fn compile_file(path: impl AsRef<Path>, options: &Options) -> Result<Output, Vec<Diagnostic>> {
+    let path = path.as_ref();
+    let contents = fs::read_to_string(path).map_err(|e| {
+        let message = format!("cannot read {}: {}", path.display(), e);
+        vec![Diagnostic::new(message)]
+    })?;
+    let file = SourceFile::new(path.display().to_string(), contents);
+    compile(file, options)
+}
+
+fn compile_unit(file: SourceFile, options: &Options) -> Result<Output, Error> {
+    let t = Tokenizer::new(Rc::new(file));
+    let tokens = t.run()?;
+    let tokens = Preprocessor::new(options)?.run(tokens)?;
+    let mut parser = Parser::new(tokens);
 
     let program = parser.run()?;
-    eprintln!("{}\n{:#?}", code, program);
-    let fun = Function::new(program, mem::take(&mut parser.locals));
-    //assert parser is at EOF
+    let mut program = lower::lower(&program);
+    let mut warnings = vec![];
+    if options.warn_unreachable_code {
+        for func in &program.functions {
+            warnings.extend(dce::unreachable_code(func));
+        }
+    }
+    passes::run_on_program(&mut program, options);
+    for func in &mut program.functions {
+        passes::run(func, options);
+        warnings.extend(fold::divisions_by_zero(func));
+    }
+    let ir = program.to_string();
+    for func in &mut program.functions {
+        ssa::destruct(func);
+        verify(func);
+    }
 
-    dbg!(&fun.locals);
-    let mut assembly = Assembly::new(&fun);
-    // Ok("".to_string())
+    let mut assembly = Assembly::new(&program);
     assembly.gen();
+    for listing in assembly.code_mut() {
+        passes::run_on_code(listing, options);
+    }
 
-    Ok(assembly.finish())
+    Ok(Output {
+        assembly: assembly.finish(),
+        ir,
+        warnings,
+    })
+}
+
+// a pass that breaks the IR is a bug of the compiler, not of the program
+fn verify(func: &ir::Function) {
+    if let Err(e) = func.verify() {
+        panic!("invalid IR: {}\n{}", e, func);
+    }
 }
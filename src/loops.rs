@@ -0,0 +1,397 @@
+//! Loop optimisations, on the natural loops of each function.
+//!
+//! Before the SSA construction, a loop tested at the top is rotated to be tested at the
+//! bottom: the test at the top only decides whether the loop is entered, and each iteration
+//! ends with a copy of it jumping back. In SSA form, the instructions computing the same
+//! value in each iteration are hoisted out of the loop, and the addresses computed from an
+//! induction variable as `base + i * size`, as for `a[i]`, become pointers of their own,
+//! bumped by `step * size` where the variable is stepped.
+
+use crate::cfg::{self, DomTree, Loop};
+use crate::ir::{BinOp, BlockId, Function, Inst, Op, Terminator, Ty, Value};
+use std::collections::{HashMap, HashSet};
+use std::mem;
+
+// the most instructions of a test copied to the bottom of its loop
+const ROTATED_MAX: usize = 16;
+
+/// Rotates the loops tested at the top, one at a time, as each changes the loops.
+pub fn rotate(func: &mut Function) {
+    loop {
+        let dom = DomTree::new(func);
+        let loops = cfg::natural_loops(func, &dom);
+        if !loops.iter().any(|l| rotate_loop(func, l)) {
+            return;
+        }
+    }
+}
+
+// A loop whose header only tests whether to go on, with a branch into the loop or out of it,
+// gets a copy of the test at the end of each block jumping back. The values of the header
+// must not be read elsewhere, as each copy defines its own. Whether it was rotated.
+fn rotate_loop(func: &mut Function, l: &Loop) -> bool {
+    let header = &func.blocks[l.header.0];
+    let (cond, then, else_) = match header.term {
+        Terminator::Branch { cond, then, else_ } => (cond, then, else_),
+        _ => return false,
+    };
+    let has_phis = |b: BlockId| {
+        let insts = &func.blocks[b.0].insts;
+        insts.iter().any(|inst| matches!(inst.op, Op::Phi(_)))
+    };
+    if l.contains(then) == l.contains(else_)
+        || header.insts.len() > ROTATED_MAX
+        || [l.header, then, else_].iter().any(|b| has_phis(*b))
+    {
+        return false;
+    }
+    let latches_jump = l.latches.iter().all(|latch| {
+        let term = &func.blocks[latch.0].term;
+        matches!(term, Terminator::Jump(_))
+    });
+    let defined: HashSet<_> = header.insts.iter().filter_map(|inst| inst.result).collect();
+    let read_elsewhere = func.blocks.iter().enumerate().any(|(b, block)| {
+        let mut operands = block.insts.iter().flat_map(|inst| inst.op.operands());
+        let read = operands.any(|v| defined.contains(&v))
+            || block.term.operands().iter().any(|v| defined.contains(v));
+        b != l.header.0 && read
+    });
+    if !latches_jump || read_elsewhere {
+        return false;
+    }
+    let test = header.insts.clone();
+    for latch in &l.latches {
+        let mut values = HashMap::new();
+        for inst in &test {
+            let mut op = inst.op.clone();
+            for v in op.operands_mut() {
+                *v = values.get(v).copied().unwrap_or(*v);
+            }
+            let result = inst.result.map(|v| {
+                let copy = Value(func.value_count);
+                func.value_count += 1;
+                values.insert(v, copy);
+                copy
+            });
+            // the warnings are about the test as written, once
+            func.blocks[latch.0].insts.push(Inst {
+                result,
+                op,
+                span: None,
+            });
+        }
+        func.blocks[latch.0].term = Terminator::Branch {
+            cond: values.get(&cond).copied().unwrap_or(cond),
+            then,
+            else_,
+        };
+    }
+    true
+}
+
+/// Hoists the instructions of each loop whose operands are defined out of it, and which
+/// cannot fail, to the block before it. The inner loops go first, so that what they hoist
+/// may be hoisted out of the loops around them as well.
+pub fn hoist_invariants(func: &mut Function) {
+    let dom = DomTree::new(func);
+    for l in cfg::natural_loops(func, &dom) {
+        if let Some(preheader) = preheader(func, &l) {
+            hoist(func, &l, preheader);
+        }
+    }
+}
+
+// the block out of the loop that all the ways into it come from
+fn preheader(func: &Function, l: &Loop) -> Option<BlockId> {
+    let preds = &func.predecessors()[l.header.0];
+    let mut outside = preds.iter().filter(|pred| !l.contains(**pred));
+    let preheader = *outside.next()?;
+    outside.all(|pred| *pred == preheader).then(|| preheader)
+}
+
+// A constant is left where it is, as it takes a register wherever it is live, and one read by
+// a hoisted instruction is copied along with it.
+fn hoist(func: &mut Function, l: &Loop, preheader: BlockId) {
+    let mut defined_in = HashMap::new();
+    let mut constants = HashMap::new();
+    for (b, block) in func.blocks.iter().enumerate() {
+        for inst in &block.insts {
+            if let Some(v) = inst.result {
+                defined_in.insert(v, BlockId(b));
+                if let Op::Const(n) = inst.op {
+                    constants.insert(v, n);
+                }
+            }
+        }
+    }
+    // each one after those it reads
+    let mut hoisted = vec![];
+    let mut copies = HashMap::new();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for b in l.blocks() {
+            let insts = mem::take(&mut func.blocks[b.0].insts);
+            for mut inst in insts {
+                let invariant = inst
+                    .op
+                    .operands()
+                    .iter()
+                    .all(|v| !l.contains(defined_in[v]) || constants.contains_key(v));
+                if !invariant || !cannot_fail(&inst.op) {
+                    func.blocks[b.0].insts.push(inst);
+                    continue;
+                }
+                for v in inst.op.operands_mut() {
+                    if !l.contains(defined_in[v]) {
+                        continue;
+                    }
+                    let n = constants[v];
+                    *v = *copies.entry(*v).or_insert_with(|| {
+                        let copy = Value(func.value_count);
+                        func.value_count += 1;
+                        hoisted.push(Inst {
+                            result: Some(copy),
+                            op: Op::Const(n),
+                            span: None,
+                        });
+                        copy
+                    });
+                }
+                defined_in.insert(inst.result.unwrap(), preheader);
+                hoisted.push(inst);
+                changed = true;
+            }
+        }
+    }
+    append(func, preheader, hoisted);
+}
+
+// the instructions that only compute a value, and may run where they did not
+fn cannot_fail(op: &Op) -> bool {
+    match op {
+        Op::SlotAddr(_) | Op::SymbolAddr(_) | Op::Unary(..) | Op::Cmp(..) | Op::Extend { .. } => {
+            true
+        }
+        Op::Binary(op, ..) => !matches!(op, BinOp::SDiv | BinOp::UDiv | BinOp::SRem | BinOp::URem),
+        _ => false,
+    }
+}
+
+// Adds instructions at the end of a block, but before a comparison only its branch reads,
+// which the code generator keeps in the flags.
+fn append(func: &mut Function, block: BlockId, insts: Vec<Inst>) {
+    let block = &mut func.blocks[block.0];
+    let mut at = block.insts.len();
+    if let (Some(last), Terminator::Branch { cond, .. }) = (block.insts.last(), &block.term) {
+        let read = insts.iter().any(|inst| inst.op.operands().contains(cond));
+        if last.result == Some(*cond) && !read {
+            at -= 1;
+        }
+    }
+    block.insts.splice(at..at, insts);
+}
+
+// An induction variable: a phi of the header of a loop, stepped by a constant in each
+// iteration.
+struct Induction {
+    init: Value,
+    step: i64,
+    // where it is stepped
+    next: Value,
+    // whether it is an `int` that is the same sign extended, as it does not overflow
+    is_int: bool,
+}
+
+/// Replaces the addresses computed in each loop as `base + i * size`, from an induction
+/// variable `i` and a `base` defined out of the loop, by a pointer starting at the first
+/// address, and bumped as the variable is stepped.
+pub fn reduce_strength(func: &mut Function) {
+    let dom = DomTree::new(func);
+    for l in cfg::natural_loops(func, &dom) {
+        let preheader = match preheader(func, &l) {
+            Some(preheader) => preheader,
+            None => continue,
+        };
+        // the phis have a value from before the loop and one from the end of an iteration
+        match l.latches.as_slice() {
+            [latch] if func.predecessors()[l.header.0].len() == 2 => {
+                reduce(func, &l, preheader, *latch)
+            }
+            _ => {}
+        }
+    }
+}
+
+fn reduce(func: &mut Function, l: &Loop, preheader: BlockId, latch: BlockId) {
+    let mut defs = HashMap::new();
+    let mut defined_in = HashMap::new();
+    for (b, block) in func.blocks.iter().enumerate() {
+        for inst in &block.insts {
+            if let Some(v) = inst.result {
+                defs.insert(v, inst.op.clone());
+                defined_in.insert(v, BlockId(b));
+            }
+        }
+    }
+    let constant = |v: &Value| match defs.get(v) {
+        Some(Op::Const(n)) => Some(*n),
+        _ => None,
+    };
+    let sext_of = |v: &Value| match defs.get(v) {
+        Some(Op::Extend {
+            ty: Ty::I32,
+            signed: true,
+            arg,
+        }) => Some(*arg),
+        _ => None,
+    };
+
+    let mut inductions = HashMap::new();
+    for inst in &func.blocks[l.header.0].insts {
+        let (phi, incoming) = match (&inst.op, inst.result) {
+            (Op::Phi(incoming), Some(phi)) => (phi, incoming),
+            _ => continue,
+        };
+        let value_from = |pred: BlockId| {
+            let edge = incoming.iter().find(|(from, _)| *from == pred);
+            edge.map(|(_, v)| *v)
+        };
+        let (init, next) = match (value_from(preheader), value_from(latch)) {
+            (Some(init), Some(next)) => (init, next),
+            _ => continue,
+        };
+        let init_is_int =
+            sext_of(&init).is_some() || constant(&init).map_or(false, |n| n == n as i32 as i64);
+        // an `int` local is sign extended as it is stored, which changes nothing, as it does
+        // not overflow
+        let stored = sext_of(&next).filter(|_| init_is_int);
+        let (x, step) = match defs.get(&stored.unwrap_or(next)) {
+            Some(Op::Binary(BinOp::Add, x, y)) => match (constant(x), constant(y)) {
+                (None, Some(step)) => (*x, step),
+                (Some(step), None) => (*y, step),
+                _ => continue,
+            },
+            _ => continue,
+        };
+        let is_int = (sext_of(&x) == Some(phi) || stored.is_some()) && init_is_int;
+        if x == phi || is_int {
+            let induction = Induction {
+                init,
+                step,
+                next,
+                is_int,
+            };
+            inductions.insert(phi, induction);
+        }
+    }
+    // the induction variable a value is, as the phi or its sign extension
+    let induction_of = |v: &Value| match (inductions.get(v), sext_of(v)) {
+        (Some(_), _) => Some(*v),
+        (None, Some(arg)) if inductions.get(&arg).map_or(false, |iv| iv.is_int) => Some(arg),
+        _ => None,
+    };
+    // `i * size`, as `i << n` once folded, or `i` alone
+    let scaled = |v: &Value| match defs.get(v) {
+        Some(Op::Binary(BinOp::Shl, x, n)) => match constant(n) {
+            Some(n) if (0..63).contains(&n) => Some((induction_of(x)?, 1 << n)),
+            _ => None,
+        },
+        Some(Op::Binary(BinOp::Mul, x, y)) => match (constant(x), constant(y)) {
+            (None, Some(size)) => Some((induction_of(x)?, size)),
+            (Some(size), None) => Some((induction_of(y)?, size)),
+            _ => None,
+        },
+        _ => Some((induction_of(v)?, 1)),
+    };
+    let invariant = |v: &Value| !l.contains(defined_in[v]) && constant(v).is_none();
+
+    // the addresses to replace, by what they are computed from
+    let mut addresses = vec![];
+    for b in l.blocks() {
+        for inst in &func.blocks[b.0].insts {
+            let (x, y) = match inst.op {
+                Op::Binary(BinOp::Add, x, y) => (x, y),
+                _ => continue,
+            };
+            let address = match (invariant(&x), invariant(&y)) {
+                (true, false) => scaled(&y).map(|(iv, size)| (iv, x, size)),
+                (false, true) => scaled(&x).map(|(iv, size)| (iv, y, size)),
+                _ => None,
+            };
+            if let Some(address) = address {
+                addresses.push((inst.result.unwrap(), address));
+            }
+        }
+    }
+
+    // one pointer for each induction variable, base and size
+    let mut pointers = HashMap::new();
+    let mut replaced = HashMap::new();
+    for (address, key) in addresses {
+        let pointer = *pointers.entry(key).or_insert_with(|| {
+            let (iv, base, size) = key;
+            new_pointer(func, &inductions[&iv], base, size, preheader, latch, l)
+        });
+        replaced.insert(address, pointer);
+    }
+    let replace = |v: &mut Value| {
+        if let Some(pointer) = replaced.get(v) {
+            *v = *pointer;
+        }
+    };
+    for b in l.blocks() {
+        let block = &mut func.blocks[b.0];
+        for inst in &mut block.insts {
+            inst.op.operands_mut().into_iter().for_each(replace);
+        }
+        block.term.operands_mut().into_iter().for_each(replace);
+    }
+}
+
+// A phi in the header starting at `base + init * size`, and stepped by `step * size` right
+// after the induction variable.
+fn new_pointer(
+    func: &mut Function,
+    iv: &Induction,
+    base: Value,
+    size: i64,
+    preheader: BlockId,
+    latch: BlockId,
+    l: &Loop,
+) -> Value {
+    let mut new_value = || {
+        func.value_count += 1;
+        Value(func.value_count - 1)
+    };
+    let inst = |result: Value, op: Op| Inst {
+        result: Some(result),
+        op,
+        span: None,
+    };
+    let (size_value, offset, start) = (new_value(), new_value(), new_value());
+    let (pointer, step, next) = (new_value(), new_value(), new_value());
+    append(
+        func,
+        preheader,
+        vec![
+            inst(size_value, Op::Const(size)),
+            inst(offset, Op::Binary(BinOp::Mul, iv.init, size_value)),
+            inst(start, Op::Binary(BinOp::Add, base, offset)),
+        ],
+    );
+    let phi = Op::Phi(vec![(preheader, start), (latch, next)]);
+    func.blocks[l.header.0].insts.insert(0, inst(pointer, phi));
+    for block in &mut func.blocks {
+        if let Some(i) = block.insts.iter().position(|x| x.result == Some(iv.next)) {
+            let bump = iv.step.wrapping_mul(size);
+            let insts = vec![
+                inst(step, Op::Const(bump)),
+                inst(next, Op::Binary(BinOp::Add, pointer, step)),
+            ];
+            block.insts.splice(i + 1..i + 1, insts);
+            break;
+        }
+    }
+    pointer
+}
@@ -0,0 +1,716 @@
+//! Lowers the AST of each function into the IR: expressions into instructions, control flow
+//! into basic blocks. Every local gets a stack slot, and so do the temporaries that carry a
+//! value from several blocks to the one where they meet, as for `&&` and `?:`.
+
+use crate::ast::{self, *};
+use crate::ir::{self, BlockId, Callee, Cond, Inst, Op, Slot, SlotData, Terminator, Ty, Value};
+use crate::parser;
+use crate::types::Type;
+use crate::Span;
+use std::collections::HashMap;
+use std::mem;
+
+// `va_arg` takes the general purpose registers first, 8 bytes each
+const GP_REGS_SIZE: i64 = 6 * 8;
+
+pub fn lower(program: &Program) -> ir::Program {
+    ir::Program {
+        functions: program.functions.iter().map(lower_function).collect(),
+        globals: program.globals.clone(),
+        strings: program.strings.clone(),
+    }
+}
+
+struct Lowering<'a> {
+    func: &'a parser::Function,
+    slots: Vec<SlotData>,
+    // filled in as they are terminated; the entry block is first
+    blocks: Vec<Option<ir::Block>>,
+    // the block being filled, if any: code after a jump goes in a new block, with no predecessor
+    current: Option<BlockId>,
+    // the blocks in the order they are filled, which is the order of the code
+    order: Vec<BlockId>,
+    insts: Vec<Inst>,
+    value_count: usize,
+    // where `break` and `continue` jump to, innermost last
+    breaks: Vec<BlockId>,
+    continues: Vec<BlockId>,
+    // the blocks of the cases of the enclosing switches
+    switches: Vec<Vec<BlockId>>,
+    labels: HashMap<String, BlockId>,
+    // where the statement being lowered starts, until its first instruction takes it
+    stmt_span: Option<Span>,
+}
+
+fn lower_function(func: &parser::Function) -> ir::Function {
+    let slots = func
+        .locals
+        .iter()
+        .map(|local| SlotData {
+            name: local.name.clone(),
+            size: local.ty().size(),
+            align: local.ty().align(),
+        })
+        .collect();
+    let mut l = Lowering {
+        func,
+        slots,
+        blocks: vec![],
+        current: None,
+        order: vec![],
+        insts: vec![],
+        value_count: 0,
+        breaks: vec![],
+        continues: vec![],
+        switches: vec![],
+        labels: HashMap::new(),
+        stmt_span: None,
+    };
+    let entry = l.new_block();
+    l.switch_to(entry);
+    // the arguments are saved first, while they are still in their registers
+    let params: Vec<_> = (0..func.params().len())
+        .map(|i| l.emit(Op::Param(i)))
+        .collect();
+    for (param, value) in func.params().iter().zip(params) {
+        let ty = func.local(*param).ty().clone();
+        let addr = l.emit(Op::SlotAddr(Slot(param.0)));
+        l.store(&ty, addr, value);
+    }
+    // the entry block is never jumped to, even by a loop at the start of the body
+    let body = l.new_block();
+    l.terminate(Terminator::Jump(body));
+    l.switch_to(body);
+    l.stmts(&func.body().stmts);
+    // falling off the end of `main` returns 0
+    let ret = (l.current.is_some() && func.name() == "main").then(|| l.constant(0));
+    l.terminate(Terminator::Return(ret));
+
+    ir::Function {
+        name: func.name().to_string(),
+        is_static: func.is_static(),
+        inlining: func.inlining(),
+        params: func.params().len(),
+        slots: l.slots,
+        blocks: renumber(l.blocks, &l.order),
+        value_count: l.value_count,
+        va_area: func.va_area().map(|local| Slot(local.0)),
+    }
+}
+
+// puts the blocks in the given order, and numbers them accordingly
+fn renumber(mut blocks: Vec<Option<ir::Block>>, order: &[BlockId]) -> Vec<ir::Block> {
+    let mut number = vec![None; blocks.len()];
+    for (i, block) in order.iter().enumerate() {
+        number[block.0] = Some(BlockId(i));
+    }
+    let number = |block: BlockId| number[block.0].expect("a jump to a block never filled");
+    order
+        .iter()
+        .map(|id| {
+            let mut block = blocks[id.0].take().expect("a block is never terminated");
+            block.term = match block.term {
+                Terminator::Jump(target) => Terminator::Jump(number(target)),
+                Terminator::Branch { cond, then, else_ } => Terminator::Branch {
+                    cond,
+                    then: number(then),
+                    else_: number(else_),
+                },
+                term => term,
+            };
+            block
+        })
+        .collect()
+}
+
+impl Lowering<'_> {
+    fn new_block(&mut self) -> BlockId {
+        self.blocks.push(None);
+        BlockId(self.blocks.len() - 1)
+    }
+
+    // the current block must be terminated
+    fn switch_to(&mut self, block: BlockId) {
+        assert!(self.current.is_none(), "switching blocks without a jump");
+        self.current = Some(block);
+        self.order.push(block);
+    }
+
+    fn current(&mut self) -> BlockId {
+        match self.current {
+            Some(block) => block,
+            None => {
+                let block = self.new_block();
+                self.switch_to(block);
+                block
+            }
+        }
+    }
+
+    // A jump right after another one, as in `return 1; }`, is left out: it would be the only
+    // code of a block that nothing jumps to.
+    fn terminate(&mut self, term: Terminator) {
+        if self.current.is_none() {
+            return;
+        }
+        let block = self.current();
+        let insts = mem::take(&mut self.insts);
+        self.blocks[block.0] = Some(ir::Block { insts, term });
+        self.current = None;
+    }
+
+    // ends the current block with a jump to `block`, which comes next
+    fn jump_to(&mut self, block: BlockId) {
+        self.terminate(Terminator::Jump(block));
+        self.switch_to(block);
+    }
+
+    fn branch(&mut self, cond: Value, then: BlockId, else_: BlockId) {
+        self.terminate(Terminator::Branch { cond, then, else_ });
+    }
+
+    fn emit(&mut self, op: Op) -> Value {
+        self.emit_at(op, None)
+    }
+
+    // an instruction with where it comes from in the sources, for warnings about it
+    fn emit_at(&mut self, op: Op, span: Option<Span>) -> Value {
+        let result = Value(self.value_count);
+        self.value_count += 1;
+        self.push(Inst {
+            result: Some(result),
+            op,
+            span,
+        });
+        result
+    }
+
+    // an instruction without a result; the first one of a statement is where the statement is,
+    // unless it has a place of its own
+    fn push(&mut self, mut inst: Inst) {
+        self.current();
+        if let Some(span) = self.stmt_span.take() {
+            inst.span.get_or_insert(span);
+        }
+        self.insts.push(inst);
+    }
+
+    fn constant(&mut self, n: i64) -> Value {
+        self.emit(Op::Const(n))
+    }
+
+    fn temp_slot(&mut self) -> Slot {
+        self.slots.push(SlotData {
+            name: "tmp".to_string(),
+            size: 8,
+            align: 8,
+        });
+        Slot(self.slots.len() - 1)
+    }
+
+    fn stmts(&mut self, stmts: &[(Statement, Span)]) {
+        for (stmt, span) in stmts {
+            self.stmt_span = Some(span.clone());
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::Expr(e) => {
+                self.expr(e);
+            }
+            Statement::Return(e) => {
+                let value = e.as_ref().map(|e| self.expr(e));
+                self.terminate(Terminator::Return(value));
+            }
+            Statement::Compound(c) => self.stmts(&c.stmts),
+            Statement::Declaration(inits) => {
+                for init in inits {
+                    self.initialization(init);
+                }
+            }
+            Statement::Empty => {}
+            Statement::If(i) => {
+                let then = self.new_block();
+                let end = self.new_block();
+                let else_ = match i.else_branch {
+                    Some(_) => self.new_block(),
+                    None => end,
+                };
+                self.branch_on(&i.condition, then, else_);
+                self.switch_to(then);
+                self.stmt(&i.then_branch);
+                if let Some(e) = &i.else_branch {
+                    self.terminate(Terminator::Jump(end));
+                    self.switch_to(else_);
+                    self.stmt(e);
+                }
+                self.jump_to(end);
+            }
+            Statement::While(w) => {
+                let (begin, body, cont, end) = (
+                    self.new_block(),
+                    self.new_block(),
+                    self.new_block(),
+                    self.new_block(),
+                );
+                self.jump_to(begin);
+                self.condition(w.condition.as_ref(), body, end);
+                self.switch_to(body);
+                self.loop_body(&w.body, cont, end);
+                self.terminate(Terminator::Jump(begin));
+                self.switch_to(end);
+            }
+            Statement::DoWhile(w) => {
+                let (body, cont, end) = (self.new_block(), self.new_block(), self.new_block());
+                self.jump_to(body);
+                self.loop_body(&w.body, cont, end);
+                self.condition(w.condition.as_ref(), body, end);
+                self.switch_to(end);
+            }
+            Statement::For(f) => {
+                if let Some(init) = &f.init {
+                    self.stmt(init);
+                }
+                let (begin, body, cont, end) = (
+                    self.new_block(),
+                    self.new_block(),
+                    self.new_block(),
+                    self.new_block(),
+                );
+                self.jump_to(begin);
+                self.condition(f.condition.as_ref(), body, end);
+                self.switch_to(body);
+                self.loop_body(&f.body, cont, end);
+                if let Some(e) = &f.update {
+                    self.expr(e);
+                }
+                self.terminate(Terminator::Jump(begin));
+                self.switch_to(end);
+            }
+            Statement::Switch(sw) => self.switch(sw),
+            Statement::Case(c) => {
+                let block = self.switches.last().expect("case outside of a switch")[c.index];
+                self.jump_to(block);
+                self.stmt(&c.body);
+            }
+            Statement::Break => {
+                let target = *self.breaks.last().expect("break outside of a loop");
+                self.terminate(Terminator::Jump(target));
+            }
+            Statement::Continue => {
+                let target = *self.continues.last().expect("continue outside of a loop");
+                self.terminate(Terminator::Jump(target));
+            }
+            Statement::Goto(name) => {
+                let target = self.label(name);
+                self.terminate(Terminator::Jump(target));
+            }
+            Statement::Label(name, body) => {
+                let block = self.label(name);
+                self.jump_to(block);
+                self.stmt(body);
+            }
+        }
+    }
+
+    // jumps to `then` if `cond` holds, or if there is none, else to `else_`
+    fn condition(&mut self, cond: Option<&Expression>, then: BlockId, else_: BlockId) {
+        match cond {
+            Some(cond) => self.branch_on(cond, then, else_),
+            None => self.terminate(Terminator::Jump(then)),
+        }
+    }
+
+    // Jumps to `then` if `cond` holds, else to `else_`, without computing its value where
+    // the branches can tell: each operand of `&&` and `||` is a branch of its own, to the
+    // next one or past the others, and `!` swaps the targets.
+    fn branch_on(&mut self, cond: &Expression, then: BlockId, else_: BlockId) {
+        match &cond.kind {
+            ExpressionKind::Binary(b) if matches!(b.op, ast::BinOp::LogAnd) => {
+                let rhs = self.new_block();
+                self.branch_on(&b.lhs, rhs, else_);
+                self.switch_to(rhs);
+                self.branch_on(&b.rhs, then, else_);
+            }
+            ExpressionKind::Binary(b) if matches!(b.op, ast::BinOp::LogOr) => {
+                let rhs = self.new_block();
+                self.branch_on(&b.lhs, then, rhs);
+                self.switch_to(rhs);
+                self.branch_on(&b.rhs, then, else_);
+            }
+            ExpressionKind::Unary(u) if matches!(u.op, UnaryOp::Not) => {
+                self.branch_on(&u.lhs, else_, then)
+            }
+            _ => {
+                let cond = self.expr(cond);
+                self.branch(cond, then, else_);
+            }
+        }
+    }
+
+    // `continue` jumps past the body, to the block `cont` which comes next
+    fn loop_body(&mut self, body: &Statement, cont: BlockId, end: BlockId) {
+        self.breaks.push(end);
+        self.continues.push(cont);
+        self.stmt(body);
+        self.continues.pop();
+        self.breaks.pop();
+        self.jump_to(cont);
+    }
+
+    fn label(&mut self, name: &str) -> BlockId {
+        if let Some(block) = self.labels.get(name) {
+            return *block;
+        }
+        let block = self.new_block();
+        self.labels.insert(name.to_string(), block);
+        block
+    }
+
+    // compares the value with each case in turn, then jumps to the default or past the body
+    fn switch(&mut self, sw: &SwitchStatement) {
+        let cases: Vec<_> = sw.cases.iter().map(|_| self.new_block()).collect();
+        let end = self.new_block();
+        let value = self.expr(&sw.condition);
+        for (case, block) in sw.cases.iter().zip(&cases) {
+            if let Some(n) = case {
+                let n = self.constant(*n);
+                let cond = self.emit(Op::Cmp(Cond::Eq, value, n));
+                let next = self.new_block();
+                self.branch(cond, *block, next);
+                self.switch_to(next);
+            }
+        }
+        let default = sw.cases.iter().position(Option::is_none);
+        self.terminate(Terminator::Jump(default.map_or(end, |i| cases[i])));
+        self.switches.push(cases);
+        self.breaks.push(end);
+        self.stmt(&sw.body);
+        self.breaks.pop();
+        self.switches.pop();
+        self.jump_to(end);
+    }
+
+    fn initialization(&mut self, init: &Initialization) {
+        let local = self.func.local(init.local);
+        let base = self.emit(Op::SlotAddr(Slot(init.local.0)));
+        if init.zero_fill {
+            let size = local.ty().size();
+            self.push(Inst {
+                result: None,
+                op: Op::Zero { addr: base, size },
+                span: None,
+            });
+        }
+        for store in &init.stores {
+            // the stores are in the local, from its start
+            let addr = self.offset(base, store.offset);
+            let value = self.expr(&store.value);
+            self.store(&store.ty, addr, value);
+        }
+    }
+
+    fn offset(&mut self, addr: Value, offset: usize) -> Value {
+        if offset == 0 {
+            return addr;
+        }
+        let offset = self.constant(offset as i64);
+        self.emit(Op::Binary(ir::BinOp::Add, addr, offset))
+    }
+
+    fn addr(&mut self, e: &Expression) -> Value {
+        match &e.kind {
+            ExpressionKind::Identifier(local) => self.emit(Op::SlotAddr(Slot(local.0))),
+            ExpressionKind::StringLiteral(id) => self.emit(Op::SymbolAddr(string_label(*id))),
+            ExpressionKind::Symbol(name) => self.emit(Op::SymbolAddr(name.clone())),
+            ExpressionKind::Member(m) => {
+                let base = self.addr(&m.base);
+                self.offset(base, m.offset)
+            }
+            ExpressionKind::Pointer(PointerExpr {
+                op: PointerOp::Deref,
+                arg,
+            }) => self.expr(arg),
+            k => unreachable!("not an lvalue: {:?}", k),
+        }
+    }
+
+    // arrays, structs and functions are not loaded: their value is their address
+    fn load(&mut self, ty: &Type, addr: Value) -> Value {
+        let (ty, signed) = match ty {
+            ty if ty.is_aggregate() => return addr,
+            Type::Func(_) => return addr,
+            Type::Bool | Type::UChar => (Ty::I8, false),
+            Type::UShort => (Ty::I16, false),
+            Type::UInt => (Ty::I32, false),
+            ty => (mem_ty(ty), true),
+        };
+        self.emit(Op::Load { ty, signed, addr })
+    }
+
+    // structs are copied, their value being their address
+    fn store(&mut self, ty: &Type, addr: Value, value: Value) {
+        let op = match ty {
+            Type::Struct(_) => Op::Copy {
+                dst: addr,
+                src: value,
+                size: ty.size(),
+            },
+            ty => Op::Store {
+                ty: mem_ty(ty),
+                addr,
+                value,
+            },
+        };
+        self.push(Inst {
+            result: None,
+            op,
+            span: None,
+        });
+    }
+
+    // sign or zero extends the low bits of a value of type `ty`
+    fn extend(&mut self, ty: &Type, arg: Value) -> Value {
+        let (ty, signed) = match ty {
+            Type::Bool | Type::UChar => (Ty::I8, false),
+            Type::Char => (Ty::I8, true),
+            Type::UShort => (Ty::I16, false),
+            Type::Short => (Ty::I16, true),
+            Type::UInt => (Ty::I32, false),
+            Type::Int => (Ty::I32, true),
+            _ => return arg,
+        };
+        self.emit(Op::Extend { ty, signed, arg })
+    }
+
+    fn expr(&mut self, e: &Expression) -> Value {
+        match &e.kind {
+            ExpressionKind::NumberLiteral(n) => self.constant(*n as i64),
+            ExpressionKind::Identifier(_)
+            | ExpressionKind::StringLiteral(_)
+            | ExpressionKind::Symbol(_)
+            | ExpressionKind::Member(_) => {
+                let addr = self.addr(e);
+                self.load(&e.ty, addr)
+            }
+            ExpressionKind::Unary(u) => {
+                let arg = self.expr(&u.lhs);
+                match u.op {
+                    UnaryOp::Neg => self.emit(Op::Unary(ir::UnOp::Neg, arg)),
+                    UnaryOp::Not => {
+                        let zero = self.constant(0);
+                        self.emit(Op::Cmp(Cond::Eq, arg, zero))
+                    }
+                    UnaryOp::BitNot => self.emit(Op::Unary(ir::UnOp::Not, arg)),
+                    UnaryOp::NoOp => arg,
+                    k => unimplemented!("{:?}", k),
+                }
+            }
+            ExpressionKind::Binary(b) => {
+                let value = self.binary(b, &e.ty);
+                // keeps the result within 32 bits, as in the operands
+                if e.ty == Type::UInt {
+                    self.extend(&e.ty, value)
+                } else {
+                    value
+                }
+            }
+            ExpressionKind::Assignment(a) => {
+                let addr = self.addr(&a.lhs);
+                let value = self.expr(&a.rhs);
+                self.store(&e.ty, addr, value);
+                value
+            }
+            ExpressionKind::Conditional(c) => {
+                let tmp = self.temp_slot();
+                let (then, else_, end) = (self.new_block(), self.new_block(), self.new_block());
+                self.branch_on(&c.condition, then, else_);
+                for (block, value) in [(then, &c.then_expr), (else_, &c.else_expr)].iter() {
+                    self.switch_to(*block);
+                    let value = self.expr(value);
+                    let addr = self.emit(Op::SlotAddr(tmp));
+                    self.store(&Type::Long, addr, value);
+                    self.terminate(Terminator::Jump(end));
+                }
+                self.switch_to(end);
+                let addr = self.emit(Op::SlotAddr(tmp));
+                self.load(&Type::Long, addr)
+            }
+            ExpressionKind::Pointer(p) => match p.op {
+                PointerOp::Ref => self.addr(&p.arg),
+                PointerOp::Deref => {
+                    let addr = self.expr(&p.arg);
+                    self.load(&e.ty, addr)
+                }
+            },
+            ExpressionKind::Call(c) => self.call(c, &e.ty),
+            ExpressionKind::Cast(arg) => {
+                let value = self.expr(arg);
+                match &e.ty {
+                    Type::Bool => {
+                        let zero = self.constant(0);
+                        self.emit(Op::Cmp(Cond::Ne, value, zero))
+                    }
+                    ty => self.extend(ty, value),
+                }
+            }
+            ExpressionKind::VaStart(ap) => self.va_start(ap),
+            ExpressionKind::VaArg(ap) => {
+                let addr = self.va_arg(ap);
+                self.load(&e.ty, addr)
+            }
+        }
+    }
+
+    fn binary(&mut self, b: &BinaryExpr, ty: &Type) -> Value {
+        use ir::BinOp::*;
+        if let ast::BinOp::LogAnd | ast::BinOp::LogOr = b.op {
+            return self.logical(b);
+        }
+        let lhs = self.expr(&b.lhs);
+        let rhs = self.expr(&b.rhs);
+        // the parser converts the operands of a comparison to their common type
+        let cmp = |signed, unsigned| if b.lhs.ty.is_unsigned() { unsigned } else { signed };
+        let op = match b.op {
+            ast::BinOp::Add => Add,
+            ast::BinOp::Sub => Sub,
+            ast::BinOp::Mul => Mul,
+            ast::BinOp::Div if ty.is_unsigned() => UDiv,
+            ast::BinOp::Div => SDiv,
+            ast::BinOp::Mod if ty.is_unsigned() => URem,
+            ast::BinOp::Mod => SRem,
+            ast::BinOp::Shl => Shl,
+            ast::BinOp::Shr if ty.is_unsigned() => LShr,
+            ast::BinOp::Shr => AShr,
+            ast::BinOp::BitAnd => And,
+            ast::BinOp::BitOr => Or,
+            ast::BinOp::BitXor => Xor,
+            ast::BinOp::Comma => return rhs,
+            ast::BinOp::EqCmp => return self.emit(Op::Cmp(Cond::Eq, lhs, rhs)),
+            ast::BinOp::NeqCmp => return self.emit(Op::Cmp(Cond::Ne, lhs, rhs)),
+            ast::BinOp::LowerCmp => {
+                return self.emit(Op::Cmp(cmp(Cond::Lt, Cond::ULt), lhs, rhs));
+            }
+            ast::BinOp::LowerEqCmp => {
+                return self.emit(Op::Cmp(cmp(Cond::Le, Cond::ULe), lhs, rhs));
+            }
+            ast::BinOp::GreaterCmp => {
+                return self.emit(Op::Cmp(cmp(Cond::Gt, Cond::UGt), lhs, rhs));
+            }
+            ast::BinOp::GreaterEqCmp => {
+                return self.emit(Op::Cmp(cmp(Cond::Ge, Cond::UGe), lhs, rhs));
+            }
+            ast::BinOp::LogAnd | ast::BinOp::LogOr => unreachable!(),
+        };
+        self.emit_at(Op::Binary(op, lhs, rhs), b.span.clone())
+    }
+
+    // the right operand is skipped once the left one decides the result:
+    // when it is 0 for `&&`, and when it is not for `||`
+    fn logical(&mut self, b: &BinaryExpr) -> Value {
+        let tmp = self.temp_slot();
+        let (rhs, end) = (self.new_block(), self.new_block());
+        let skipped = match b.op {
+            ast::BinOp::LogAnd => 0,
+            _ => 1,
+        };
+        let addr = self.emit(Op::SlotAddr(tmp));
+        let result = self.constant(skipped);
+        self.store(&Type::Long, addr, result);
+        let lhs = self.expr(&b.lhs);
+        match b.op {
+            ast::BinOp::LogAnd => self.branch(lhs, rhs, end),
+            _ => self.branch(lhs, end, rhs),
+        }
+        self.switch_to(rhs);
+        let value = self.expr(&b.rhs);
+        let zero = self.constant(0);
+        let value = self.emit(Op::Cmp(Cond::Ne, value, zero));
+        let addr = self.emit(Op::SlotAddr(tmp));
+        self.store(&Type::Long, addr, value);
+        self.jump_to(end);
+        let addr = self.emit(Op::SlotAddr(tmp));
+        self.load(&Type::Long, addr)
+    }
+
+    fn call(&mut self, call: &CallExpr, ret: &Type) -> Value {
+        let args = call.args.iter().map(|arg| self.expr(arg)).collect();
+        // functions known by name are called directly, anything else through a pointer
+        let callee = match &call.callee.kind {
+            ExpressionKind::Symbol(name) if matches!(call.callee.ty, Type::Func(_)) => {
+                Callee::Direct(name.clone())
+            }
+            _ => Callee::Indirect(self.expr(&call.callee)),
+        };
+        let value = self.emit(Op::Call { callee, args });
+        // only the low bits of the result are defined by the ABI
+        self.extend(ret, value)
+    }
+
+    // Named arguments took the first registers, `va_arg` continues after them and then
+    // with the arguments passed on the stack by the caller.
+    fn va_start(&mut self, ap: &Expression) -> Value {
+        let va_area = self
+            .func
+            .va_area()
+            .expect("va_start in a function with fixed arguments");
+        let ap = self.expr(ap);
+        let gp_offset = self.constant(self.func.params().len() as i64 * 8);
+        self.store(&Type::Int, ap, gp_offset);
+        let fp_offset = self.constant(GP_REGS_SIZE);
+        let addr = self.offset(ap, 4);
+        self.store(&Type::Int, addr, fp_offset);
+        let overflow_arg_area = self.emit(Op::StackArgs);
+        let addr = self.offset(ap, 8);
+        self.store(&Type::Long, addr, overflow_arg_area);
+        let reg_save_area = self.emit(Op::SlotAddr(Slot(va_area.0)));
+        let addr = self.offset(ap, 16);
+        self.store(&Type::Long, addr, reg_save_area);
+        self.constant(0)
+    }
+
+    // the address of the next argument
+    fn va_arg(&mut self, ap: &Expression) -> Value {
+        let tmp = self.temp_slot();
+        let (reg, stack, end) = (self.new_block(), self.new_block(), self.new_block());
+        let ap = self.expr(ap);
+        let gp_offset = self.load(&Type::UInt, ap);
+        let limit = self.constant(GP_REGS_SIZE);
+        let in_stack = self.emit(Op::Cmp(Cond::UGe, gp_offset, limit));
+        self.branch(in_stack, stack, reg);
+
+        self.switch_to(reg);
+        let addr = self.offset(ap, 16);
+        let reg_save_area = self.load(&Type::Long, addr);
+        let arg = self.emit(Op::Binary(ir::BinOp::Add, reg_save_area, gp_offset));
+        let next = self.offset(gp_offset, 8);
+        self.store(&Type::Int, ap, next);
+        let addr = self.emit(Op::SlotAddr(tmp));
+        self.store(&Type::Long, addr, arg);
+        self.terminate(Terminator::Jump(end));
+
+        self.switch_to(stack);
+        let addr = self.offset(ap, 8);
+        let arg = self.load(&Type::Long, addr);
+        let next = self.offset(arg, 8);
+        self.store(&Type::Long, addr, next);
+        let addr = self.emit(Op::SlotAddr(tmp));
+        self.store(&Type::Long, addr, arg);
+        self.jump_to(end);
+
+        let addr = self.emit(Op::SlotAddr(tmp));
+        self.load(&Type::Long, addr)
+    }
+}
+
+// how a scalar of type `ty` is held in memory
+fn mem_ty(ty: &Type) -> Ty {
+    match ty.size() {
+        1 => Ty::I8,
+        2 => Ty::I16,
+        4 => Ty::I32,
+        _ => Ty::I64,
+    }
+}
@@ -1,13 +1,241 @@
-use std::env;
-use toycc::run;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::{env, fs, process};
+use toycc::{
+    compile, compile_file, pass_names, preprocess, Diagnostic, Options, Output, SourceFile,
+};
 // use tree_sitter::{Language, Parser, TreeCursor};
 
+// What the driver stops at.
+#[derive(Copy, Clone, PartialEq)]
+enum Stage {
+    // `-E`: the preprocessed source
+    Preprocess,
+    // `-emit-ir`: the intermediate representation
+    EmitIr,
+    // `-S`: assembly
+    Assemble,
+    // `-c`: objects
+    Compile,
+    // an executable
+    Link,
+}
+
+// toycc [-E|-emit-ir|-S|-c] [-o output] [-I dir] [-isystem dir] [-D name[=value]] [-U name]
+//       [-O[level]] [-fno-<pass>] [-fdump-<pass>] [-Wunreachable-code] file...
+// Each `.c` file is compiled on its own, `-` reads one from the standard input. Objects, as
+// `.o` and `.a` files, are passed to the linker along with the compiled files.
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    assert_eq!(2, args.len());
-    match run(&args[1]) {
-        Ok(asm) => println!("{}", asm),
-        Err(e) => eprint!("{}", e),
+    let mut options = Options::default();
+    let mut stage = Stage::Link;
+    let mut output = None;
+    let mut system = vec![];
+    let mut inputs = vec![];
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "-E" {
+            stage = Stage::Preprocess;
+        } else if arg == "-emit-ir" {
+            stage = Stage::EmitIr;
+        } else if arg == "-S" {
+            stage = Stage::Assemble;
+        } else if arg == "-c" {
+            stage = Stage::Compile;
+        } else if arg == "-o" {
+            output = Some(args.next().unwrap_or_else(|| fail("missing file after -o")));
+        } else if arg == "-I" {
+            let dir = args
+                .next()
+                .unwrap_or_else(|| fail("missing directory after -I"));
+            options.include_paths.push(dir.into());
+        } else if let Some(dir) = arg.strip_prefix("-I") {
+            options.include_paths.push(dir.into());
+        } else if arg == "-D" || arg == "-U" {
+            // rather than taking the next option for a name
+            let name = args
+                .next()
+                .filter(|name| !name.starts_with('-'))
+                .unwrap_or_else(|| fail(&format!("missing macro name after {}", arg)));
+            options.macros.push(macro_option(&arg, &name));
+        } else if arg.starts_with("-D") || arg.starts_with("-U") {
+            options.macros.push(macro_option(&arg[..2], &arg[2..]));
+        } else if arg == "-O" {
+            options.opt_level = 1;
+        } else if let Some(level) = arg.strip_prefix("-O") {
+            // as with cc, the levels above the highest are that one
+            options.opt_level = level
+                .parse()
+                .unwrap_or_else(|_| fail(&format!("unknown option {}", arg)));
+        } else if let Some(pass) = arg.strip_prefix("-fno-") {
+            options.disabled_passes.push(pass_name(pass));
+        } else if let Some(pass) = arg.strip_prefix("-fdump-") {
+            options.dumped_passes.push(pass_name(pass));
+        } else if arg == "-Wunreachable-code" {
+            options.warn_unreachable_code = true;
+        } else if arg == "-isystem" {
+            let dir = args
+                .next()
+                .unwrap_or_else(|| fail("missing directory after -isystem"));
+            system.push(dir.into());
+        } else if arg.starts_with('-') && arg != "-" {
+            fail(&format!("unknown option {}", arg));
+        } else {
+            inputs.push(arg);
+        }
+    }
+    system.append(&mut options.system_include_paths);
+    options.system_include_paths = system;
+
+    if inputs.is_empty() {
+        fail("no input files");
+    }
+    let sources = inputs.iter().filter(|input| !is_object(input)).count();
+    if output.is_some() && stage != Stage::Link && sources > 1 {
+        fail("-o with -E, -emit-ir, -S or -c takes a single input file");
+    }
+
+    let mut objects = vec![];
+    let mut temps = vec![];
+    for input in &inputs {
+        if is_object(input) {
+            objects.push(PathBuf::from(input));
+            continue;
+        }
+        if stage == Stage::Preprocess {
+            let text = preprocess(read_source(input), &options).unwrap_or_else(|e| report(&e));
+            write_output(output.as_deref(), &text);
+            continue;
+        }
+        let out = if input == "-" {
+            compile(read_source(input), &options)
+        } else {
+            compile_file(input, &options)
+        };
+        let Output {
+            assembly,
+            ir,
+            warnings,
+        } = out.unwrap_or_else(|e| report(&e));
+        warnings.iter().for_each(|w| eprint!("{}", w));
+        match stage {
+            Stage::EmitIr => {
+                let default = (input != "-").then(|| output_name(input, "ir"));
+                write_output(output.as_deref().or_else(|| default.as_deref()), &ir);
+            }
+            Stage::Assemble => {
+                // from the standard input, the assembly goes to the standard output
+                let default = (input != "-").then(|| output_name(input, "s"));
+                write_output(output.as_deref().or_else(|| default.as_deref()), &assembly);
+            }
+            Stage::Compile => {
+                let object = output.clone().unwrap_or_else(|| output_name(input, "o"));
+                assemble(&assembly, Path::new(&object));
+            }
+            _ => {
+                let object = temp_path(temps.len(), "o");
+                assemble(&assembly, &object);
+                temps.push(object.clone());
+                objects.push(object);
+            }
+        }
+    }
+    if stage == Stage::Link {
+        let status = process::Command::new("cc")
+            .arg("-o")
+            .arg(output.as_deref().unwrap_or("a.out"))
+            .args(&objects)
+            .status();
+        temps.iter().for_each(|temp| drop(fs::remove_file(temp)));
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(_) => process::exit(1),
+            Err(e) => fail(&format!("cannot run the linker: {}", e)),
+        }
+    }
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("toycc: {}", msg);
+    process::exit(1);
+}
+
+fn report(diagnostics: &[Diagnostic]) -> ! {
+    for d in diagnostics {
+        eprint!("{}", d);
+    }
+    process::exit(1);
+}
+
+fn pass_name(name: &str) -> String {
+    if !pass_names().any(|pass| pass == name) {
+        let names: Vec<_> = pass_names().collect();
+        fail(&format!("unknown pass {}, not one of {}", name, names.join(", ")));
+    }
+    name.to_string()
+}
+
+fn is_object(input: &str) -> bool {
+    input.ends_with(".o") || input.ends_with(".a")
+}
+
+fn read_source(input: &str) -> SourceFile {
+    let contents = if input == "-" {
+        let mut contents = String::new();
+        io::stdin().read_to_string(&mut contents).unwrap();
+        contents
+    } else {
+        fs::read_to_string(input).unwrap_or_else(|e| fail(&format!("cannot open {}: {}", input, e)))
+    };
+    let name = if input == "-" { "<stdin>" } else { input };
+    SourceFile::new(name.to_string(), contents)
+}
+
+// `dir/file.c` gives `file.s` or `file.o` in the current directory, as with gcc
+fn output_name(input: &str, ext: &str) -> String {
+    let stem = Path::new(input).file_stem().unwrap_or_default();
+    format!("{}.{}", stem.to_string_lossy(), ext)
+}
+
+// `None` and `-` are the standard output
+fn write_output(path: Option<&str>, text: &str) {
+    match path {
+        None | Some("-") => print!("{}", text),
+        Some(path) => {
+            fs::write(path, text).unwrap_or_else(|e| fail(&format!("cannot write {}: {}", path, e)))
+        }
+    }
+}
+
+fn temp_path(n: usize, ext: &str) -> PathBuf {
+    env::temp_dir().join(format!("toycc-{}-{}.{}", process::id(), n, ext))
+}
+
+// runs the system assembler on `assembly`
+fn assemble(assembly: &str, object: &Path) {
+    let source = temp_path(usize::MAX, "s");
+    fs::write(&source, assembly)
+        .unwrap_or_else(|e| fail(&format!("cannot write {}: {}", source.display(), e)));
+    let status = process::Command::new("as")
+        .arg("-o")
+        .arg(object)
+        .arg(&source)
+        .status();
+    drop(fs::remove_file(&source));
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(_) => process::exit(1),
+        Err(e) => fail(&format!("cannot run the assembler: {}", e)),
+    }
+}
+
+// `-D name` defines `name` as 1
+fn macro_option(flag: &str, arg: &str) -> (String, Option<String>) {
+    if flag == "-U" {
+        return (arg.to_string(), None);
+    }
+    match arg.find('=') {
+        Some(i) => (arg[..i].to_string(), Some(arg[i + 1..].to_string())),
+        None => (arg.to_string(), Some("1".to_string())),
     }
 }
 
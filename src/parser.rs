@@ -1,54 +1,167 @@
 use crate::ast::*;
-use crate::tokenizer::TokenKind;
 use crate::tokenizer::TokenKind::*;
-use crate::{tokenizer::Token, Error};
+use crate::tokenizer::{integer_constant, TokenKind};
+use crate::types::{Param, StructType, Type};
+use crate::{tokenizer::Token, Error, ErrorKind, Span};
+use std::collections::{HashMap, HashSet};
+use std::mem;
+use std::rc::Rc;
 
-pub struct Parser<'a> {
-    code: &'a str,
+#[derive(Default)]
+struct Scope {
+    vars: Vec<(String, Var)>,
+    // structs, unions and enums
+    tags: Vec<(String, Type)>,
+}
+
+#[derive(Clone)]
+enum Var {
+    Local(LocalId),
+    // a global, or a function declared in a block, by its symbol
+    Symbol(String, Type),
+    Typedef(Type),
+    EnumConst(i64),
+}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Storage {
+    Auto,
+    Static,
+    Extern,
+    Typedef,
+}
+
+pub struct Parser {
     pub locals: Vec<Local>,
+    // the file scope followed by the block scopes of the current function, innermost last
+    scopes: Vec<Scope>,
+    // types of the functions seen so far
+    functions: HashMap<String, Type>,
+    globals: Vec<Global>,
+    // file scope names declared `static`
+    internal: HashSet<String>,
+    // what the declarations of each function ask of inlining it, and those of the current one
+    inlining: HashMap<String, Inlining>,
+    inline: Inlining,
+    strings: Vec<Vec<u8>>,
+    // the register save area of the current function, if it is variadic
+    va_area: Option<LocalId>,
+    // the name of the current function, which `__func__` holds
+    function_name: Option<String>,
+    // the labels of the enclosing switches, innermost last
+    switches: Vec<Vec<Option<i64>>>,
+    // the enclosing statements that `break` and `continue` leave
+    breakable: usize,
+    loops: usize,
+    // the labels of the current function, and the gotos to check against them
+    labels: Vec<String>,
+    gotos: Vec<(String, Token)>,
+    // reversed, with `Eof` first: it is never popped
     tokens: Vec<Token>,
 }
 
-impl<'a> Parser<'a> {
-    pub fn new(code: &'a str, tokens: Vec<Token>) -> Self {
+impl Parser {
+    pub fn new(tokens: Vec<Token>) -> Self {
         Self {
-            code,
             locals: vec![],
+            scopes: vec![],
+            functions: HashMap::new(),
+            globals: vec![],
+            internal: HashSet::new(),
+            inlining: HashMap::new(),
+            inline: Inlining::Default,
+            strings: vec![],
+            va_area: None,
+            function_name: None,
+            switches: vec![],
+            breakable: 0,
+            loops: 0,
+            labels: vec![],
+            gotos: vec![],
             tokens,
         }
     }
 
-    fn eof(&self) -> Token {
-        Token {
-            start: self.code.len(),
-            end: self.code.len() + 1,
-            kind: TokenKind::Eof,
+    fn error(&self, token: &Token, msg: &str) -> Error {
+        Error {
+            kind: ErrorKind::Generic(msg.to_string()),
+            span: Some(Span::new(token.file.clone(), token.start)),
         }
     }
 
     fn peek(&self) -> Token {
-        self.tokens.last().cloned().unwrap_or_else(|| self.eof())
+        self.tokens.last().cloned().unwrap()
     }
 
     fn next(&mut self) -> Token {
-        self.tokens.pop().unwrap_or_else(|| self.eof())
+        if self.tokens.len() == 1 {
+            return self.tokens[0].clone();
+        }
+        self.tokens.pop().unwrap()
     }
 
-    fn push_local(&mut self, name: String) -> LocalId {
-        let id = match self.locals.iter().position(|x| x.name() == &name) {
-            Some(x) => x,
-            None => {
-                self.locals.push(Local::new(name));
-                self.locals.len() - 1
-            }
-        };
-        LocalId(id)
+    // the kind of the n-th token after the next one
+    fn peek_kind_at(&self, n: usize) -> Option<&TokenKind> {
+        self.tokens
+            .len()
+            .checked_sub(n + 1)
+            .map(|i| &self.tokens[i].kind)
     }
 
-    fn consume(&mut self, kind: TokenKind) -> Token {
-        let next = self.next();
-        assert_eq!(kind, next.kind);
-        next
+    fn push_local(&mut self, name: String, ty: Type) -> LocalId {
+        self.locals.push(Local::new(name.clone(), ty));
+        let id = LocalId(self.locals.len() - 1);
+        self.push_var(name, Var::Local(id));
+        id
+    }
+
+    // a redeclaration in the same scope, as `extern int a[]; int a[3];`, replaces the previous one
+    fn push_var(&mut self, name: String, var: Var) {
+        let vars = &mut self.scopes.last_mut().unwrap().vars;
+        match vars.iter_mut().find(|(n, _)| *n == name) {
+            Some(v) => v.1 = var,
+            None => vars.push((name, var)),
+        }
+    }
+
+    fn find_var(&self, name: &str) -> Option<Var> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.vars.iter().rev())
+            .find(|(n, _)| n == name)
+            .map(|(_, var)| var.clone())
+    }
+
+    fn find_tag(&self, tag: &str) -> Option<Type> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.tags.iter().rev())
+            .find(|(name, _)| name == tag)
+            .map(|(_, ty)| ty.clone())
+    }
+
+    fn push_tag(&mut self, tag: String, ty: Type) {
+        self.scopes.last_mut().unwrap().tags.push((tag, ty));
+    }
+
+    fn consume(&mut self, kind: TokenKind) -> Result<Token, Error> {
+        let next = self.peek();
+        if next.kind != kind {
+            let msg = format!("expected '{}'", kind.spelling());
+            return Err(self.error(&next, &msg));
+        }
+        Ok(self.next())
+    }
+
+    // between the declarators of a declaration, which ends at a `;`
+    fn declarator_comma(&mut self) -> Result<(), Error> {
+        if self.skip(Comma) {
+            Ok(())
+        } else {
+            Err(self.error(&self.peek(), "expected ',' or ';'"))
+        }
     }
 
     fn skip(&mut self, kind: TokenKind) -> bool {
@@ -67,7 +180,8 @@ impl<'a> Parser<'a> {
     }
 
     fn binary(&mut self, lhs: Expression, min_bp: u8) -> Result<Expression, Error> {
-        let op = match self.consume_binary().kind {
+        let op_token = self.consume_binary();
+        let op = match op_token.kind {
             TokenKind::Lower => BinOp::LowerCmp,
             TokenKind::LowerEqual => BinOp::LowerEqCmp,
             TokenKind::Greater => BinOp::GreaterCmp,
@@ -77,25 +191,120 @@ impl<'a> Parser<'a> {
             TokenKind::Plus => BinOp::Add,
             TokenKind::Minus => BinOp::Sub,
             TokenKind::Slash => BinOp::Div,
+            TokenKind::Percent => BinOp::Mod,
             TokenKind::Star => BinOp::Mul,
+            TokenKind::LowerLower => BinOp::Shl,
+            TokenKind::GreaterGreater => BinOp::Shr,
+            TokenKind::Amp => BinOp::BitAnd,
+            TokenKind::Pipe => BinOp::BitOr,
+            TokenKind::Caret => BinOp::BitXor,
+            TokenKind::AmpAmp => BinOp::LogAnd,
+            TokenKind::PipePipe => BinOp::LogOr,
+            TokenKind::Comma => BinOp::Comma,
             k => unimplemented!("{:?}", k),
         };
         let rhs = self.expression(min_bp)?;
-        Ok(Expression::Binary(BinaryExpr {
-            op,
-            lhs: Box::new(lhs),
-            rhs: Box::new(rhs),
-        }))
+        self.new_binary_op(op, lhs, rhs, &op_token)
+    }
+
+    fn new_binary_op(
+        &self,
+        op: BinOp,
+        lhs: Expression,
+        rhs: Expression,
+        t: &Token,
+    ) -> Result<Expression, Error> {
+        let mut e = match op {
+            BinOp::Add => self.new_add(lhs, rhs, t),
+            BinOp::Sub => self.new_sub(lhs, rhs, t),
+            // the result of a shift has the type of its left operand
+            BinOp::Shl | BinOp::Shr => {
+                let ty = promote(&lhs.ty);
+                Ok(new_binary(op, lhs, rhs, ty))
+            }
+            BinOp::Mul | BinOp::Div | BinOp::Mod | BinOp::BitAnd | BinOp::BitOr | BinOp::BitXor => {
+                let (lhs, rhs) = usual_conversions(lhs, rhs);
+                let ty = lhs.ty.clone();
+                Ok(new_binary(op, lhs, rhs, ty))
+            }
+            BinOp::Comma => {
+                let ty = rhs.ty.decay();
+                Ok(new_binary(op, lhs, rhs, ty))
+            }
+            BinOp::LogAnd | BinOp::LogOr => Ok(new_binary(op, lhs, rhs, Type::Int)),
+            op => {
+                let (lhs, rhs) = usual_conversions(lhs, rhs);
+                Ok(new_binary(op, lhs, rhs, Type::Int))
+            }
+        }?;
+        if let ExpressionKind::Binary(b) = &mut e.kind {
+            b.span = Some(Span::new(t.file.clone(), t.start));
+        }
+        Ok(e)
+    }
+
+    // `p + n` is scaled by the size of the pointee, so that it points `n` elements further
+    fn new_add(&self, lhs: Expression, rhs: Expression, op: &Token) -> Result<Expression, Error> {
+        match (lhs.ty.base(), rhs.ty.base()) {
+            (None, None) => {
+                let (lhs, rhs) = usual_conversions(lhs, rhs);
+                let ty = lhs.ty.clone();
+                Ok(new_binary(BinOp::Add, lhs, rhs, ty))
+            }
+            (Some(_), Some(_)) => Err(self.error(op, "invalid operands")),
+            (None, Some(_)) => self.new_add(rhs, lhs, op),
+            (Some(base), None) => {
+                let size = number(base.size());
+                let ty = lhs.ty.decay();
+                let rhs = new_binary(BinOp::Mul, rhs, size, Type::Int);
+                Ok(new_binary(BinOp::Add, lhs, rhs, ty))
+            }
+        }
+    }
+
+    // `p - n` is scaled like `p + n`, `p - q` yields the number of elements between them
+    fn new_sub(&self, lhs: Expression, rhs: Expression, op: &Token) -> Result<Expression, Error> {
+        match (lhs.ty.base(), rhs.ty.base()) {
+            (None, None) => {
+                let (lhs, rhs) = usual_conversions(lhs, rhs);
+                let ty = lhs.ty.clone();
+                Ok(new_binary(BinOp::Sub, lhs, rhs, ty))
+            }
+            (None, Some(_)) => Err(self.error(op, "invalid operands")),
+            (Some(base), None) => {
+                let size = number(base.size());
+                let ty = lhs.ty.decay();
+                let rhs = new_binary(BinOp::Mul, rhs, size, Type::Int);
+                Ok(new_binary(BinOp::Sub, lhs, rhs, ty))
+            }
+            (Some(base), Some(_)) => {
+                let size = number(base.size());
+                let diff = new_binary(BinOp::Sub, lhs, rhs, Type::Long);
+                Ok(new_binary(BinOp::Div, diff, size, Type::Long))
+            }
+        }
     }
 
     // parens
     fn grouping(&mut self) -> Result<Expression, Error> {
-        self.consume(TokenKind::LeftParen);
+        self.consume(TokenKind::LeftParen)?;
         let expr = self.expression(0)?;
-        self.consume(TokenKind::RightParen);
+        self.consume(TokenKind::RightParen)?;
         Ok(expr)
     }
 
+    // `(type) expr`
+    fn cast(&mut self) -> Result<Expression, Error> {
+        let t = self.consume(LeftParen)?;
+        let ty = self.typename()?;
+        self.consume(RightParen)?;
+        let arg = self.expression(PREC_UNARY)?;
+        if ty.is_float() || arg.ty.is_float() {
+            return Err(self.error(&t, "floating-point values are not supported"));
+        }
+        Ok(new_cast(arg, ty))
+    }
+
     // fn primary(&mut self) -> Result<PrimaryNode, Error> {
     //     let t = self.next();
     //     match t.kind {
@@ -109,42 +318,246 @@ impl<'a> Parser<'a> {
     fn unary(&mut self) -> Result<Expression, Error> {
         let t = self.next();
         let ((), r_bp) = prefix_binding_power(&t.kind);
+        if t.kind == TokenKind::Sizeof {
+            return self.sizeof(r_bp);
+        }
         let lhs = self.expression(r_bp)?;
         let e = match &t.kind {
-            TokenKind::Plus => Expression::Unary(UnaryExpr {
-                op: UnaryOp::NoOp,
-                lhs: Box::new(lhs),
-            }),
-            TokenKind::Minus => Expression::Unary(UnaryExpr {
-                op: UnaryOp::Neg,
-                lhs: Box::new(lhs),
-            }),
-            TokenKind::Amp => Expression::Pointer(PointerExpr {
-                op: PointerOp::Ref,
-                arg: Box::new(lhs),
-            }),
-            TokenKind::Star => Expression::Pointer(PointerExpr {
+            TokenKind::Plus => {
+                let ty = promote(&lhs.ty);
+                Expression::new(
+                    ExpressionKind::Unary(UnaryExpr {
+                        op: UnaryOp::NoOp,
+                        lhs: Box::new(lhs),
+                    }),
+                    ty,
+                )
+            }
+            TokenKind::Minus => {
+                let ty = promote(&lhs.ty);
+                Expression::new(
+                    ExpressionKind::Unary(UnaryExpr {
+                        op: UnaryOp::Neg,
+                        lhs: Box::new(lhs),
+                    }),
+                    ty,
+                )
+            }
+            TokenKind::Amp => {
+                if !is_lvalue(&lhs) {
+                    return Err(self.error(&t, "not an lvalue"));
+                }
+                let ty = Type::pointer_to(lhs.ty.clone());
+                Expression::new(
+                    ExpressionKind::Pointer(PointerExpr {
+                        op: PointerOp::Ref,
+                        arg: Box::new(lhs),
+                    }),
+                    ty,
+                )
+            }
+            TokenKind::Star => self.new_deref(lhs, &t)?,
+            TokenKind::Not => Expression::new(
+                ExpressionKind::Unary(UnaryExpr {
+                    op: UnaryOp::Not,
+                    lhs: Box::new(lhs),
+                }),
+                Type::Int,
+            ),
+            TokenKind::Tilde => {
+                let ty = promote(&lhs.ty);
+                Expression::new(
+                    ExpressionKind::Unary(UnaryExpr {
+                        op: UnaryOp::BitNot,
+                        lhs: Box::new(lhs),
+                    }),
+                    ty,
+                )
+            }
+            // `++x` is `x += 1`
+            TokenKind::PlusPlus => self.compound_assignment(lhs, BinOp::Add, number(1), &t)?,
+            TokenKind::MinusMinus => self.compound_assignment(lhs, BinOp::Sub, number(1), &t)?,
+            k => unimplemented!("{:?}", k),
+        };
+        Ok(e)
+    }
+
+    // `x++` is `(x += 1) - 1`, converted back to the type of `x`
+    fn postfix_increment(&mut self, lhs: Expression) -> Result<Expression, Error> {
+        let t = self.next();
+        let (op, inverse) = match t.kind {
+            PlusPlus => (BinOp::Add, BinOp::Sub),
+            _ => (BinOp::Sub, BinOp::Add),
+        };
+        let ty = lhs.ty.clone();
+        let updated = self.compound_assignment(lhs, op, number(1), &t)?;
+        let value = self.new_binary_op(inverse, updated, number(1), &t)?;
+        Ok(new_cast(value, ty))
+    }
+
+    fn new_deref(&self, arg: Expression, t: &Token) -> Result<Expression, Error> {
+        // `*f` is the function `f` itself
+        if let Type::Func(_) = arg.ty {
+            return Ok(arg);
+        }
+        let ty = match arg.ty.base() {
+            Some(base) => base.clone(),
+            None => return Err(self.error(t, "invalid pointer dereference")),
+        };
+        Ok(Expression::new(
+            ExpressionKind::Pointer(PointerExpr {
                 op: PointerOp::Deref,
-                arg: Box::new(lhs),
+                arg: Box::new(arg),
             }),
-            k => unimplemented!("{:?}", k),
+            ty,
+        ))
+    }
+
+    // `sizeof` does not decay arrays: `sizeof(a)` is the size of the whole array
+    fn sizeof(&mut self, r_bp: u8) -> Result<Expression, Error> {
+        let ty = if self.peek().kind == LeftParen && self.is_typename_at(1) {
+            self.consume(LeftParen)?;
+            let ty = self.typename()?;
+            self.consume(RightParen)?;
+            ty
+        } else {
+            self.expression(r_bp)?.ty
+        };
+        Ok(number(ty.size()))
+    }
+
+    // `a[i]` is `*(a + i)`
+    fn subscript(&mut self, lhs: Expression) -> Result<Expression, Error> {
+        let t = self.consume(LeftBracket)?;
+        let index = self.expression(0)?;
+        self.consume(RightBracket)?;
+        let addr = self.new_add(lhs, index, &t)?;
+        self.new_deref(addr, &t)
+    }
+
+    // `p->x` is `(*p).x`
+    fn member(&mut self, lhs: Expression) -> Result<Expression, Error> {
+        let t = self.next();
+        let base = match t.kind {
+            Arrow => self.new_deref(lhs, &t)?,
+            _ => lhs,
         };
+        let st = match &base.ty {
+            Type::Struct(st) => st.clone(),
+            _ => return Err(self.error(&t, "not a struct")),
+        };
+        let t = self.next();
+        let member = match &t.kind {
+            Ident(name) => st.member(name),
+            _ => None,
+        };
+        let member = match member {
+            Some(member) => member,
+            None => return Err(self.error(&t, "no such member")),
+        };
+        Ok(Expression::new(
+            ExpressionKind::Member(MemberExpr {
+                base: Box::new(base),
+                offset: member.offset,
+            }),
+            member.ty,
+        ))
+    }
+
+    fn va_builtin(&mut self, builtin: VaBuiltin, t: &Token) -> Result<Expression, Error> {
+        self.consume(LeftParen)?;
+        let ap = self.expression(PREC_ASSIGNMENT)?;
+        if ap.ty.decay() != Type::va_list().decay() {
+            return Err(self.error(t, "expected a va_list"));
+        }
+        let e = match builtin {
+            VaBuiltin::Start => {
+                self.consume(Comma)?;
+                self.expression(PREC_ASSIGNMENT)?;
+                if self.va_area.is_none() {
+                    return Err(self.error(t, "va_start used in a function with fixed arguments"));
+                }
+                Expression::new(ExpressionKind::VaStart(Box::new(ap)), Type::Int)
+            }
+            VaBuiltin::Arg => {
+                self.consume(Comma)?;
+                let ty = self.typename()?;
+                Expression::new(ExpressionKind::VaArg(Box::new(ap)), ty)
+            }
+            // nothing to release
+            VaBuiltin::End => ap,
+            // `*dst = *src`
+            VaBuiltin::Copy => {
+                self.consume(Comma)?;
+                let src = self.expression(PREC_ASSIGNMENT)?;
+                let dst = self.new_deref(ap, t)?;
+                let src = self.new_deref(src, t)?;
+                let ty = dst.ty.clone();
+                Expression::new(
+                    ExpressionKind::Assignment(AssignmentExpr {
+                        lhs: Box::new(dst),
+                        rhs: Box::new(src),
+                        op: AssignmentOp::Eq,
+                    }),
+                    ty,
+                )
+            }
+        };
+        self.consume(RightParen)?;
         Ok(e)
     }
 
+    fn call(&mut self, callee: Expression) -> Result<Expression, Error> {
+        let t = self.consume(LeftParen)?;
+        let ty = match callee.ty.callee() {
+            Some(f) if f.ret.is_float() => {
+                return Err(self.error(&t, "floating-point values are not supported"))
+            }
+            Some(f) => f.ret.clone(),
+            None => return Err(self.error(&t, "not a function")),
+        };
+        let mut args = vec![];
+        while !self.skip(RightParen) {
+            if !args.is_empty() {
+                self.consume(Comma)?;
+            }
+            args.push(self.expression(PREC_ASSIGNMENT)?);
+        }
+        Ok(Expression::new(
+            ExpressionKind::Call(CallExpr {
+                callee: Box::new(callee),
+                args,
+            }),
+            ty,
+        ))
+    }
+
     fn assignment(&mut self, lhs: Expression, min_bp: u8) -> Result<Expression, Error> {
         // let mut lhs = Expression::Unary(self.expression(0)?);
-        let op = match self.next().kind {
-            TokenKind::Equal => AssignmentOp::Eq,
+        let t = self.next();
+        let op = match t.kind {
+            TokenKind::Equal => None,
+            TokenKind::PlusEqual => Some(BinOp::Add),
+            TokenKind::MinusEqual => Some(BinOp::Sub),
+            TokenKind::StarEqual => Some(BinOp::Mul),
+            TokenKind::SlashEqual => Some(BinOp::Div),
+            TokenKind::PercentEqual => Some(BinOp::Mod),
+            TokenKind::AmpEqual => Some(BinOp::BitAnd),
+            TokenKind::PipeEqual => Some(BinOp::BitOr),
+            TokenKind::CaretEqual => Some(BinOp::BitXor),
+            TokenKind::LowerLowerEqual => Some(BinOp::Shl),
+            TokenKind::GreaterGreaterEqual => Some(BinOp::Shr),
             k => unimplemented!("{:?}", k),
         };
 
+        if !is_lvalue(&lhs) {
+            return Err(self.error(&t, "not an lvalue"));
+        }
         let rhs = self.expression(min_bp)?;
-        Ok(Expression::Assignment(AssignmentExpr {
-            lhs: Box::new(lhs),
-            rhs: Box::new(rhs),
-            op
-        }))
+        match op {
+            Some(op) => self.compound_assignment(lhs, op, rhs, &t),
+            None => Ok(new_assignment(lhs, rhs)),
+        }
 
         // while self.peek().kind == TokenKind::Equal {
         //     let lvalue = if let Expression::Unary(ExprStmt::Primary(PrimaryNode::Ident(local))) = lhs {
@@ -152,7 +565,7 @@ impl<'a> Parser<'a> {
         //     } else {
         //         panic!("wrong LValue for an assignment: {:?}", lhs);
         //     };
-        //     self.consume(TokenKind::Equal);
+        //     self.consume(TokenKind::Equal)?;
         //     lhs = Expression::Assignment(AssignmentNode {
         //         lhs: lvalue,
         //         rhs: Box::new(self.assignment()?),
@@ -161,24 +574,150 @@ impl<'a> Parser<'a> {
         // Ok(lhs)
     }
 
+    // `a op= b` is `a = a op b`, with `a` evaluated once: unless it is a variable, its address
+    // is kept in a temporary, as in `tmp = &a, *tmp = *tmp op b`
+    fn compound_assignment(
+        &mut self,
+        lhs: Expression,
+        op: BinOp,
+        rhs: Expression,
+        t: &Token,
+    ) -> Result<Expression, Error> {
+        if !is_lvalue(&lhs) {
+            return Err(self.error(t, "not an lvalue"));
+        }
+        if let ExpressionKind::Identifier(_) | ExpressionKind::Symbol(_) = lhs.kind {
+            let value = self.new_binary_op(op, lhs.clone(), rhs, t)?;
+            return Ok(new_assignment(lhs, value));
+        }
+        let ty = lhs.ty.clone();
+        let ptr_ty = Type::pointer_to(ty.clone());
+        self.locals
+            .push(Local::new("__tmp__".to_string(), ptr_ty.clone()));
+        let tmp = LocalId(self.locals.len() - 1);
+        let tmp = Expression::new(ExpressionKind::Identifier(tmp), ptr_ty.clone());
+        let addr = Expression::new(
+            ExpressionKind::Pointer(PointerExpr {
+                op: PointerOp::Ref,
+                arg: Box::new(lhs),
+            }),
+            ptr_ty,
+        );
+        let save = new_assignment(tmp.clone(), addr);
+        let target = self.new_deref(tmp, t)?;
+        let value = self.new_binary_op(op, target.clone(), rhs, t)?;
+        let update = new_assignment(target, value);
+        Ok(new_binary(BinOp::Comma, save, update, ty))
+    }
+
+    // `c ? a : b`, after `c`
+    fn conditional(&mut self, condition: Expression, min_bp: u8) -> Result<Expression, Error> {
+        let t = self.consume(Question)?;
+        let then_expr = self.expression(0)?;
+        self.consume(Colon)?;
+        let else_expr = self.expression(min_bp)?;
+        let (then_ty, else_ty) = (then_expr.ty.decay(), else_expr.ty.decay());
+        let (then_expr, else_expr) = usual_conversions(then_expr, else_expr);
+        let ty = if then_ty.is_integer() && else_ty.is_integer() {
+            then_expr.ty.clone()
+        } else if then_ty.base().is_some() {
+            then_ty
+        } else if else_ty.base().is_some() {
+            else_ty
+        } else if then_ty == else_ty {
+            then_ty
+        } else {
+            return Err(self.error(&t, "incompatible operand types"));
+        };
+        Ok(Expression::new(
+            ExpressionKind::Conditional(ConditionalExpr {
+                condition: Box::new(condition),
+                then_expr: Box::new(then_expr),
+                else_expr: Box::new(else_expr),
+            }),
+            ty,
+        ))
+    }
+
     pub fn expression(&mut self, min_bp: u8) -> Result<Expression, Error> {
         let mut lhs = match self.peek().kind {
-            Num(v) => {
+            Num(spelling) => {
+                let t = self.next();
+                let (n, suffix) = integer_constant(&spelling)
+                    .ok_or_else(|| self.error(&t, "invalid integer constant"))?;
+                literal(n, &suffix, !spelling.starts_with('0'))
+            }
+            CharLit(c) => {
                 self.next();
-                Expression::NumberLiteral(v)
+                number(c as i8 as i64 as usize)
             }
             Ident(i) => {
+                let t = self.next();
+                if let (Some(builtin), LeftParen) = (va_builtin(&i), self.peek().kind) {
+                    self.va_builtin(builtin, &t)?
+                } else if let Some(var) = self.find_var(&i) {
+                    let e = match var {
+                        Var::Local(id) => {
+                            let ty = self.locals[id.0].ty().clone();
+                            Expression::new(ExpressionKind::Identifier(id), ty)
+                        }
+                        Var::Symbol(name, ty) => Expression::new(ExpressionKind::Symbol(name), ty),
+                        Var::EnumConst(v) => number(v as usize),
+                        Var::Typedef(_) => return Err(self.error(&t, "unexpected type name")),
+                    };
+                    if e.ty.is_float() {
+                        return Err(self.error(&t, "floating-point values are not supported"));
+                    }
+                    e
+                } else if let Some(ty) = self.functions.get(&i) {
+                    let ty = ty.clone();
+                    Expression::new(ExpressionKind::Symbol(i), ty)
+                } else if self.peek().kind == LeftParen {
+                    // implicitly declared as `int f()`
+                    let ty = Type::func(Type::Int, vec![], false);
+                    Expression::new(ExpressionKind::Symbol(i), ty)
+                } else if let (Some(name), "__func__") = (&self.function_name, i.as_str()) {
+                    // declared in the function scope on first use, as
+                    // `static const char __func__[] = "name";`
+                    let bytes = name.clone().into_bytes();
+                    let ty = Type::array_of(Type::Char, bytes.len() + 1);
+                    self.strings.push(bytes);
+                    let label = string_label(self.strings.len() - 1);
+                    self.scopes[1].vars.push((i, Var::Symbol(label.clone(), ty.clone())));
+                    Expression::new(ExpressionKind::Symbol(label), ty)
+                } else {
+                    return Err(self.error(&t, "undefined variable"));
+                }
+            }
+            Str(bytes) => {
                 self.next();
-                Expression::Identifier(self.push_local(i))
+                let ty = Type::array_of(Type::Char, bytes.len() + 1);
+                self.strings.push(bytes);
+                Expression::new(ExpressionKind::StringLiteral(self.strings.len() - 1), ty)
             }
     //         Num(_) | Ident(_) => ExprStmt::Primary(self.primary()?),
+            LeftParen if self.is_typename_at(1) => self.cast()?,
             LeftParen => self.grouping()?,
-            Plus | Minus | Star | Amp => self.unary()?,
-            k => unimplemented!("{:?}", k),
+            Plus | Minus | Star | Amp | Not | Tilde | PlusPlus | MinusMinus | Sizeof => {
+                self.unary()?
+            }
+            _ => return Err(self.error(&self.peek(), "expected an expression")),
         };
 
         loop {
             let next = self.peek().kind;
+            if let Some((l_bp, ())) = postfix_binding_power(&next) {
+                if l_bp < min_bp {
+                    break;
+                }
+                lhs = match next {
+                    LeftBracket => self.subscript(lhs)?,
+                    LeftParen => self.call(lhs)?,
+                    PlusPlus | MinusMinus => self.postfix_increment(lhs)?,
+                    _ => self.member(lhs)?,
+                };
+                continue;
+            }
     //         match next {
     //             Eof | RightParen | Semicolon | Equal => break,
     //             Star | Slash | Plus | Minus | EqualEqual | NotEqual | Lower | Greater
@@ -190,7 +729,11 @@ impl<'a> Parser<'a> {
                     break;
                 }
                 lhs = match next {
-                    Equal => self.assignment(lhs, r_bp)?,
+                    Equal | PlusEqual | MinusEqual | StarEqual | SlashEqual | PercentEqual
+                    | AmpEqual | PipeEqual | CaretEqual | LowerLowerEqual | GreaterGreaterEqual => {
+                        self.assignment(lhs, r_bp)?
+                    }
+                    Question => self.conditional(lhs, r_bp)?,
                     _ => self.binary(lhs, r_bp)?,
                 };
                 // if next.binary() {
@@ -210,48 +753,63 @@ impl<'a> Parser<'a> {
 
     // fn expr_stmt(&mut self) -> Result<Expression, Error> {
     //     let id = self.expr()?;
-    //     self.consume(TokenKind::Semicolon);
+    //     self.consume(TokenKind::Semicolon)?;
     //     Ok(id)
     // }
 
     fn statement(&mut self) -> Result<Statement, Error> {
         match self.peek().kind {
             TokenKind::While => {
-                self.consume(TokenKind::While);
-                self.consume(TokenKind::LeftParen);
+                self.consume(TokenKind::While)?;
+                self.consume(TokenKind::LeftParen)?;
                 let condition = Some(self.expression(0)?);
-                self.consume(TokenKind::RightParen);
-                let body = Box::new(self.statement()?);
+                self.consume(TokenKind::RightParen)?;
+                let body = Box::new(self.loop_body()?);
                 Ok(Statement::While(WhileStatement {
                     condition,
                     body,
                 }))
             }
+            TokenKind::Do => {
+                self.consume(TokenKind::Do)?;
+                let body = Box::new(self.loop_body()?);
+                self.consume(TokenKind::While)?;
+                self.consume(TokenKind::LeftParen)?;
+                let condition = Some(self.expression(0)?);
+                self.consume(TokenKind::RightParen)?;
+                self.consume(TokenKind::Semicolon)?;
+                Ok(Statement::DoWhile(WhileStatement { condition, body }))
+            }
             TokenKind::For => {
-                self.consume(TokenKind::For);
-                self.consume(TokenKind::LeftParen);
+                self.consume(TokenKind::For)?;
+                self.consume(TokenKind::LeftParen)?;
+                // a declaration in `init` is scoped to the loop
+                self.scopes.push(Scope::default());
                 let init = if self.skip(TokenKind::Semicolon) {
                     None
+                } else if self.is_typename() {
+                    Some(Box::new(self.declaration()?))
                 } else {
-                    let init = Some(self.expression(0)?);
-                    self.consume(TokenKind::Semicolon);
-                    init
+                    let init = self.expression(0)?;
+                    self.consume(TokenKind::Semicolon)?;
+                    Some(Box::new(Statement::Expr(init)))
                 };
                 let condition = if self.skip(TokenKind::Semicolon) {
                     None
                 } else {
                     let condition = Some(self.expression(0)?);
-                    self.consume(TokenKind::Semicolon);
+                    self.consume(TokenKind::Semicolon)?;
                     condition
                 };
                 let update = if self.skip(TokenKind::RightParen) {
                     None
                 } else {
                     let update = Some(self.expression(0)?);
-                    self.consume(TokenKind::RightParen);
+                    self.consume(TokenKind::RightParen)?;
                     update
                 };
-                let body = Box::new(self.statement()?);
+                let body = Box::new(self.loop_body()?);
+                self.scopes.pop();
                 Ok(Statement::For(ForStatement {
                     init,
                     condition,
@@ -260,10 +818,10 @@ impl<'a> Parser<'a> {
                 }))
             }
             TokenKind::If => {
-                self.consume(TokenKind::If);
-                self.consume(TokenKind::LeftParen);
+                self.consume(TokenKind::If)?;
+                self.consume(TokenKind::LeftParen)?;
                 let condition = self.expression(0)?;
-                self.consume(TokenKind::RightParen);
+                self.consume(TokenKind::RightParen)?;
                 let then_branch = Box::new(self.statement()?);
                 let else_branch = if self.skip(TokenKind::Else) {
                     let res = Some(Box::new(self.statement()?));
@@ -278,20 +836,87 @@ impl<'a> Parser<'a> {
                 }))
             }
             TokenKind::Return => {
-                self.consume(TokenKind::Return);
+                self.consume(TokenKind::Return)?;
+                if self.skip(TokenKind::Semicolon) {
+                    return Ok(Statement::Return(None));
+                }
                 let lhs = self.expression(0)?;
-                self.consume(TokenKind::Semicolon);
-                Ok(Statement::Return(lhs))
-            }
-            TokenKind::LeftCurly => {
-                let mut stmts = vec![];
-                self.consume(TokenKind::LeftCurly);
-                while self.peek().kind != TokenKind::RightCurly {
-                    stmts.push(self.statement()?);
+                self.consume(TokenKind::Semicolon)?;
+                Ok(Statement::Return(Some(lhs)))
+            }
+            TokenKind::Switch => {
+                self.consume(TokenKind::Switch)?;
+                self.consume(TokenKind::LeftParen)?;
+                let condition = self.expression(0)?;
+                self.consume(TokenKind::RightParen)?;
+                self.switches.push(vec![]);
+                self.breakable += 1;
+                let body = self.statement();
+                self.breakable -= 1;
+                let cases = self.switches.pop().unwrap();
+                Ok(Statement::Switch(SwitchStatement {
+                    condition,
+                    body: Box::new(body?),
+                    cases,
+                }))
+            }
+            TokenKind::Case | TokenKind::Default => {
+                let t = self.next();
+                let value = match t.kind {
+                    TokenKind::Case => Some(self.const_expr()?),
+                    _ => None,
+                };
+                self.consume(TokenKind::Colon)?;
+                let cases = match self.switches.last_mut() {
+                    Some(cases) => cases,
+                    None => return Err(self.error(&t, "stray case label")),
+                };
+                if cases.contains(&value) {
+                    return Err(self.error(&t, "duplicate case label"));
+                }
+                cases.push(value);
+                let index = cases.len() - 1;
+                let body = Box::new(self.statement()?);
+                Ok(Statement::Case(CaseStatement { index, body }))
+            }
+            TokenKind::Break => {
+                let t = self.consume(TokenKind::Break)?;
+                if self.breakable == 0 {
+                    return Err(self.error(&t, "stray break statement"));
+                }
+                self.consume(TokenKind::Semicolon)?;
+                Ok(Statement::Break)
+            }
+            TokenKind::Continue => {
+                let t = self.consume(TokenKind::Continue)?;
+                if self.loops == 0 {
+                    return Err(self.error(&t, "stray continue statement"));
                 }
-                self.consume(TokenKind::RightCurly);
-                Ok(Statement::Compound(CompoundStatement { stmts }))
+                self.consume(TokenKind::Semicolon)?;
+                Ok(Statement::Continue)
             }
+            TokenKind::Goto => {
+                self.consume(TokenKind::Goto)?;
+                let t = self.next();
+                let name = match &t.kind {
+                    Ident(name) => name.clone(),
+                    _ => return Err(self.error(&t, "expected a label")),
+                };
+                self.gotos.push((name.clone(), t));
+                self.consume(TokenKind::Semicolon)?;
+                Ok(Statement::Goto(name))
+            }
+            Ident(name) if self.peek_kind_at(1) == Some(&Colon) => {
+                let t = self.next();
+                self.consume(TokenKind::Colon)?;
+                if self.labels.contains(&name) {
+                    return Err(self.error(&t, "duplicate label"));
+                }
+                self.labels.push(name.clone());
+                let body = Box::new(self.statement()?);
+                Ok(Statement::Label(name, body))
+            }
+            TokenKind::LeftCurly => Ok(Statement::Compound(self.compound_statement()?)),
             TokenKind::Semicolon => {
                 loop {
                     if !self.skip(TokenKind::Semicolon) {
@@ -302,47 +927,1356 @@ impl<'a> Parser<'a> {
             }
             _ => {
                     let lhs = self.expression(0)?;
-                    self.consume(TokenKind::Semicolon);
+                    self.consume(TokenKind::Semicolon)?;
                     Ok(Statement::Expr(lhs))
             }
         }
     }
 
+    // the body of a loop, which `break` and `continue` leave
+    fn loop_body(&mut self) -> Result<Statement, Error> {
+        self.breakable += 1;
+        self.loops += 1;
+        let body = self.statement();
+        self.breakable -= 1;
+        self.loops -= 1;
+        body
+    }
+
+    // labels are visible in the whole function, before and after them
+    fn function_body(&mut self) -> Result<CompoundStatement, Error> {
+        let body = self.compound_statement()?;
+        let labels = mem::take(&mut self.labels);
+        for (name, t) in mem::take(&mut self.gotos) {
+            if !labels.contains(&name) {
+                return Err(self.error(&t, "use of undeclared label"));
+            }
+        }
+        Ok(body)
+    }
+
+    fn compound_statement(&mut self) -> Result<CompoundStatement, Error> {
+        let mut stmts = vec![];
+        self.consume(TokenKind::LeftCurly)?;
+        self.scopes.push(Scope::default());
+        while !matches!(self.peek().kind, RightCurly | Eof) {
+            let t = self.peek();
+            let stmt = if self.is_typename() {
+                self.declaration()?
+            } else {
+                self.statement()?
+            };
+            stmts.push((stmt, Span::new(t.file, t.start)));
+        }
+        self.scopes.pop();
+        self.consume(TokenKind::RightCurly)?;
+        Ok(CompoundStatement { stmts })
+    }
+
+    fn is_typename(&self) -> bool {
+        self.is_typename_at(0)
+    }
+
+    fn is_typename_at(&self, n: usize) -> bool {
+        match self.peek_kind_at(n) {
+            Some(Void) | Some(Bool) | Some(Char) | Some(Short) | Some(Int) | Some(Long)
+            | Some(Float) | Some(Double) | Some(Signed) | Some(Unsigned) | Some(Struct)
+            | Some(Union) | Some(Enum) | Some(Typedef) | Some(Const) | Some(Volatile)
+            | Some(Restrict) | Some(Inline) | Some(Noreturn) | Some(Static) | Some(Extern) => true,
+            Some(Ident(name)) => {
+                is_va_list(name)
+                    || is_attribute(name)
+                    || name == "__extension__"
+                    || matches!(self.find_var(name), Some(Var::Typedef(_)))
+            }
+            _ => false,
+        }
+    }
+
+    // Declaration specifiers, in any order, as `unsigned long int` or `char const`. The storage
+    // class is only allowed in declarations. Qualifiers and GNU attributes are accepted and ignored.
+    fn declspec_storage(&mut self) -> Result<(Type, Storage), Error> {
+        let start = self.peek();
+        let mut storage = Storage::Auto;
+        let mut counter = 0;
+        let mut other = None;
+        loop {
+            let t = self.peek();
+            let spec = match &t.kind {
+                Static | Extern | Typedef => {
+                    if storage != Storage::Auto {
+                        return Err(self.error(&t, "multiple storage classes"));
+                    }
+                    storage = match t.kind {
+                        Static => Storage::Static,
+                        Extern => Storage::Extern,
+                        _ => Storage::Typedef,
+                    };
+                    0
+                }
+                Inline => {
+                    self.inline = self.inline.max(Inlining::Hint);
+                    0
+                }
+                Const | Volatile | Restrict | Noreturn => 0,
+                Ident(name) if name == "__extension__" => 0,
+                Ident(name) if is_attribute(name) => {
+                    self.attributes()?;
+                    continue;
+                }
+                Void => VOID,
+                Bool => BOOL,
+                Char => CHAR,
+                Short => SHORT,
+                Int => INT,
+                Long => LONG,
+                Float => FLOAT,
+                Double => DOUBLE,
+                Signed => SIGNED,
+                Unsigned => UNSIGNED,
+                Struct | Union | Enum if counter == 0 => {
+                    self.next();
+                    other = Some(match t.kind {
+                        Enum => self.enum_decl()?,
+                        kind => self.struct_decl(kind == Union)?,
+                    });
+                    counter = OTHER;
+                    continue;
+                }
+                // a typedef name is the type only if there is none yet: in `typedef int T; int T;`
+                // the second `T` is the name being declared
+                Ident(name) if counter == 0 => match self.find_var(name) {
+                    Some(Var::Typedef(ty)) => {
+                        self.next();
+                        other = Some(ty);
+                        counter = OTHER;
+                        continue;
+                    }
+                    _ if is_va_list(name) => {
+                        self.next();
+                        other = Some(Type::va_list());
+                        counter = OTHER;
+                        continue;
+                    }
+                    _ => break,
+                },
+                _ => break,
+            };
+            self.next();
+            counter += spec;
+        }
+        let ty = match (counter, other) {
+            (0, _) => return Err(self.error(&start, "expected a type")),
+            (OTHER, Some(ty)) => ty,
+            (counter, _) => match spec_type(counter) {
+                Some(ty) => ty,
+                None => return Err(self.error(&start, "invalid type")),
+            },
+        };
+        Ok((ty, storage))
+    }
+
+    fn declspec(&mut self) -> Result<Type, Error> {
+        let t = self.peek();
+        match self.declspec_storage()? {
+            (ty, Storage::Auto) => Ok(ty),
+            _ => Err(self.error(&t, "storage class specifier not allowed here")),
+        }
+    }
+
+    // `__attribute__((...))`, possibly repeated. Only those about inlining are kept, for the
+    // function being declared.
+    fn attributes(&mut self) -> Result<(), Error> {
+        while let Ident(name) = self.peek().kind {
+            if !is_attribute(&name) {
+                break;
+            }
+            let t = self.next();
+            self.consume(LeftParen)?;
+            let (mut n, mut depth) = (0, 0);
+            let mut inlining = self.inline;
+            while let Some(kind) = self.peek_kind_at(n) {
+                match kind {
+                    LeftParen => depth += 1,
+                    RightParen if depth == 0 => break,
+                    RightParen => depth -= 1,
+                    Ident(name) => inlining = inlining.max(inline_attribute(name)),
+                    Eof => break,
+                    _ => {}
+                }
+                n += 1;
+            }
+            self.inline = inlining;
+            self.skip_parens(&t)?;
+        }
+        Ok(())
+    }
+
+    // skips to the `)` closing an already consumed `(`
+    fn skip_parens(&mut self, open: &Token) -> Result<(), Error> {
+        let mut depth = 0;
+        loop {
+            match self.next().kind {
+                LeftParen => depth += 1,
+                RightParen if depth == 0 => return Ok(()),
+                RightParen => depth -= 1,
+                Eof => return Err(self.error(open, "unclosed parenthesis")),
+                _ => {}
+            }
+        }
+    }
+
+    // `__asm__("name")` after a declarator gives the symbol of the function or variable,
+    // as glibc headers do to redirect functions to another version. Attributes may surround it.
+    fn asm_label(&mut self) -> Result<Option<String>, Error> {
+        self.attributes()?;
+        let t = self.peek();
+        match &t.kind {
+            Ident(name) if is_asm(name) => {}
+            _ => return Ok(None),
+        }
+        self.next();
+        self.consume(LeftParen)?;
+        let mut label = vec![];
+        while let Str(bytes) = self.peek().kind {
+            self.next();
+            label.extend(bytes);
+        }
+        self.consume(RightParen)?;
+        self.attributes()?;
+        match String::from_utf8(label) {
+            Ok(label) if !label.is_empty() => Ok(Some(label)),
+            _ => Err(self.error(&t, "invalid asm label")),
+        }
+    }
+
+    // `struct tag { members }`, or `struct tag` referring to an earlier declaration. A tag
+    // seen for the first time declares an incomplete struct, which can be defined later.
+    fn struct_decl(&mut self, is_union: bool) -> Result<Type, Error> {
+        self.attributes()?;
+        let t = self.peek();
+        let tag = match &t.kind {
+            Ident(tag) => {
+                self.next();
+                Some(tag.clone())
+            }
+            _ => None,
+        };
+        if let (Some(tag), false) = (&tag, self.peek().kind == LeftCurly) {
+            if let Some(ty) = self.find_tag(tag) {
+                return Ok(ty);
+            }
+            let st = Rc::new(StructType::declare(Some(tag.clone()), is_union));
+            self.push_tag(tag.clone(), Type::Struct(st.clone()));
+            return Ok(Type::Struct(st));
+        }
+
+        self.consume(LeftCurly)?;
+        // the definition completes an incomplete struct of the same scope, and the tag is
+        // known inside of it, as in `struct node { struct node *next; }`
+        let declared = tag.as_ref().and_then(|tag| {
+            self.scopes
+                .last()
+                .unwrap()
+                .tags
+                .iter()
+                .rev()
+                .find(|(name, _)| name == tag)
+                .map(|(_, ty)| ty.clone())
+        });
+        let st = match declared {
+            Some(Type::Struct(st)) if !st.is_complete() && st.is_union == is_union => st,
+            _ => {
+                let st = Rc::new(StructType::declare(tag.clone(), is_union));
+                if let Some(tag) = tag {
+                    self.push_tag(tag, Type::Struct(st.clone()));
+                }
+                st
+            }
+        };
+        let mut members = vec![];
+        while !self.skip(RightCurly) {
+            let base = self.declspec()?;
+            let mut first = true;
+            while !self.skip(Semicolon) {
+                if !first {
+                    self.declarator_comma()?;
+                }
+                first = false;
+                members.push(self.named_declarator(base.clone())?);
+                self.attributes()?;
+            }
+        }
+        let members = members.into_iter().map(|(ty, name)| (name, ty)).collect();
+        st.define(members);
+        self.attributes()?;
+        Ok(Type::Struct(st))
+    }
+
+    // `enum tag { A, B = 2 }` declares its constants in the enclosing scope. Enums are ints.
+    fn enum_decl(&mut self) -> Result<Type, Error> {
+        self.attributes()?;
+        let t = self.peek();
+        let tag = match &t.kind {
+            Ident(tag) => {
+                self.next();
+                Some(tag.clone())
+            }
+            _ => None,
+        };
+        if let (Some(tag), false) = (&tag, self.peek().kind == LeftCurly) {
+            return match self.find_tag(tag) {
+                Some(ty) => Ok(ty),
+                None => Err(self.error(&t, "unknown enum type")),
+            };
+        }
+
+        self.consume(LeftCurly)?;
+        let mut value = 0;
+        while !self.skip(RightCurly) {
+            let t = self.next();
+            let name = match t.kind {
+                Ident(name) => name,
+                _ => return Err(self.error(&t, "expected an enumerator")),
+            };
+            if self.skip(Equal) {
+                value = self.const_expr()?;
+            }
+            self.push_var(name, Var::EnumConst(value));
+            value += 1;
+            if !self.skip(Comma) {
+                self.consume(RightCurly)?;
+                break;
+            }
+        }
+        self.attributes()?;
+        if let Some(tag) = tag {
+            self.push_tag(tag, Type::Int);
+        }
+        Ok(Type::Int)
+    }
+
+    // an integer constant expression, as an array length or the value of an enumerator
+    fn const_expr(&mut self) -> Result<i64, Error> {
+        let t = self.peek();
+        let e = self.expression(PREC_ASSIGNMENT + 2)?;
+        match eval(&e) {
+            Some((None, v)) => Ok(v),
+            _ => Err(self.error(&t, "expected a constant expression")),
+        }
+    }
+
+    // The name is optional, as in parameters and type names.
+    fn declarator(&mut self, mut ty: Type) -> Result<(Type, Option<String>), Error> {
+        while self.skip(Star) {
+            ty = Type::pointer_to(ty);
+            loop {
+                match self.peek().kind {
+                    Const | Volatile | Restrict => {
+                        self.next();
+                    }
+                    Ident(name) if is_attribute(&name) => self.attributes()?,
+                    _ => break,
+                }
+            }
+        }
+        if self.peek().kind == LeftParen && self.is_nested_declarator() {
+            return self.nested_declarator(ty);
+        }
+        let name = match self.peek().kind {
+            Ident(name) => {
+                self.next();
+                Some(name)
+            }
+            _ => None,
+        };
+        let ty = self.type_suffix(ty)?;
+        Ok((ty, name))
+    }
+
+    // `(` starts a parameter list in `int (int)` or `int ()`, and a nested declarator in `int (*fp)()`
+    fn is_nested_declarator(&self) -> bool {
+        !matches!(self.peek_kind_at(1), Some(RightParen)) && !self.is_typename_at(1)
+    }
+
+    // In `int (*fp)(int)` the suffix applies first: `fp` is a pointer to `int (int)`.
+    // The tokens of the nested declarator are set aside until the suffix is parsed.
+    fn nested_declarator(&mut self, ty: Type) -> Result<(Type, Option<String>), Error> {
+        let open = self.consume(LeftParen)?;
+        let mut nested = vec![];
+        let mut depth = 0;
+        loop {
+            let t = self.next();
+            match t.kind {
+                LeftParen => depth += 1,
+                RightParen if depth == 0 => break,
+                RightParen => depth -= 1,
+                Eof => return Err(self.error(&open, "unclosed declarator")),
+                _ => {}
+            }
+            nested.push(t);
+        }
+        let ty = self.type_suffix(ty)?;
+
+        let rest = self.tokens.len();
+        self.tokens.extend(nested.into_iter().rev());
+        let result = self.declarator(ty)?;
+        if self.tokens.len() != rest {
+            return Err(self.error(&self.peek(), "invalid declarator"));
+        }
+        Ok(result)
+    }
+
+    fn named_declarator(&mut self, ty: Type) -> Result<(Type, String), Error> {
+        let t = self.peek();
+        match self.declarator(ty)? {
+            (ty, Some(name)) => Ok((ty, name)),
+            (_, None) => Err(self.error(&t, "expected a variable name")),
+        }
+    }
+
+    fn abstract_declarator(&mut self, ty: Type) -> Result<Type, Error> {
+        Ok(self.declarator(ty)?.0)
+    }
+
+    // `int a[2][3]` is an array of 2 arrays of 3 ints. `[]` leaves the length unknown (0).
+    fn type_suffix(&mut self, ty: Type) -> Result<Type, Error> {
+        if self.skip(LeftParen) {
+            return self.func_params(ty);
+        }
+        if !self.skip(LeftBracket) {
+            return Ok(ty);
+        }
+        // qualifiers of array parameters, as in `char *argv[restrict]`
+        while let Const | Volatile | Restrict | Static = self.peek().kind {
+            self.next();
+        }
+        let len = if self.peek().kind == RightBracket {
+            0
+        } else {
+            let t = self.peek();
+            match self.const_expr()? {
+                len if len >= 0 => len as usize,
+                _ => return Err(self.error(&t, "invalid array length")),
+            }
+        };
+        self.consume(RightBracket)?;
+        let ty = self.type_suffix(ty)?;
+        Ok(Type::array_of(ty, len))
+    }
+
+    // Array and function parameters are adjusted to pointers: `int a[10]` is passed as `int *a`.
+    fn func_params(&mut self, ret: Type) -> Result<Type, Error> {
+        let mut params = vec![];
+        let mut variadic = false;
+        // `(void)` takes no parameters
+        if self.peek().kind == Void && self.peek_kind_at(1) == Some(&RightParen) {
+            self.next();
+            self.next();
+            return Ok(Type::func(ret, params, variadic));
+        }
+        while !self.skip(RightParen) {
+            if !params.is_empty() {
+                self.consume(Comma)?;
+            }
+            if self.skip(Ellipsis) {
+                variadic = true;
+                self.consume(RightParen)?;
+                break;
+            }
+            let ty = self.declspec()?;
+            let (ty, name) = self.declarator(ty)?;
+            let ty = match ty {
+                Type::Array(base, _) => Type::Ptr(base),
+                ty @ Type::Func(_) => Type::pointer_to(ty),
+                ty => ty,
+            };
+            params.push(Param { name, ty });
+        }
+        Ok(Type::func(ret, params, variadic))
+    }
+
+    fn typename(&mut self) -> Result<Type, Error> {
+        let ty = self.declspec()?;
+        self.abstract_declarator(ty)
+    }
+
+    // Functions and `extern` variables declared in a block refer to symbols defined elsewhere,
+    // `static` variables are globals only visible in the block.
+    fn declaration(&mut self) -> Result<Statement, Error> {
+        let (base, storage) = self.declspec_storage()?;
+        let mut inits = vec![];
+        let mut first = true;
+        while !self.skip(Semicolon) {
+            if !first {
+                self.declarator_comma()?;
+            }
+            first = false;
+            let t = self.peek();
+            let (ty, name) = self.named_declarator(base.clone())?;
+            let label = self.asm_label()?;
+            if storage == Storage::Typedef {
+                self.push_var(name, Var::Typedef(ty));
+            } else if storage == Storage::Extern || matches!(ty, Type::Func(_)) {
+                let symbol = label.unwrap_or_else(|| name.clone());
+                self.push_var(name, Var::Symbol(symbol, ty));
+            } else if ty == Type::Void || ty.is_float() {
+                return Err(self.error(&t, "invalid variable type"));
+            } else if storage == Storage::Static {
+                let symbol = format!("{}.{}", name, self.globals.len());
+                self.push_var(name, Var::Symbol(symbol.clone(), ty.clone()));
+                self.global(symbol, ty, true)?;
+            } else {
+                let local = self.push_local(name, ty);
+                if self.skip(Equal) {
+                    inits.push(self.local_initializer(local)?);
+                }
+            }
+        }
+        Ok(Statement::Declaration(inits))
+    }
+
+    // `int a[] = {1, 2}` takes the length of `a` from its initializer
+    fn initializer(&mut self, ty: Type) -> Result<(Type, Vec<InitStore>), Error> {
+        let mut stores = vec![];
+        let len = self.init(&ty, 0, &mut stores)?;
+        let ty = match ty {
+            Type::Array(base, 0) => Type::Array(base, len),
+            ty => ty,
+        };
+        Ok((ty, stores))
+    }
+
+    fn local_initializer(&mut self, local: LocalId) -> Result<Initialization, Error> {
+        let ty = self.locals[local.0].ty().clone();
+        let (ty, stores) = self.initializer(ty)?;
+
+        let mut covered = vec![false; ty.size()];
+        for store in &stores {
+            let end = store.offset + store.ty.size();
+            covered[store.offset..end]
+                .iter_mut()
+                .for_each(|b| *b = true);
+        }
+        let zero_fill = covered.iter().any(|b| !b);
+
+        self.locals[local.0].ty = ty;
+        Ok(Initialization {
+            local,
+            zero_fill,
+            stores,
+        })
+    }
+
+    // The initializer of a global is evaluated at compile time into its bytes.
+    fn static_initializer(&mut self, ty: Type) -> Result<(Type, StaticInit), Error> {
+        let t = self.peek();
+        let (ty, stores) = self.initializer(ty)?;
+        let mut bytes = vec![0; ty.size()];
+        let mut relocs = vec![];
+        for store in stores {
+            let (label, value) = match eval(&store.value) {
+                Some(v) => v,
+                None => return Err(self.error(&t, "initializer element is not constant")),
+            };
+            let size = store.ty.size();
+            match label {
+                Some(label) if size == 8 => relocs.push(Reloc {
+                    offset: store.offset,
+                    label,
+                    addend: value,
+                }),
+                Some(_) => return Err(self.error(&t, "initializer element is not constant")),
+                None => bytes[store.offset..store.offset + size]
+                    .copy_from_slice(&value.to_le_bytes()[..size]),
+            }
+        }
+        relocs.sort_by_key(|r| r.offset);
+        Ok((ty, StaticInit { bytes, relocs }))
+    }
+
+    // Defines the global `name`, with its initializer if there is one. A file scope variable
+    // may be declared several times, but initialized only once.
+    fn global(&mut self, name: String, ty: Type, is_static: bool) -> Result<(), Error> {
+        let t = self.peek();
+        let (ty, init) = if self.skip(Equal) {
+            let (ty, init) = self.static_initializer(ty)?;
+            (ty, Some(init))
+        } else {
+            (ty, None)
+        };
+        self.push_var(name.clone(), Var::Symbol(name.clone(), ty.clone()));
+        match self.globals.iter_mut().find(|g| g.name == name) {
+            Some(g) if g.init.is_some() && init.is_some() => {
+                return Err(self.error(&t, "redefinition of a global"))
+            }
+            Some(g) => {
+                g.ty = ty;
+                g.is_static |= is_static;
+                if init.is_some() {
+                    g.init = init;
+                }
+            }
+            None => self.globals.push(Global {
+                name,
+                ty,
+                is_static,
+                init,
+            }),
+        }
+        Ok(())
+    }
+
+    fn is_string_init(&self, ty: &Type) -> bool {
+        matches!((ty, &self.peek().kind), (Type::Array(base, _), Str(_)) if **base == Type::Char)
+    }
+
+    // Initializes the object of type `ty` at `offset`, returning the number of elements of an array.
+    fn init(
+        &mut self,
+        ty: &Type,
+        offset: usize,
+        stores: &mut Vec<InitStore>,
+    ) -> Result<usize, Error> {
+        if self.is_string_init(ty) {
+            return Ok(self.string_init(ty, offset, stores));
+        }
+        let braced = self.skip(LeftCurly);
+        match ty {
+            Type::Array(base, len) if braced => self.array_init(base, *len, offset, stores, true),
+            Type::Struct(st) if braced => {
+                self.struct_init(st, offset, stores, true)?;
+                Ok(0)
+            }
+            Type::Array(..) => Err(self.error(&self.peek(), "expected an initializer list")),
+            // `int x = {3};`
+            _ if braced => {
+                self.init(ty, offset, stores)?;
+                self.skip(Comma);
+                self.consume(RightCurly)?;
+                Ok(0)
+            }
+            _ => {
+                let t = self.peek();
+                let value = self.expression(PREC_ASSIGNMENT)?;
+                if let (Type::Struct(_), false) = (ty, *ty == value.ty) {
+                    return Err(self.error(&t, "incompatible struct initializer"));
+                }
+                let value = implicit_cast(value, ty);
+                stores.push(InitStore {
+                    offset,
+                    ty: ty.clone(),
+                    value,
+                });
+                Ok(0)
+            }
+        }
+    }
+
+    // An aggregate element without braces takes its elements from the enclosing list.
+    fn element_init(
+        &mut self,
+        ty: &Type,
+        offset: usize,
+        stores: &mut Vec<InitStore>,
+    ) -> Result<(), Error> {
+        let braced = self.peek().kind == LeftCurly || self.is_string_init(ty);
+        match ty {
+            Type::Array(base, len) if !braced => {
+                self.array_init(base, *len, offset, stores, false)?;
+            }
+            Type::Struct(st) if !braced => self.struct_init(st, offset, stores, false)?,
+            _ => {
+                self.init(ty, offset, stores)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Whether the list has ended. Without braces, it also ends before a designator of the enclosing list.
+    fn init_list_end(&mut self, braced: bool, first: bool) -> Result<bool, Error> {
+        if braced {
+            if self.skip(RightCurly) {
+                return Ok(true);
+            }
+            if !first {
+                self.consume(Comma)?;
+            }
+            Ok(self.skip(RightCurly))
+        } else {
+            if first {
+                return Ok(false);
+            }
+            let end = self.peek().kind != Comma
+                || matches!(
+                    self.peek_kind_at(1),
+                    Some(RightCurly) | Some(Dot) | Some(LeftBracket)
+                );
+            if !end {
+                self.consume(Comma)?;
+            }
+            Ok(end)
+        }
+    }
+
+    // `.x.y = 1` or `[2][3] = 1` inside of an element selected by a designator
+    fn designation(&mut self, mut ty: Type, mut offset: usize) -> Result<(Type, usize), Error> {
+        loop {
+            let t = self.peek();
+            match (&t.kind, &ty) {
+                (Dot, Type::Struct(st)) => {
+                    self.next();
+                    let member = self.designated_member(st)?;
+                    offset += member.offset;
+                    ty = member.ty;
+                }
+                (LeftBracket, Type::Array(base, len)) => {
+                    self.next();
+                    let index = self.designated_index(*len)?;
+                    offset += index * base.size();
+                    ty = *base.clone();
+                }
+                (Equal, _) => {
+                    self.next();
+                    return Ok((ty, offset));
+                }
+                _ => return Err(self.error(&t, "invalid designator")),
+            }
+        }
+    }
+
+    fn designated_index(&mut self, len: usize) -> Result<usize, Error> {
+        let t = self.next();
+        let index = match &t.kind {
+            Num(spelling) => integer_constant(spelling).map(|(index, _)| index),
+            _ => None,
+        };
+        let index = match index {
+            Some(index) if len == 0 || index < len => index,
+            _ => return Err(self.error(&t, "invalid array designator")),
+        };
+        self.consume(RightBracket)?;
+        Ok(index)
+    }
+
+    fn designated_member(&mut self, st: &StructType) -> Result<crate::types::Member, Error> {
+        let t = self.next();
+        let member = match &t.kind {
+            Ident(name) => st.member(name),
+            _ => None,
+        };
+        member.ok_or_else(|| self.error(&t, "no such member"))
+    }
+
+    fn array_init(
+        &mut self,
+        base: &Type,
+        len: usize,
+        offset: usize,
+        stores: &mut Vec<InitStore>,
+        braced: bool,
+    ) -> Result<usize, Error> {
+        let mut index = 0;
+        let mut count = 0;
+        let mut first = true;
+        while !self.init_list_end(braced, first)? {
+            first = false;
+            if braced && self.skip(LeftBracket) {
+                index = self.designated_index(len)?;
+                let elem = offset + index * base.size();
+                let (ty, elem) = self.designation(base.clone(), elem)?;
+                self.element_init(&ty, elem, stores)?;
+            } else if len != 0 && index >= len {
+                if !braced {
+                    break;
+                }
+                return Err(self.error(&self.peek(), "excess elements in array initializer"));
+            } else {
+                self.element_init(base, offset + index * base.size(), stores)?;
+            }
+            index += 1;
+            count = count.max(index);
+            if !braced && index == len {
+                break;
+            }
+        }
+        Ok(count)
+    }
+
+    fn struct_init(
+        &mut self,
+        st: &StructType,
+        offset: usize,
+        stores: &mut Vec<InitStore>,
+        braced: bool,
+    ) -> Result<(), Error> {
+        let members = st.members();
+        // a union is initialized through a single member, the first one unless designated
+        let len = if st.is_union {
+            members.len().min(1)
+        } else {
+            members.len()
+        };
+        let mut index = 0;
+        let mut first = true;
+        while !self.init_list_end(braced, first)? {
+            first = false;
+            if braced && self.skip(Dot) {
+                let member = self.designated_member(st)?;
+                index = if st.is_union {
+                    0
+                } else {
+                    members.iter().position(|m| m.name == member.name).unwrap()
+                };
+                let (ty, elem) = self.designation(member.ty, offset + member.offset)?;
+                self.element_init(&ty, elem, stores)?;
+            } else {
+                let member = match members.get(index).filter(|_| index < len) {
+                    Some(member) => member.clone(),
+                    None if !braced => break,
+                    None => {
+                        return Err(
+                            self.error(&self.peek(), "excess elements in struct initializer")
+                        )
+                    }
+                };
+                self.element_init(&member.ty, offset + member.offset, stores)?;
+            }
+            index += 1;
+            if !braced && index == len {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    // `char s[] = "abc"` copies the string, including its terminating NUL if there is room
+    fn string_init(&mut self, ty: &Type, offset: usize, stores: &mut Vec<InitStore>) -> usize {
+        let mut bytes = match self.next().kind {
+            Str(bytes) => bytes,
+            _ => unreachable!(),
+        };
+        bytes.push(0);
+        let len = match ty {
+            Type::Array(_, 0) => bytes.len(),
+            Type::Array(_, len) => *len,
+            _ => unreachable!(),
+        };
+        for (i, b) in bytes.into_iter().take(len).enumerate() {
+            stores.push(InitStore {
+                offset: offset + i,
+                ty: Type::Char,
+                value: number(b as usize),
+            });
+        }
+        len
+    }
+
+    // A function definition, or declarations of functions and globals.
+    fn external_declaration(&mut self) -> Result<Option<Function>, Error> {
+        self.inline = Inlining::Default;
+        let (base, storage) = self.declspec_storage()?;
+        let mut first = true;
+        // `struct tag { ... };` only declares a type
+        while !self.skip(Semicolon) {
+            if !first {
+                self.declarator_comma()?;
+            }
+            let t = self.peek();
+            let (ty, name) = self.named_declarator(base.clone())?;
+            let label = self.asm_label()?;
+            if storage == Storage::Typedef {
+                self.push_var(name, Var::Typedef(ty));
+                first = false;
+                continue;
+            }
+            if storage == Storage::Static {
+                self.internal.insert(name.clone());
+            }
+            if let Some(label) = label {
+                self.push_var(name, Var::Symbol(label, ty));
+            } else if let Type::Func(_) = ty {
+                self.functions.insert(name.clone(), ty.clone());
+                let inlining = self.inlining.entry(name.clone()).or_default();
+                *inlining = (*inlining).max(self.inline);
+                if first && self.peek().kind == LeftCurly {
+                    return self.function(name, ty, &t).map(Some);
+                }
+            } else if storage == Storage::Extern && self.peek().kind != Equal {
+                self.push_var(name.clone(), Var::Symbol(name, ty));
+            } else if ty == Type::Void || ty.is_float() {
+                return Err(self.error(&t, "invalid variable type"));
+            } else {
+                let is_static = self.internal.contains(&name);
+                self.global(name, ty, is_static)?;
+            }
+            first = false;
+        }
+        Ok(None)
+    }
+
+    fn function(&mut self, name: String, ty: Type, t: &Token) -> Result<Function, Error> {
+        let func = match &ty {
+            Type::Func(func) => func.clone(),
+            _ => unreachable!(),
+        };
+        self.scopes.push(Scope::default());
+        let mut params = vec![];
+        for param in &func.params {
+            let name = match &param.name {
+                Some(name) => name.clone(),
+                None => return Err(self.error(t, "parameter name omitted")),
+            };
+            params.push(self.push_local(name, param.ty.clone()));
+        }
+        self.va_area = if func.variadic {
+            let ty = Type::array_of(Type::Char, VA_AREA_SIZE);
+            Some(self.push_local("__va_area__".to_string(), ty))
+        } else {
+            None
+        };
+        self.function_name = Some(name.clone());
+        let body = self.function_body()?;
+        self.function_name = None;
+        self.scopes.pop();
+
+        let locals = mem::take(&mut self.locals);
+        let va_area = self.va_area.take();
+        let is_static = self.internal.contains(&name);
+        let inlining = self.inlining[&name];
+        Ok(Function::new(
+            name, params, body, locals, va_area, is_static, inlining,
+        ))
+    }
+
     pub fn run(&mut self) -> Result<Program, Error> {
         let mut program = Program::default();
-        self.consume(TokenKind::LeftCurly);
-        while self.peek().kind != TokenKind::RightCurly {
-            let statement = self.statement()?;
-            program.stmt.stmts.push(statement);
+        self.scopes.push(Scope::default());
+        // a bare block is the body of `main`
+        if self.peek().kind == LeftCurly {
+            let ty = Type::func(Type::Int, vec![], false);
+            self.functions.insert("main".to_string(), ty);
+            self.scopes.push(Scope::default());
+            self.function_name = Some("main".to_string());
+            let body = self.function_body()?;
+            self.function_name = None;
+            self.scopes.pop();
+            let locals = mem::take(&mut self.locals);
+            program.functions.push(Function::new(
+                "main".to_string(),
+                vec![],
+                body,
+                locals,
+                None,
+                false,
+                Inlining::Default,
+            ));
         }
-        self.consume(TokenKind::RightCurly);
-        self.consume(TokenKind::Eof);
+        while self.peek().kind != TokenKind::Eof {
+            if let Some(function) = self.external_declaration()? {
+                program.functions.push(function);
+            }
+        }
+        self.consume(TokenKind::Eof)?;
+        program.globals = mem::take(&mut self.globals);
+        program.strings = mem::take(&mut self.strings);
 
         Ok(program)
     }
 }
 
+// general purpose registers followed by the vector registers, as saved in the prologue of variadic functions
+pub const VA_AREA_SIZE: usize = 6 * 8 + 8 * 16;
+
+#[derive(Copy, Clone)]
+enum VaBuiltin {
+    Start,
+    Arg,
+    End,
+    Copy,
+}
+
+// the `<stdarg.h>` macros are built in, under both their standard and GNU names
+fn va_builtin(name: &str) -> Option<VaBuiltin> {
+    let name = name.strip_prefix("__builtin_").unwrap_or(name);
+    match name {
+        "va_start" => Some(VaBuiltin::Start),
+        "va_arg" => Some(VaBuiltin::Arg),
+        "va_end" => Some(VaBuiltin::End),
+        "va_copy" => Some(VaBuiltin::Copy),
+        _ => None,
+    }
+}
+
+fn is_va_list(name: &str) -> bool {
+    name == "va_list" || name == "__builtin_va_list"
+}
+
+// what an attribute, as `always_inline` or `__noinline__`, asks of inlining
+fn inline_attribute(name: &str) -> Inlining {
+    match name.trim_start_matches("__").trim_end_matches("__") {
+        "always_inline" => Inlining::Always,
+        "noinline" => Inlining::Never,
+        _ => Inlining::Default,
+    }
+}
+
+fn is_attribute(name: &str) -> bool {
+    name == "__attribute__" || name == "__attribute"
+}
+
+fn is_asm(name: &str) -> bool {
+    name == "__asm__" || name == "__asm" || name == "asm"
+}
+
+// Type specifiers are counted, each one in its own bits, so that `long long` is told apart from
+// `long`. `OTHER` is a struct, union, enum or typedef name, which takes no other specifier.
+const VOID: u32 = 1;
+const BOOL: u32 = 1 << 2;
+const CHAR: u32 = 1 << 4;
+const SHORT: u32 = 1 << 6;
+const INT: u32 = 1 << 8;
+const LONG: u32 = 1 << 10;
+const FLOAT: u32 = 1 << 12;
+const DOUBLE: u32 = 1 << 14;
+const OTHER: u32 = 1 << 16;
+const SIGNED: u32 = 1 << 17;
+const UNSIGNED: u32 = 1 << 18;
+
+fn spec_type(counter: u32) -> Option<Type> {
+    let types = [
+        (VOID, Type::Void),
+        (BOOL, Type::Bool),
+        (CHAR, Type::Char),
+        (SIGNED + CHAR, Type::Char),
+        (UNSIGNED + CHAR, Type::UChar),
+        (SHORT, Type::Short),
+        (SHORT + INT, Type::Short),
+        (SIGNED + SHORT, Type::Short),
+        (SIGNED + SHORT + INT, Type::Short),
+        (UNSIGNED + SHORT, Type::UShort),
+        (UNSIGNED + SHORT + INT, Type::UShort),
+        (INT, Type::Int),
+        (SIGNED, Type::Int),
+        (SIGNED + INT, Type::Int),
+        (UNSIGNED, Type::UInt),
+        (UNSIGNED + INT, Type::UInt),
+        (LONG, Type::Long),
+        (LONG + INT, Type::Long),
+        (LONG + LONG, Type::Long),
+        (LONG + LONG + INT, Type::Long),
+        (SIGNED + LONG, Type::Long),
+        (SIGNED + LONG + INT, Type::Long),
+        (SIGNED + LONG + LONG, Type::Long),
+        (SIGNED + LONG + LONG + INT, Type::Long),
+        (UNSIGNED + LONG, Type::ULong),
+        (UNSIGNED + LONG + INT, Type::ULong),
+        (UNSIGNED + LONG + LONG, Type::ULong),
+        (UNSIGNED + LONG + LONG + INT, Type::ULong),
+        (FLOAT, Type::Float),
+        (DOUBLE, Type::Double),
+        (LONG + DOUBLE, Type::LongDouble),
+    ];
+    types
+        .iter()
+        .find(|(c, _)| *c == counter)
+        .map(|(_, ty)| ty.clone())
+}
+
+// Evaluates a constant expression: a number, or the address of a symbol plus an offset.
+fn eval(e: &Expression) -> Option<(Option<String>, i64)> {
+    match &e.kind {
+        ExpressionKind::NumberLiteral(n) => Some((None, *n as i64)),
+        ExpressionKind::Binary(b) => {
+            let (lhs_label, lhs) = eval(&b.lhs)?;
+            let (rhs_label, rhs) = eval(&b.rhs)?;
+            match (b.op, lhs_label, rhs_label) {
+                (BinOp::Add, label, None) | (BinOp::Add, None, label) => {
+                    Some((label, lhs.wrapping_add(rhs)))
+                }
+                (BinOp::Sub, label, None) => Some((label, lhs.wrapping_sub(rhs))),
+                (op, None, None) => {
+                    let v = eval_binary(op, lhs, rhs, b.lhs.ty.is_unsigned())?;
+                    Some((None, truncate(v, &e.ty)))
+                }
+                _ => None,
+            }
+        }
+        ExpressionKind::Conditional(c) => match eval(&c.condition)? {
+            (None, 0) => eval(&c.else_expr),
+            (None, _) => eval(&c.then_expr),
+            _ => None,
+        },
+        ExpressionKind::Cast(arg) => {
+            let (label, v) = eval(arg)?;
+            match label {
+                Some(_) if e.ty.size() == 8 => Some((label, v)),
+                Some(_) => None,
+                None => Some((None, truncate(v, &e.ty))),
+            }
+        }
+        ExpressionKind::Unary(u) => {
+            let (label, v) = eval(&u.lhs)?;
+            match (u.op, label) {
+                (UnaryOp::Neg, None) => Some((None, v.wrapping_neg())),
+                (UnaryOp::Not, None) => Some((None, (v == 0) as i64)),
+                (UnaryOp::BitNot, None) => Some((None, !v)),
+                (UnaryOp::NoOp, label) => Some((label, v)),
+                _ => None,
+            }
+        }
+        ExpressionKind::Pointer(PointerExpr {
+            op: PointerOp::Ref,
+            arg,
+        }) => eval_addr(arg),
+        // arrays and functions evaluate to their address
+        _ if e.ty.is_aggregate() || matches!(e.ty, Type::Func(_)) => eval_addr(e),
+        _ => None,
+    }
+}
+
+fn eval_addr(e: &Expression) -> Option<(Option<String>, i64)> {
+    match &e.kind {
+        ExpressionKind::Symbol(name) => Some((Some(name.clone()), 0)),
+        ExpressionKind::StringLiteral(id) => Some((Some(string_label(*id)), 0)),
+        ExpressionKind::Member(m) => {
+            let (label, v) = eval_addr(&m.base)?;
+            Some((label, v + m.offset as i64))
+        }
+        ExpressionKind::Pointer(PointerExpr {
+            op: PointerOp::Deref,
+            arg,
+        }) => eval(arg),
+        _ => None,
+    }
+}
+
+// `unsigned` operands, converted to the same type, compare and divide as unsigned values
+fn eval_binary(op: BinOp, lhs: i64, rhs: i64, unsigned: bool) -> Option<i64> {
+    let (x, y) = (lhs as u64, rhs as u64);
+    let v = match op {
+        BinOp::Add => lhs.wrapping_add(rhs),
+        BinOp::Sub => lhs.wrapping_sub(rhs),
+        BinOp::Mul => lhs.wrapping_mul(rhs),
+        BinOp::Div | BinOp::Mod if rhs == 0 => return None,
+        BinOp::Div if unsigned => (x / y) as i64,
+        BinOp::Div => lhs.wrapping_div(rhs),
+        BinOp::Mod if unsigned => (x % y) as i64,
+        BinOp::Mod => lhs.wrapping_rem(rhs),
+        BinOp::EqCmp => (lhs == rhs) as i64,
+        BinOp::NeqCmp => (lhs != rhs) as i64,
+        BinOp::LowerCmp if unsigned => (x < y) as i64,
+        BinOp::LowerCmp => (lhs < rhs) as i64,
+        BinOp::LowerEqCmp if unsigned => (x <= y) as i64,
+        BinOp::LowerEqCmp => (lhs <= rhs) as i64,
+        BinOp::GreaterCmp if unsigned => (x > y) as i64,
+        BinOp::GreaterCmp => (lhs > rhs) as i64,
+        BinOp::GreaterEqCmp if unsigned => (x >= y) as i64,
+        BinOp::GreaterEqCmp => (lhs >= rhs) as i64,
+        BinOp::Shl => lhs.wrapping_shl(rhs as u32),
+        BinOp::Shr if unsigned => x.wrapping_shr(rhs as u32) as i64,
+        BinOp::Shr => lhs.wrapping_shr(rhs as u32),
+        BinOp::BitAnd => lhs & rhs,
+        BinOp::BitOr => lhs | rhs,
+        BinOp::BitXor => lhs ^ rhs,
+        BinOp::LogAnd => (lhs != 0 && rhs != 0) as i64,
+        BinOp::LogOr => (lhs != 0 || rhs != 0) as i64,
+        // not a constant expression, even with constant operands
+        BinOp::Comma => return None,
+    };
+    Some(v)
+}
+
+// the value of `v` converted to the integer type `ty`
+fn truncate(v: i64, ty: &Type) -> i64 {
+    match ty {
+        Type::Bool => (v != 0) as i64,
+        Type::Char => v as i8 as i64,
+        Type::UChar => v as u8 as i64,
+        Type::Short => v as i16 as i64,
+        Type::UShort => v as u16 as i64,
+        Type::Int => v as i32 as i64,
+        Type::UInt => v as u32 as i64,
+        _ => v,
+    }
+}
+
+fn number(n: usize) -> Expression {
+    Expression::new(ExpressionKind::NumberLiteral(n), Type::Int)
+}
+
+// An integer literal has the first type of `int`, `unsigned int`, `long` and `unsigned long` that
+// holds its value, skipping the unsigned ones for decimal literals without a `u` suffix, and those
+// smaller than `long` with an `l` one, as in C11 6.4.4.1.
+fn literal(n: usize, suffix: &str, decimal: bool) -> Expression {
+    let (unsigned, long) = (suffix.contains('u'), suffix.contains('l'));
+    let ty = if !unsigned && !long && n <= i32::MAX as usize {
+        Type::Int
+    } else if (unsigned || !decimal) && !long && n <= u32::MAX as usize {
+        Type::UInt
+    } else if !unsigned && n <= i64::MAX as usize {
+        Type::Long
+    } else {
+        Type::ULong
+    };
+    Expression::new(ExpressionKind::NumberLiteral(n), ty)
+}
+
+fn new_cast(arg: Expression, ty: Type) -> Expression {
+    Expression::new(ExpressionKind::Cast(Box::new(arg)), ty)
+}
+
+// Values are converted to the type they are assigned to by the stores, which keep their low
+// bytes. Only `_Bool` needs a conversion of its own: any value but 0 is stored as 1.
+fn implicit_cast(value: Expression, ty: &Type) -> Expression {
+    match ty {
+        Type::Bool if value.ty != Type::Bool => new_cast(value, Type::Bool),
+        _ => value,
+    }
+}
+
+// the integer promotions: types smaller than `int` are computed as `int`
+fn promote(ty: &Type) -> Type {
+    match ty {
+        ty if ty.is_integer() && ty.size() < 4 => Type::Int,
+        ty if ty.is_integer() => ty.clone(),
+        Type::Ptr(_) => Type::Long,
+        _ => Type::Int,
+    }
+}
+
+// the usual arithmetic conversions: the larger type of the promoted operands, and the unsigned one
+// if they have the same size
+fn arith_type(lhs: &Type, rhs: &Type) -> Type {
+    let (lhs, rhs) = (promote(lhs), promote(rhs));
+    if lhs.size() != rhs.size() {
+        return if lhs.size() > rhs.size() { lhs } else { rhs };
+    }
+    if rhs.is_unsigned() {
+        rhs
+    } else {
+        lhs
+    }
+}
+
+// Both operands converted to their common type, when they are integers, so that `-1 < 1u` compares
+// unsigned values and `-8 / 2u` divides them.
+fn usual_conversions(lhs: Expression, rhs: Expression) -> (Expression, Expression) {
+    if !lhs.ty.is_integer() || !rhs.ty.is_integer() {
+        return (lhs, rhs);
+    }
+    let ty = arith_type(&lhs.ty, &rhs.ty);
+    // promoted values are already held as `int`
+    let convert = |e: Expression| {
+        if promote(&e.ty) == ty {
+            e
+        } else {
+            new_cast(e, ty.clone())
+        }
+    };
+    (convert(lhs), convert(rhs))
+}
+
+// whether `e` designates an object, whose address can be taken
+fn is_lvalue(e: &Expression) -> bool {
+    match &e.kind {
+        ExpressionKind::Identifier(_)
+        | ExpressionKind::Symbol(_)
+        | ExpressionKind::StringLiteral(_) => true,
+        ExpressionKind::Member(m) => is_lvalue(&m.base),
+        ExpressionKind::Pointer(p) => matches!(p.op, PointerOp::Deref),
+        _ => false,
+    }
+}
+
+fn new_assignment(lhs: Expression, rhs: Expression) -> Expression {
+    let ty = lhs.ty.clone();
+    let rhs = implicit_cast(rhs, &ty);
+    Expression::new(
+        ExpressionKind::Assignment(AssignmentExpr {
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+            op: AssignmentOp::Eq,
+        }),
+        ty,
+    )
+}
+
+fn new_binary(op: BinOp, lhs: Expression, rhs: Expression, ty: Type) -> Expression {
+    Expression::new(
+        ExpressionKind::Binary(BinaryExpr {
+            op,
+            lhs: Box::new(lhs),
+            rhs: Box::new(rhs),
+            span: None,
+        }),
+        ty,
+    )
+}
+
 // https://en.cppreference.com/w/c/language/operator_precedence
 const TOTAL: u8 = 15;
+const PREC_POSTFIX: u8 = TOTAL - 1;
 const PREC_UNARY: u8 = TOTAL - 2;
 const PREC_FACTOR: u8 = TOTAL - 3;
 const PREC_TERM: u8 = TOTAL - 4;
+const PREC_SHIFT: u8 = TOTAL - 5;
 const PREC_RELATIONAL: u8 = TOTAL - 6;
+const PREC_BIT_AND: u8 = TOTAL - 8;
+const PREC_BIT_XOR: u8 = TOTAL - 9;
+const PREC_BIT_OR: u8 = TOTAL - 10;
+const PREC_LOG_AND: u8 = TOTAL - 11;
+const PREC_LOG_OR: u8 = TOTAL - 12;
+const PREC_CONDITIONAL: u8 = TOTAL - 13;
 const PREC_ASSIGNMENT: u8 = TOTAL - 14;
+const PREC_COMMA: u8 = TOTAL - 15;
 
 fn prefix_binding_power(t: &TokenKind) -> ((), u8) {
     match t {
-        TokenKind::Plus | TokenKind::Minus | TokenKind::Star | TokenKind::Amp => ((), PREC_UNARY),
+        TokenKind::Plus
+        | TokenKind::Minus
+        | TokenKind::Star
+        | TokenKind::Amp
+        | TokenKind::Not
+        | TokenKind::Tilde
+        | TokenKind::PlusPlus
+        | TokenKind::MinusMinus
+        | TokenKind::Sizeof => ((), PREC_UNARY),
         t => unimplemented!("{:?}", t),
     }
 }
 
+fn postfix_binding_power(t: &TokenKind) -> Option<(u8, ())> {
+    match t {
+        TokenKind::LeftBracket
+        | TokenKind::LeftParen
+        | TokenKind::Dot
+        | TokenKind::Arrow
+        | TokenKind::PlusPlus
+        | TokenKind::MinusMinus => Some((PREC_POSTFIX, ())),
+        _ => None,
+    }
+}
+
 fn infix_binding_power(t: &TokenKind) -> Option<(u8, u8)> {
     use TokenKind::*;
     let res = match t {
-        Equal => (PREC_ASSIGNMENT + 1, PREC_ASSIGNMENT),
+        // below the assignments, which are left to the callers that take a single expression
+        Comma => (PREC_COMMA, PREC_COMMA + 1),
+        Equal | PlusEqual | MinusEqual | StarEqual | SlashEqual | PercentEqual | AmpEqual
+        | PipeEqual | CaretEqual | LowerLowerEqual | GreaterGreaterEqual => {
+            (PREC_ASSIGNMENT + 1, PREC_ASSIGNMENT)
+        }
+        // right associative, above the assignment it cannot contain
+        Question => (PREC_CONDITIONAL + 1, PREC_CONDITIONAL + 1),
         Plus | Minus => (PREC_TERM, PREC_TERM + 1),
-        Star | Slash => (PREC_FACTOR, PREC_FACTOR + 1),
+        Star | Slash | Percent => (PREC_FACTOR, PREC_FACTOR + 1),
+        LowerLower | GreaterGreater => (PREC_SHIFT, PREC_SHIFT + 1),
+        Amp => (PREC_BIT_AND, PREC_BIT_AND + 1),
+        Caret => (PREC_BIT_XOR, PREC_BIT_XOR + 1),
+        Pipe => (PREC_BIT_OR, PREC_BIT_OR + 1),
+        AmpAmp => (PREC_LOG_AND, PREC_LOG_AND + 1),
+        PipePipe => (PREC_LOG_OR, PREC_LOG_OR + 1),
         EqualEqual | NotEqual | Lower | Greater | LowerEqual | GreaterEqual => {
             (PREC_RELATIONAL, PREC_RELATIONAL + 1)
         }
@@ -353,44 +2287,63 @@ fn infix_binding_power(t: &TokenKind) -> Option<(u8, u8)> {
 
 #[derive(Clone, Debug)]
 pub struct Function {
+    name: String,
+    params: Vec<LocalId>,
     stmt: CompoundStatement,
     pub locals: Vec<Local>,
-    stack_size: usize,
+    va_area: Option<LocalId>,
+    // internal linkage: the symbol is not exported
+    is_static: bool,
+    inlining: Inlining,
 }
 
 impl Function {
-    pub fn new(program: Program, locals: Vec<Local>) -> Self {
-        let mut result = Self {
-            stmt: program.stmt,
+    pub fn new(
+        name: String,
+        params: Vec<LocalId>,
+        stmt: CompoundStatement,
+        locals: Vec<Local>,
+        va_area: Option<LocalId>,
+        is_static: bool,
+        inlining: Inlining,
+    ) -> Self {
+        Self {
+            name,
+            params,
+            stmt,
             locals,
-            stack_size: 0,
-        };
-        result.assign_locals_offsets();
-        result
+            va_area,
+            is_static,
+            inlining,
+        }
     }
 
-    pub fn stack_size(&self) -> usize {
-        self.stack_size
+    pub fn name(&self) -> &str {
+        &self.name
     }
 
-    pub fn body(&self) -> &CompoundStatement {
-        &self.stmt
+    pub fn params(&self) -> &[LocalId] {
+        &self.params
     }
 
-    pub fn local(&self, id: LocalId) -> &Local {
-        &self.locals[id.0]
+    /// Where the registers of a variadic function are saved for `va_arg`.
+    pub fn va_area(&self) -> Option<LocalId> {
+        self.va_area
     }
 
-    fn assign_locals_offsets(&mut self) {
-        let mut total = 0;
-        for l in self.locals.iter_mut().rev() {
-            total += 8;
-            l.offset = total;
-        }
-        self.stack_size = Self::align_stack_size(total, 16)
+    pub fn is_static(&self) -> bool {
+        self.is_static
     }
 
-    fn align_stack_size(size: usize, base_align: usize) -> usize {
-        base_align * ((size + base_align - 1) / base_align)
+    pub fn inlining(&self) -> Inlining {
+        self.inlining
+    }
+
+    pub fn body(&self) -> &CompoundStatement {
+        &self.stmt
+    }
+
+    pub fn local(&self, id: LocalId) -> &Local {
+        &self.locals[id.0]
     }
 }
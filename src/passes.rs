@@ -0,0 +1,119 @@
+//! The passes over the IR of the whole program, then of each function, then over the code of
+//! each function, and the optimisation levels they run at.
+//!
+//! At `-O0` none of them runs, and each local stays in its stack slot as the lowering put it.
+//! A pass can be left out with `-fno-<name>`, and the IR or code of each function written out
+//! before and after it with `-fdump-<name>`. The dumps go to the standard error as the passes
+//! run, so that those of a pass that breaks the IR are there when the compiler panics.
+
+use crate::ir::{Function, Program};
+use crate::x86::Listing;
+use crate::{cfg, dce, fold, inline, loops, peephole, ssa, verify, Options};
+use std::fmt::Display;
+
+struct Pass<T> {
+    name: &'static str,
+    // the lowest `-O` level it runs at
+    level: u32,
+    run: fn(&mut T),
+}
+
+// over the functions as they are lowered, before the others
+const INLINE: Pass<Program> = Pass {
+    name: "inline",
+    level: 2,
+    run: inline::inline,
+};
+
+// in the order they run
+const PASSES: [Pass<Function>; 7] = [
+    Pass {
+        name: "loop-rotate",
+        level: 2,
+        run: loops::rotate,
+    },
+    Pass {
+        name: "mem2reg",
+        level: 1,
+        run: ssa::construct,
+    },
+    Pass {
+        name: "licm",
+        level: 2,
+        run: loops::hoist_invariants,
+    },
+    Pass {
+        name: "strength-reduce",
+        level: 2,
+        run: loops::reduce_strength,
+    },
+    Pass {
+        name: "fold",
+        level: 1,
+        run: fold::fold,
+    },
+    Pass {
+        name: "dce",
+        level: 1,
+        run: dce::eliminate,
+    },
+    Pass {
+        name: "simplify-cfg",
+        level: 2,
+        run: cfg::merge_blocks,
+    },
+];
+
+// over the code, once it is generated
+const PEEPHOLE: Pass<Listing> = Pass {
+    name: "peephole",
+    level: 1,
+    run: peephole::optimize,
+};
+
+/// The names of the passes, as `-fno-<name>` and `-fdump-<name>` take them.
+pub fn names() -> impl Iterator<Item = &'static str> {
+    let passes = PASSES.iter().map(|pass| pass.name);
+    Some(INLINE.name)
+        .into_iter()
+        .chain(passes)
+        .chain(Some(PEEPHOLE.name))
+}
+
+/// Runs the passes over the whole program the options enable, as `run` does.
+pub fn run_on_program(program: &mut Program, options: &Options) {
+    if run_pass(&INLINE, program, options) {
+        program.functions.iter().for_each(verify);
+    }
+}
+
+/// Runs the passes the options enable, and writes out the dumps they ask for.
+pub fn run(func: &mut Function, options: &Options) {
+    for pass in &PASSES {
+        if run_pass(pass, func, options) {
+            verify(func);
+        }
+    }
+}
+
+/// Runs the passes over the code of a function the options enable, as `run` does.
+pub fn run_on_code(listing: &mut Listing, options: &Options) {
+    run_pass(&PEEPHOLE, listing, options);
+}
+
+// Whether the pass ran.
+fn run_pass<T: Display>(pass: &Pass<T>, t: &mut T, options: &Options) -> bool {
+    let listed = |names: &[String]| names.iter().any(|name| name == pass.name);
+    if pass.level > options.opt_level || listed(&options.disabled_passes) {
+        return false;
+    }
+    let dump = listed(&options.dumped_passes);
+    if dump {
+        eprint!("; before {}\n{}", pass.name, t);
+    }
+    (pass.run)(t);
+    if dump {
+        eprint!("; after {}\n{}", pass.name, t);
+    }
+    true
+}
@@ -0,0 +1,241 @@
+//! Rewrites the code of a function a few instructions at a time into shorter code that does the
+//! same. The code generator leaves values it no longer needs in registers, and the flags of
+//! comparisons it has already materialised, so whether those are still read is known from
+//! their liveness over the listing.
+
+use crate::x86::{low32, register, Insn, Line, Listing, Regs, FLAGS};
+use std::collections::HashMap;
+
+pub fn optimize(listing: &mut Listing) {
+    while rewrite(listing) {}
+}
+
+// One sweep over the code, with the liveness from before it: the rewrites only ever make
+// registers live for less long, but for the fusion of a jump with the comparison before it,
+// after which the sweep stops. Whether anything changed.
+fn rewrite(listing: &mut Listing) -> bool {
+    let live = live_after(&listing.lines);
+    let old = std::mem::take(&mut listing.lines);
+    let insn = |i: usize| match old.get(i) {
+        Some(Line::Insn(insn)) => Some(insn),
+        _ => None,
+    };
+    let mut changed = false;
+    let mut i = 0;
+    while i < old.len() {
+        let (insn, next) = match (insn(i), insn(i + 1)) {
+            (Some(insn), next) => (insn, next),
+            (None, _) => {
+                listing.lines.push(old[i].clone());
+                i += 1;
+                continue;
+            }
+        };
+        if let Some(replacement) = next.and_then(|next| push_pop(insn, next)) {
+            listing
+                .lines
+                .extend(replacement.into_iter().map(Line::Insn));
+            changed = true;
+            i += 2;
+            continue;
+        }
+        if let Some(jump) = next.and_then(|next| fused_jump(&old[..i], insn, next)) {
+            listing.lines.push(Line::Insn(jump));
+            listing.lines.extend(old[i + 2..].iter().cloned());
+            return true;
+        }
+        // a dead move goes, rather than becoming a `xor`
+        let replacement = is_dead(insn, live[i]).then(|| None).or_else(|| {
+            zero_test(insn)
+                .or_else(|| zero_xor(insn, live[i]))
+                .map(Some)
+        });
+        match replacement {
+            Some(replacement) => {
+                listing.lines.extend(replacement.map(Line::Insn));
+                changed = true;
+            }
+            None => listing.lines.push(old[i].clone()),
+        }
+        i += 1;
+    }
+    changed
+}
+
+// `push x; pop y` is `mov x, y`, but from memory to memory
+fn push_pop(insn: &Insn, next: &Insn) -> Option<Vec<Insn>> {
+    if insn.op != "push" || next.op != "pop" {
+        return None;
+    }
+    let (src, dst) = (insn.args[0].as_str(), next.args[0].as_str());
+    if src == dst {
+        return Some(vec![]);
+    }
+    if register(src).is_none() && register(dst).is_none() {
+        return None;
+    }
+    Some(vec![Insn::new("mov", &[src, dst])])
+}
+
+// A comparison with 0, and a `je` or `jne` on it, of a value just set from the flags as
+// `setcc %al; movzb %al, %rax`, maybe moved to where it lives, are the `jcc` on the flags.
+// Nothing in between changes them.
+fn fused_jump(before: &[Line], insn: &Insn, next: &Insn) -> Option<Insn> {
+    let value = match insn.op.as_str() {
+        "test" if insn.args[0] == insn.args[1] => insn.args[0].as_str(),
+        "cmp" if insn.args[0] == "$0" => insn.args[1].as_str(),
+        _ => return None,
+    };
+    let label = next.args.get(0)?.as_str();
+    let taken_if_set = match next.jcc()? {
+        "ne" => true,
+        "e" => false,
+        _ => return None,
+    };
+    let mut before = before.iter().rev().map(|line| match line {
+        Line::Insn(insn) => Some(insn),
+        Line::Label(_) => None,
+    });
+    let mut prev = before.next()??;
+    if value != "%rax" {
+        if *prev != Insn::new("mov", &["%rax", value]) {
+            return None;
+        }
+        prev = before.next()??;
+    }
+    if *prev != Insn::new("movzb", &["%al", "%rax"]) {
+        return None;
+    }
+    let prev = before.next()??;
+    let cc = prev.setcc().filter(|_| prev.args[0] == "%al")?;
+    let cc = if taken_if_set { cc } else { inverse(cc)? };
+    Some(Insn::new(&format!("j{}", cc), &[label]))
+}
+
+// the condition code that holds when `cc` does not
+fn inverse(cc: &str) -> Option<&'static str> {
+    let pairs = [
+        ("e", "ne"),
+        ("l", "ge"),
+        ("le", "g"),
+        ("b", "ae"),
+        ("be", "a"),
+    ];
+    pairs.iter().find_map(|(x, y)| match cc {
+        _ if cc == *x => Some(*y),
+        _ if cc == *y => Some(*x),
+        _ => None,
+    })
+}
+
+// `cmp $0, %reg` is `test %reg, %reg`, which has no immediate to encode
+fn zero_test(insn: &Insn) -> Option<Insn> {
+    match insn.args.as_slice() {
+        [zero, reg] if insn.op == "cmp" && zero == "$0" && register(reg).is_some() => {
+            Some(Insn::new("test", &[reg.as_str(), reg.as_str()]))
+        }
+        _ => None,
+    }
+}
+
+// `mov $0, %reg` is `xor %reg, %reg` on the 32-bit register, which zeroes all of it, where
+// nothing reads the flags it changes
+fn zero_xor(insn: &Insn, live: Regs) -> Option<Insn> {
+    match insn.args.as_slice() {
+        [zero, reg] if matches!(insn.op.as_str(), "mov" | "movq" | "movl") && zero == "$0" => {
+            let (_, width) = register(reg)?;
+            if width < 4 || live.contains(FLAGS) {
+                return None;
+            }
+            let reg = if width == 8 {
+                low32(reg)
+            } else {
+                reg.to_string()
+            };
+            Some(Insn::new("xor", &[&reg, &reg]))
+        }
+        _ => None,
+    }
+}
+
+// a move or an address computed to a register nothing reads afterwards
+fn is_dead(insn: &Insn, live: Regs) -> bool {
+    let op = insn.op.as_str();
+    let moves = matches!(op, "mov" | "movq" | "movl" | "lea")
+        || op.starts_with("movz")
+        || op.starts_with("movs") && op != "movsd"
+        || insn.setcc().is_some();
+    match insn.args.last() {
+        Some(dst) if moves && register(dst).is_some() => {
+            let (_, defs) = insn.effects();
+            !live.contains(defs.0)
+        }
+        _ => false,
+    }
+}
+
+// The registers live after each line: read before they are written on some path from it.
+fn live_after(lines: &[Line]) -> Vec<Regs> {
+    // the code is in blocks from each label and after each jump
+    let mut starts = vec![];
+    let mut labels = HashMap::new();
+    for (i, line) in lines.iter().enumerate() {
+        let after_jump = i > 0 && matches!(&lines[i - 1], Line::Insn(insn) if insn.is_jump());
+        if i == 0 || after_jump || matches!(line, Line::Label(_)) {
+            starts.push(i);
+        }
+        if let Line::Label(label) = line {
+            labels.insert(label.as_str(), starts.len() - 1);
+        }
+    }
+    let count = starts.len();
+    let end = |b: usize| starts.get(b + 1).copied().unwrap_or(lines.len());
+    // anywhere unknown, everything may be read
+    let all = Regs(u32::MAX);
+    let successors: Vec<Vec<Option<usize>>> = (0..count)
+        .map(|b| {
+            let next = (b + 1 < count).then(|| b + 1);
+            match lines[starts[b]..end(b)].last() {
+                Some(Line::Insn(insn)) if insn.op == "ret" => vec![],
+                Some(Line::Insn(insn)) if insn.is_jump() => {
+                    let target = labels.get(insn.args[0].as_str()).copied();
+                    if insn.op == "jmp" {
+                        vec![target]
+                    } else {
+                        vec![target, next]
+                    }
+                }
+                _ => vec![next],
+            }
+        })
+        .collect();
+    // the registers live at each line of a block, given those live out of it
+    let through = |b: usize, mut live: Regs, after: &mut [Regs]| {
+        for i in (starts[b]..end(b)).rev() {
+            after[i] = live;
+            if let Line::Insn(insn) = &lines[i] {
+                let (uses, defs) = insn.effects();
+                live = Regs(live.0 & !defs.0 | uses.0);
+            }
+        }
+        live
+    };
+    let mut after = vec![Regs::default(); lines.len()];
+    let mut live_in = vec![Regs::default(); count];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for b in (0..count).rev() {
+            let out = successors[b].iter().fold(Regs::default(), |out, succ| {
+                let live = succ.map_or(all, |succ| live_in[succ]);
+                Regs(out.0 | live.0)
+            });
+            let live = through(b, out, &mut after);
+            if live != live_in[b] {
+                live_in[b] = live;
+                changed = true;
+            }
+        }
+    }
+    after
+}
@@ -0,0 +1,1064 @@
+use crate::tokenizer::TokenKind::*;
+use crate::tokenizer::{integer_constant, SourceFile, Token, TokenKind, Tokenizer};
+use crate::{Error, ErrorKind, Options, Span};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{fs, mem};
+
+// object-like macros defined before the input is read
+const PREDEFINED: &[(&str, &str)] = &[
+    ("__STDC__", "1"),
+    ("__STDC_VERSION__", "201112L"),
+    ("__STDC_HOSTED__", "1"),
+    ("__toycc__", "1"),
+    ("__x86_64__", "1"),
+    ("__x86_64", "1"),
+    ("__amd64__", "1"),
+    ("__amd64", "1"),
+    ("__LP64__", "1"),
+    ("_LP64", "1"),
+    ("__linux__", "1"),
+    ("__linux", "1"),
+    ("__unix__", "1"),
+    ("__unix", "1"),
+    ("__ELF__", "1"),
+    ("__CHAR_BIT__", "8"),
+    ("__SIZEOF_INT__", "4"),
+    ("__SIZEOF_LONG__", "8"),
+    ("__SIZEOF_LONG_LONG__", "8"),
+    ("__SIZEOF_SHORT__", "2"),
+    ("__SIZEOF_POINTER__", "8"),
+    ("__SIZEOF_FLOAT__", "4"),
+    ("__SIZEOF_DOUBLE__", "8"),
+    ("__SIZE_TYPE__", "unsigned long"),
+    ("__PTRDIFF_TYPE__", "long"),
+    ("__WCHAR_TYPE__", "int"),
+    ("__WCHAR_MAX__", "2147483647"),
+    ("__WCHAR_MIN__", "(-__WCHAR_MAX__ - 1)"),
+    ("__ORDER_LITTLE_ENDIAN__", "1234"),
+    ("__ORDER_BIG_ENDIAN__", "4321"),
+    ("__BYTE_ORDER__", "__ORDER_LITTLE_ENDIAN__"),
+];
+
+// where the macros defined other than by `#define` come from, in diagnostics
+const BUILT_IN: &str = "<built-in>";
+const COMMAND_LINE: &str = "<command line>";
+
+#[derive(Clone)]
+struct Macro {
+    // `None` for object-like macros
+    params: Option<Vec<String>>,
+    // the last parameter is `__VA_ARGS__`
+    variadic: bool,
+    body: Vec<Token>,
+    // computes the expansion of `__LINE__` and the like, instead of `body`
+    handler: Option<fn(&mut Preprocessor, &Token) -> TokenKind>,
+}
+
+// an `#if`, `#ifdef` or `#ifndef` group
+struct Cond {
+    ctx: CondCtx,
+    // whether one of its branches was taken
+    included: bool,
+    token: Token,
+}
+
+#[derive(PartialEq)]
+enum CondCtx {
+    Then,
+    Elif,
+    Else,
+}
+
+/// Runs directives and expands macros. Tokens come in and go out in the reversed order
+/// produced by the tokenizer and consumed by the parser.
+pub struct Preprocessor {
+    options: Options,
+    macros: HashMap<String, Macro>,
+    // the tokens left to preprocess, the next one last
+    tokens: Vec<Token>,
+    // the groups being read, innermost last
+    conds: Vec<Cond>,
+    // files not read again: those with `#pragma once`, and those whose include guard is defined
+    once: HashSet<PathBuf>,
+    guards: HashMap<PathBuf, String>,
+    // the names of the files found in the system directories
+    system: HashSet<String>,
+    // the next value of `__COUNTER__`
+    counter: usize,
+}
+
+impl Preprocessor {
+    /// The predefined macros come first, then those of `-D` and `-U` in order, which fail
+    /// when they are no valid definitions.
+    pub fn new(options: &Options) -> Result<Self, Error> {
+        let mut pp = Self {
+            options: options.clone(),
+            macros: HashMap::new(),
+            tokens: vec![],
+            conds: vec![],
+            once: HashSet::new(),
+            guards: HashMap::new(),
+            system: HashSet::new(),
+            counter: 0,
+        };
+        for (name, value) in PREDEFINED {
+            pp.define_builtin(BUILT_IN, &format!("{} {}", name, value))?;
+        }
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+        let (date, time) = date_time(now.as_secs());
+        pp.define_builtin(BUILT_IN, &format!("__DATE__ \"{}\"", date))?;
+        pp.define_builtin(BUILT_IN, &format!("__TIME__ \"{}\"", time))?;
+        pp.define_handler("__FILE__", |_, t| Str(t.file.name.clone().into_bytes()));
+        pp.define_handler("__LINE__", |_, t| Num(t.line.to_string()));
+        pp.define_handler("__COUNTER__", |pp, _| {
+            pp.counter += 1;
+            Num((pp.counter - 1).to_string())
+        });
+        for (name, value) in &options.macros {
+            match value {
+                Some(value) => pp.define_builtin(COMMAND_LINE, &format!("{} {}", name, value))?,
+                None => {
+                    pp.macros.remove(name);
+                }
+            }
+        }
+        Ok(pp)
+    }
+
+    // `text` is what follows `#define`, read from a file of that name
+    fn define_builtin(&mut self, name: &str, text: &str) -> Result<(), Error> {
+        let file = SourceFile::new(name.to_string(), text.to_string());
+        let mut line = Tokenizer::new(Rc::new(file)).run()?;
+        let eof = line.remove(0);
+        line.reverse();
+        self.define(line, &eof)
+    }
+
+    fn define_handler(&mut self, name: &str, handler: fn(&mut Preprocessor, &Token) -> TokenKind) {
+        let m = Macro {
+            params: None,
+            variadic: false,
+            body: vec![],
+            handler: Some(handler),
+        };
+        self.macros.insert(name.to_string(), m);
+    }
+
+    pub fn run(&mut self, tokens: Vec<Token>) -> Result<Vec<Token>, Error> {
+        self.tokens = tokens;
+        let mut out = vec![];
+        while let Some(t) = self.tokens.pop() {
+            // a `#` a macro expands to starts no directive
+            if t.kind == Hash && t.at_bol && t.hideset.is_empty() {
+                self.directive(&t)?;
+            } else if !self.expand_macro(&t)? {
+                out.push(t);
+            }
+        }
+        if let Some(cond) = self.conds.last() {
+            return Err(error(&cond.token, "unterminated conditional directive"));
+        }
+        out.reverse();
+        Ok(out)
+    }
+
+    // the rest of the line of a directive
+    fn line(&mut self) -> Vec<Token> {
+        let mut line = vec![];
+        while self.tokens.last().map_or(false, |t| !t.at_bol) {
+            line.push(self.tokens.pop().unwrap());
+        }
+        line
+    }
+
+    fn directive(&mut self, hash: &Token) -> Result<(), Error> {
+        let mut line = self.line();
+        if line.is_empty() {
+            // the null directive
+            return Ok(());
+        }
+        let t = line.remove(0);
+        match t.kind.ident_name().as_deref() {
+            Some("define") => self.define(line, &t),
+            Some("undef") => {
+                let name = line.first().and_then(|t| t.kind.ident_name());
+                match name {
+                    Some(name) => {
+                        self.macros.remove(&name);
+                        Ok(())
+                    }
+                    None => Err(error(hash, "macro name missing")),
+                }
+            }
+            Some("include") => self.include(line, &t),
+            Some("if") => {
+                let included = self.eval_cond(line, &t)?;
+                self.push_cond(included, t);
+                Ok(())
+            }
+            Some("ifdef") | Some("ifndef") => {
+                let name = line
+                    .first()
+                    .and_then(|t| t.kind.ident_name())
+                    .ok_or_else(|| error(&t, "macro name missing"))?;
+                let defined = self.macros.contains_key(&name);
+                let ifdef = t.kind.ident_name().as_deref() == Some("ifdef");
+                self.push_cond(defined == ifdef, t);
+                Ok(())
+            }
+            Some("elif") => {
+                let included = match self.conds.last() {
+                    Some(cond) if cond.ctx != CondCtx::Else => cond.included,
+                    _ => return Err(error(&t, "stray #elif")),
+                };
+                let taken = !included && self.eval_cond(line, &t)?;
+                let cond = self.conds.last_mut().unwrap();
+                cond.ctx = CondCtx::Elif;
+                cond.included |= taken;
+                if !taken {
+                    self.skip_cond();
+                }
+                Ok(())
+            }
+            Some("else") => {
+                let cond = match self.conds.last_mut() {
+                    Some(cond) if cond.ctx != CondCtx::Else => cond,
+                    _ => return Err(error(&t, "stray #else")),
+                };
+                cond.ctx = CondCtx::Else;
+                if cond.included {
+                    self.skip_cond();
+                }
+                Ok(())
+            }
+            Some("endif") => match self.conds.pop() {
+                Some(_) => Ok(()),
+                None => Err(error(&t, "stray #endif")),
+            },
+            Some("pragma") => {
+                if line.first().and_then(|t| t.kind.ident_name()).as_deref() == Some("once") {
+                    if let Ok(path) = fs::canonicalize(&t.file.name) {
+                        self.once.insert(path);
+                    }
+                }
+                // other pragmas are ignored
+                Ok(())
+            }
+            Some("error") => {
+                let msg: Vec<_> = line.iter().map(|t| t.kind.spelling()).collect();
+                Err(error(hash, &format!("#error {}", msg.join(" "))))
+            }
+            _ => Err(error(&t, "invalid preprocessor directive")),
+        }
+    }
+
+    fn push_cond(&mut self, included: bool, token: Token) {
+        self.conds.push(Cond {
+            ctx: CondCtx::Then,
+            included,
+            token,
+        });
+        if !included {
+            self.skip_cond();
+        }
+    }
+
+    // Skips a group whose condition is false, nested groups included, up to the `#elif`,
+    // `#else` or `#endif` after it.
+    fn skip_cond(&mut self) {
+        let mut depth = 0;
+        while let Some(t) = self.tokens.last() {
+            if t.kind == Eof {
+                return;
+            }
+            if t.kind == Hash && t.at_bol {
+                let name = self.tokens.iter().rev().nth(1);
+                let name = name.filter(|t| !t.at_bol).and_then(|t| t.kind.ident_name());
+                match name.as_deref() {
+                    Some("if") | Some("ifdef") | Some("ifndef") => depth += 1,
+                    Some("elif") | Some("else") | Some("endif") if depth == 0 => return,
+                    Some("endif") => depth -= 1,
+                    _ => {}
+                }
+            }
+            self.tokens.pop();
+        }
+    }
+
+    // The condition of `#if` and `#elif`: `defined` is resolved before macros are expanded,
+    // identifiers left afterwards are 0.
+    fn eval_cond(&mut self, line: Vec<Token>, directive: &Token) -> Result<bool, Error> {
+        let mut tokens = vec![];
+        let mut line = line.into_iter();
+        while let Some(t) = line.next() {
+            if t.kind.ident_name().as_deref() != Some("defined") {
+                tokens.push(t);
+                continue;
+            }
+            let mut name = line.next();
+            let paren = name.as_ref().map(|t| &t.kind) == Some(&LeftParen);
+            if paren {
+                name = line.next();
+            }
+            let name = name
+                .and_then(|t| t.kind.ident_name())
+                .ok_or_else(|| error(&t, "macro name missing"))?;
+            if paren && line.next().map(|t| t.kind) != Some(RightParen) {
+                return Err(error(&t, "expected ')' after 'defined'"));
+            }
+            let defined = self.macros.contains_key(&name);
+            tokens.push(Token {
+                kind: Num((defined as usize).to_string()),
+                ..t
+            });
+        }
+        let tokens: Vec<_> = self
+            .expand_arg(&tokens)?
+            .into_iter()
+            .map(|t| match t.kind.ident_name() {
+                Some(_) => Token {
+                    kind: Num("0".to_string()),
+                    ..t
+                },
+                None => t,
+            })
+            .collect();
+        let mut expr = CondExpr {
+            tokens: &tokens,
+            pos: 0,
+            directive,
+            unevaluated: 0,
+        };
+        let value = expr.ternary()?;
+        if expr.pos != tokens.len() {
+            return Err(expr.error("extra tokens in preprocessor expression"));
+        }
+        Ok(value.value != 0)
+    }
+
+    fn include(&mut self, line: Vec<Token>, t: &Token) -> Result<(), Error> {
+        let (name, quoted) = self.include_name(line, t)?;
+        let path = self
+            .find_include(&name, quoted, &t.file)
+            .ok_or_else(|| error(t, &format!("{}: file not found", name)))?;
+        let key = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+        if self.once.contains(&key) {
+            return Ok(());
+        }
+        if let Some(guard) = self.guards.get(&key) {
+            if self.macros.contains_key(guard) {
+                return Ok(());
+            }
+        }
+        let contents = fs::read_to_string(&path)
+            .map_err(|e| error(t, &format!("{}: {}", path.display(), e)))?;
+        let file = SourceFile::new(path.to_string_lossy().into_owned(), contents);
+        let dirs = &self.options.system_include_paths;
+        if dirs.iter().any(|dir| path.starts_with(dir)) {
+            self.system.insert(file.name.clone());
+        }
+        let mut tokens = Tokenizer::new(Rc::new(file)).run()?;
+        // the end of an included file is not the end of the input
+        tokens.remove(0);
+        if let Some(guard) = include_guard(&tokens) {
+            self.guards.insert(key, guard);
+        }
+        self.tokens.extend(tokens);
+        Ok(())
+    }
+
+    // `"file"` or `<file>`, possibly as the expansion of macros
+    fn include_name(&mut self, line: Vec<Token>, t: &Token) -> Result<(String, bool), Error> {
+        let line = match line.first().map(|t| &t.kind) {
+            Some(Str(_)) | Some(Lower) => line,
+            _ => self.expand_arg(&line)?,
+        };
+        match line.first().map(|t| &t.kind) {
+            Some(Str(bytes)) => Ok((String::from_utf8_lossy(bytes).into_owned(), true)),
+            Some(Lower) => {
+                let mut name = String::new();
+                for (i, tok) in line.iter().enumerate().skip(1) {
+                    if tok.kind == Greater {
+                        return Ok((name, false));
+                    }
+                    if i > 1 && tok.has_space {
+                        name.push(' ');
+                    }
+                    name.push_str(&tok.kind.spelling());
+                }
+                Err(error(t, "expected '>'"))
+            }
+            _ => Err(error(t, "expected \"FILENAME\" or <FILENAME>")),
+        }
+    }
+
+    // `#include "..."` looks next to the including file first, then both kinds look in
+    // the `-I` paths and the system directories
+    fn find_include(&self, name: &str, quoted: bool, from: &SourceFile) -> Option<PathBuf> {
+        if Path::new(name).is_absolute() {
+            return Some(PathBuf::from(name));
+        }
+        let mut dirs = vec![];
+        if quoted {
+            let dir = Path::new(&from.name)
+                .parent()
+                .unwrap_or_else(|| Path::new(""));
+            dirs.push(dir.to_path_buf());
+        }
+        dirs.extend(self.options.include_paths.iter().cloned());
+        dirs.extend(self.options.system_include_paths.iter().cloned());
+        dirs.into_iter()
+            .map(|dir| dir.join(name))
+            .find(|path| path.is_file())
+    }
+
+    // A macro is function-like when `(` follows its name without whitespace in between.
+    fn define(&mut self, line: Vec<Token>, directive: &Token) -> Result<(), Error> {
+        let mut line = line.into_iter();
+        let t = line
+            .next()
+            .ok_or_else(|| error(directive, "macro name missing"))?;
+        let name = t
+            .kind
+            .ident_name()
+            .ok_or_else(|| error(&t, "macro name must be an identifier"))?;
+        // glibc's <sys/cdefs.h> defines it away for compilers other than GCC, but the parser
+        // reads the attributes it knows and skips the others; a program may still define it
+        if name == "__attribute__" && self.system.contains(&directive.file.name) {
+            return Ok(());
+        }
+        let mut body: Vec<Token> = line.collect();
+        let (params, variadic) = match body.first() {
+            Some(open) if open.kind == LeftParen && !open.has_space => {
+                body.remove(0);
+                let (params, variadic) = macro_params(&mut body, &t)?;
+                (Some(params), variadic)
+            }
+            _ => (None, false),
+        };
+        let m = Macro {
+            params,
+            variadic,
+            body,
+            handler: None,
+        };
+        self.macros.insert(name, m);
+        Ok(())
+    }
+
+    // Replaces the macro invoked by `t`, if any, by its expansion. The expansion goes back
+    // on the tokens to preprocess, so that the macros in it expand as well.
+    fn expand_macro(&mut self, t: &Token) -> Result<bool, Error> {
+        let name = match t.kind.ident_name() {
+            Some(name) if !t.hideset.contains(&name) => name,
+            _ => return Ok(false),
+        };
+        let m = match self.macros.get(&name) {
+            Some(m) => m.clone(),
+            None => return Ok(false),
+        };
+        if let Some(handler) = m.handler {
+            let kind = handler(self, t);
+            self.tokens.push(Token { kind, ..t.clone() });
+            return Ok(true);
+        }
+        let (body, mut hideset) = match &m.params {
+            None => (self.subst(&m, &[], &[])?, t.hideset.clone()),
+            Some(params) => {
+                // a function-like macro name without arguments is left alone
+                if self.tokens.last().map(|t| &t.kind) != Some(&LeftParen) {
+                    return Ok(false);
+                }
+                let (args, rparen) = self.macro_args(params.len(), m.variadic, t)?;
+                // the macros hiding both the name and the closing parenthesis
+                let hideset = t
+                    .hideset
+                    .iter()
+                    .filter(|name| rparen.hideset.contains(name))
+                    .cloned()
+                    .collect();
+                (self.subst(&m, params, &args)?, hideset)
+            }
+        };
+        hideset.push(name);
+
+        // the expansion is located where the macro is used
+        let expansion = body.into_iter().enumerate().map(|(i, mut tok)| {
+            for name in &hideset {
+                if !tok.hideset.contains(name) {
+                    tok.hideset.push(name.clone());
+                }
+            }
+            tok.file = t.file.clone();
+            tok.line = t.line;
+            tok.start = t.start;
+            // a line starting with the macro starts with its expansion
+            tok.at_bol = i == 0 && t.at_bol;
+            if i == 0 {
+                tok.has_space = t.has_space;
+            }
+            tok
+        });
+        let expansion: Vec<_> = expansion.collect();
+        self.tokens.extend(expansion.into_iter().rev());
+        Ok(true)
+    }
+
+    // The arguments of a call to a function-like macro, split on the commas outside of
+    // parentheses, and its closing parenthesis.
+    fn macro_args(
+        &mut self,
+        nparams: usize,
+        variadic: bool,
+        name: &Token,
+    ) -> Result<(Vec<Vec<Token>>, Token), Error> {
+        self.tokens.pop();
+        let mut args = vec![vec![]];
+        let mut depth = 0;
+        let rparen = loop {
+            let t = match self.tokens.pop() {
+                Some(t) if t.kind != Eof => t,
+                _ => return Err(error(name, "unterminated macro invocation")),
+            };
+            match t.kind {
+                LeftParen => depth += 1,
+                RightParen if depth == 0 => break t,
+                RightParen => depth -= 1,
+                // the variadic argument takes the commas after it
+                Comma if depth == 0 && !(variadic && args.len() == nparams) => {
+                    args.push(vec![]);
+                    continue;
+                }
+                _ => {}
+            }
+            args.last_mut().unwrap().push(t);
+        };
+        if nparams == 0 && args.len() == 1 && args[0].is_empty() {
+            args.clear();
+        }
+        if variadic && args.len() + 1 == nparams {
+            args.push(vec![]);
+        }
+        if args.len() != nparams {
+            return Err(error(name, "wrong number of macro arguments"));
+        }
+        Ok((args, rparen))
+    }
+
+    // Substitutes the arguments for the parameters in the body of `m`, and pastes the operands
+    // of `##`. Arguments are macro-expanded first, except as operands of `#` and `##`. In the
+    // body of an object-like macro, `#` is no operator.
+    fn subst(
+        &mut self,
+        m: &Macro,
+        params: &[String],
+        args: &[Vec<Token>],
+    ) -> Result<Vec<Token>, Error> {
+        let param = |t: &Token| {
+            t.kind
+                .ident_name()
+                .and_then(|name| params.iter().position(|p| *p == name))
+        };
+        let va_args = if m.variadic {
+            params.len().checked_sub(1)
+        } else {
+            None
+        };
+        let va_empty = va_args.map(|p| args[p].is_empty());
+        let body = &m.body;
+        let mut out: Vec<Token> = vec![];
+        let mut i = 0;
+        while i < body.len() {
+            let t = &body[i];
+            let next = body.get(i + 1);
+            let next_kind = next.map(|t| &t.kind);
+
+            if t.kind == Hash && m.params.is_some() {
+                match next.and_then(param) {
+                    Some(p) => out.push(stringize(t, &args[p])),
+                    None => return Err(error(t, "'#' is not followed by a macro parameter")),
+                }
+                i += 2;
+                continue;
+            }
+
+            // GNU `, ## __VA_ARGS__` drops the comma when there are no variadic arguments
+            if t.kind == Comma
+                && next_kind == Some(&HashHash)
+                && va_args.is_some()
+                && body.get(i + 2).and_then(param) == va_args
+            {
+                if va_empty == Some(true) {
+                    i += 3;
+                } else {
+                    out.push(t.clone());
+                    i += 2;
+                }
+                continue;
+            }
+
+            if t.kind == HashHash {
+                let (lhs, rhs) = match (out.pop(), next) {
+                    (Some(lhs), Some(rhs)) => (lhs, rhs),
+                    _ => {
+                        return Err(error(
+                            t,
+                            "'##' cannot appear at either end of a macro expansion",
+                        ))
+                    }
+                };
+                match param(rhs) {
+                    Some(p) if args[p].is_empty() => out.push(lhs),
+                    Some(p) => {
+                        out.push(paste(&lhs, &args[p][0])?);
+                        out.extend(args[p][1..].iter().cloned());
+                    }
+                    None => out.push(paste(&lhs, rhs)?),
+                }
+                i += 2;
+                continue;
+            }
+
+            if let (Some(name), Some(&LeftParen)) = (t.kind.ident_name(), next_kind) {
+                if let (Some(empty), "__VA_OPT__") = (va_empty, name.as_str()) {
+                    let (inner, end) = va_opt(body, i + 1, t)?;
+                    if !empty {
+                        let inner = Macro {
+                            params: m.params.clone(),
+                            variadic: m.variadic,
+                            body: inner,
+                            handler: None,
+                        };
+                        out.extend(self.subst(&inner, params, args)?);
+                    }
+                    i = end;
+                    continue;
+                }
+            }
+
+            if let Some(p) = param(t) {
+                if next_kind == Some(&HashHash) {
+                    // an empty operand of `##` leaves the other one as is
+                    match (args[p].is_empty(), body.get(i + 2).and_then(param)) {
+                        (true, Some(q)) => {
+                            out.extend(args[q].iter().cloned());
+                            i += 3;
+                        }
+                        (true, None) => i += 2,
+                        (false, _) => {
+                            out.extend(args[p].iter().cloned());
+                            i += 1;
+                        }
+                    }
+                    continue;
+                }
+                let mut arg = self.expand_arg(&args[p])?;
+                if let Some(first) = arg.first_mut() {
+                    first.has_space = t.has_space;
+                }
+                out.extend(arg);
+                i += 1;
+                continue;
+            }
+
+            out.push(t.clone());
+            i += 1;
+        }
+        Ok(out)
+    }
+
+    // Arguments are fully expanded on their own, before being substituted.
+    fn expand_arg(&mut self, arg: &[Token]) -> Result<Vec<Token>, Error> {
+        let rest = mem::replace(&mut self.tokens, arg.iter().rev().cloned().collect());
+        let mut out = vec![];
+        while let Some(t) = self.tokens.pop() {
+            if !self.expand_macro(&t)? {
+                out.push(t);
+            }
+        }
+        self.tokens = rest;
+        Ok(out)
+    }
+}
+
+/// The tokens as text, as output by `-E`. Lines are kept, and `# line "file"` markers
+/// tell where the tokens come from when it is not the next line of the same file.
+pub fn print(tokens: &[Token]) -> String {
+    let mut out = String::new();
+    let mut file: Option<&Rc<SourceFile>> = None;
+    let mut line = 0;
+    for t in tokens.iter().rev().filter(|t| t.kind != Eof) {
+        let same_file = file.map_or(false, |file| Rc::ptr_eq(file, &t.file));
+        if !same_file || t.line < line || t.line > line + 8 {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(&format!("# {} \"{}\"\n", t.line, t.file.name));
+            file = Some(&t.file);
+            line = t.line;
+        } else if t.line > line && t.at_bol {
+            out.push_str(&"\n".repeat(t.line - line));
+            line = t.line;
+        } else {
+            // spliced lines and macro arguments spanning lines are joined
+            line = line.max(t.line);
+            if t.has_space {
+                out.push(' ');
+            }
+            out.push_str(&t.kind.spelling());
+            continue;
+        }
+        // the first token of a line keeps its indentation
+        let bol = t.file.contents[..t.start].rfind('\n').map_or(0, |i| i + 1);
+        out.push_str(&" ".repeat(t.start - bol));
+        out.push_str(&t.kind.spelling());
+    }
+    out.push('\n');
+    out
+}
+
+// `__DATE__` as "Mmm dd yyyy" and `__TIME__` as "hh:mm:ss", in UTC
+fn date_time(secs: u64) -> (String, String) {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let (days, secs) = (secs / 86400, secs % 86400);
+    let time = format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60);
+
+    // days since 1970-01-01 to a civil date, from http://howardhinnant.github.io/date_algorithms.html
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    let date = format!("{} {:2} {}", MONTHS[month as usize - 1], day, year);
+    (date, time)
+}
+
+fn error(token: &Token, msg: &str) -> Error {
+    Error {
+        kind: ErrorKind::Generic(msg.to_string()),
+        span: Some(Span::new(token.file.clone(), token.start)),
+    }
+}
+
+// The macro of `#ifndef NAME ... #endif` around a whole file: once it is defined,
+// including the file again expands to nothing.
+fn include_guard(tokens: &[Token]) -> Option<String> {
+    let tokens: Vec<_> = tokens.iter().rev().collect();
+    let directive = |i: usize| match tokens.get(i) {
+        Some(t) if t.kind == Hash && t.at_bol => tokens
+            .get(i + 1)
+            .filter(|t| !t.at_bol)
+            .and_then(|t| t.kind.ident_name()),
+        _ => None,
+    };
+    if directive(0).as_deref() != Some("ifndef") {
+        return None;
+    }
+    let guard = tokens.get(2)?.kind.ident_name()?;
+    let mut depth = 0;
+    for i in 0..tokens.len() {
+        match directive(i).as_deref() {
+            Some("if") | Some("ifdef") | Some("ifndef") => depth += 1,
+            Some("elif") | Some("else") if depth == 1 => return None,
+            Some("endif") => {
+                depth -= 1;
+                if depth == 0 {
+                    // nothing but the rest of the `#endif` line may follow
+                    let rest_of_file = tokens[i + 1..].iter().any(|t| t.at_bol);
+                    return if rest_of_file { None } else { Some(guard) };
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// The integer constant expression of `#if`, once macros are expanded.
+struct CondExpr<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    directive: &'a Token,
+    // inside operands not evaluated, as in `1 || 1 / 0`, where division by zero is no error
+    unevaluated: usize,
+}
+
+// A value of `#if`, computed as `intmax_t` or `uintmax_t`. An operation with an unsigned
+// operand is unsigned, as with the usual arithmetic conversions, but for a shift, which is
+// of the type of its left operand.
+#[derive(Copy, Clone)]
+struct CondValue {
+    value: i64,
+    unsigned: bool,
+}
+
+impl CondValue {
+    fn signed(value: i64) -> Self {
+        Self {
+            value,
+            unsigned: false,
+        }
+    }
+
+    fn bool(b: bool) -> Self {
+        Self::signed(b as i64)
+    }
+}
+
+impl CondExpr<'_> {
+    fn error(&self, msg: &str) -> Error {
+        error(self.tokens.get(self.pos).unwrap_or(self.directive), msg)
+    }
+
+    fn peek(&self) -> Option<&TokenKind> {
+        self.tokens.get(self.pos).map(|t| &t.kind)
+    }
+
+    fn skip(&mut self, kind: TokenKind) -> bool {
+        if self.peek() == Some(&kind) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn ternary(&mut self) -> Result<CondValue, Error> {
+        let cond = self.binary(0)?;
+        if !self.skip(Question) {
+            return Ok(cond);
+        }
+        let then = self.operand(cond.value == 0, Self::ternary)?;
+        if !self.skip(Colon) {
+            return Err(self.error("expected ':'"));
+        }
+        let otherwise = self.operand(cond.value != 0, Self::ternary)?;
+        let value = if cond.value != 0 { then } else { otherwise };
+        Ok(CondValue {
+            unsigned: then.unsigned || otherwise.unsigned,
+            ..value
+        })
+    }
+
+    fn operand(
+        &mut self,
+        unevaluated: bool,
+        parse: impl FnOnce(&mut Self) -> Result<CondValue, Error>,
+    ) -> Result<CondValue, Error> {
+        self.unevaluated += unevaluated as usize;
+        let value = parse(self);
+        self.unevaluated -= unevaluated as usize;
+        value
+    }
+
+    // binary operators by precedence climbing, with C's precedence levels
+    fn binary(&mut self, min_prec: u8) -> Result<CondValue, Error> {
+        let mut lhs = self.unary()?;
+        loop {
+            let prec = match self.peek() {
+                Some(PipePipe) => 1,
+                Some(AmpAmp) => 2,
+                Some(Pipe) => 3,
+                Some(Caret) => 4,
+                Some(Amp) => 5,
+                Some(EqualEqual) | Some(NotEqual) => 6,
+                Some(Lower) | Some(Greater) | Some(LowerEqual) | Some(GreaterEqual) => 7,
+                Some(LowerLower) | Some(GreaterGreater) => 8,
+                Some(Plus) | Some(Minus) => 9,
+                Some(Star) | Some(Slash) | Some(Percent) => 10,
+                _ => return Ok(lhs),
+            };
+            if prec < min_prec {
+                return Ok(lhs);
+            }
+            let op = self.tokens[self.pos].kind.clone();
+            self.pos += 1;
+            let decided = match op {
+                PipePipe => lhs.value != 0,
+                AmpAmp => lhs.value == 0,
+                _ => false,
+            };
+            let rhs = self.operand(decided, |e| e.binary(prec + 1))?;
+            let unsigned = lhs.unsigned || rhs.unsigned;
+            let (x, y) = (lhs.value, rhs.value);
+            // the unsigned comparisons as signed ones, with the sign bit flipped
+            let (ordered_x, ordered_y) = if unsigned {
+                (x ^ i64::MIN, y ^ i64::MIN)
+            } else {
+                (x, y)
+            };
+            let value = match op {
+                PipePipe => (x != 0 || y != 0) as i64,
+                AmpAmp => (x != 0 && y != 0) as i64,
+                Pipe => x | y,
+                Caret => x ^ y,
+                Amp => x & y,
+                EqualEqual => (x == y) as i64,
+                NotEqual => (x != y) as i64,
+                Lower => (ordered_x < ordered_y) as i64,
+                Greater => (ordered_x > ordered_y) as i64,
+                LowerEqual => (ordered_x <= ordered_y) as i64,
+                GreaterEqual => (ordered_x >= ordered_y) as i64,
+                LowerLower => x.wrapping_shl(y as u32),
+                GreaterGreater if lhs.unsigned => (x as u64).wrapping_shr(y as u32) as i64,
+                GreaterGreater => x.wrapping_shr(y as u32),
+                Plus => x.wrapping_add(y),
+                Minus => x.wrapping_sub(y),
+                Star => x.wrapping_mul(y),
+                Slash | Percent if y == 0 && self.unevaluated > 0 => 0,
+                Slash | Percent if y == 0 => {
+                    return Err(self.error("division by zero in preprocessor expression"))
+                }
+                Slash if unsigned => (x as u64 / y as u64) as i64,
+                Slash => x.wrapping_div(y),
+                _ if unsigned => (x as u64 % y as u64) as i64,
+                _ => x.wrapping_rem(y),
+            };
+            lhs = match op {
+                PipePipe | AmpAmp | EqualEqual | NotEqual | Lower | Greater | LowerEqual
+                | GreaterEqual => CondValue::signed(value),
+                LowerLower | GreaterGreater => CondValue { value, ..lhs },
+                _ => CondValue { value, unsigned },
+            };
+        }
+    }
+
+    fn unary(&mut self) -> Result<CondValue, Error> {
+        let t = match self.tokens.get(self.pos) {
+            Some(t) => t,
+            None => return Err(self.error("expected an expression")),
+        };
+        self.pos += 1;
+        match &t.kind {
+            Plus => self.unary(),
+            Minus => {
+                let operand = self.unary()?;
+                let value = operand.value.wrapping_neg();
+                Ok(CondValue { value, ..operand })
+            }
+            Not => Ok(CondValue::bool(self.unary()?.value == 0)),
+            Tilde => {
+                let operand = self.unary()?;
+                let value = !operand.value;
+                Ok(CondValue { value, ..operand })
+            }
+            LeftParen => {
+                let value = self.ternary()?;
+                if !self.skip(RightParen) {
+                    return Err(self.error("expected ')'"));
+                }
+                Ok(value)
+            }
+            // a constant too large for `intmax_t` is a `uintmax_t`
+            Num(spelling) => match integer_constant(spelling) {
+                Some((n, suffix)) => Ok(CondValue {
+                    value: n as i64,
+                    unsigned: suffix.contains('u') || n > i64::MAX as usize,
+                }),
+                None => {
+                    self.pos -= 1;
+                    Err(self.error("invalid integer constant"))
+                }
+            },
+            CharLit(c) => Ok(CondValue::signed(*c as i8 as i64)),
+            _ => {
+                self.pos -= 1;
+                Err(self.error("invalid preprocessor expression"))
+            }
+        }
+    }
+}
+
+// `(a, b)` or `(...)`, after the macro name and `(`
+fn macro_params(body: &mut Vec<Token>, name: &Token) -> Result<(Vec<String>, bool), Error> {
+    let mut tokens = mem::take(body).into_iter();
+    let mut params = vec![];
+    let mut variadic = false;
+    let mut next = || {
+        tokens
+            .next()
+            .ok_or_else(|| error(name, "unterminated macro parameter list"))
+    };
+    loop {
+        let t = next()?;
+        match &t.kind {
+            RightParen if params.is_empty() => break,
+            Ellipsis => {
+                variadic = true;
+                params.push("__VA_ARGS__".to_string());
+                let t = next()?;
+                if t.kind != RightParen {
+                    return Err(error(&t, "expected ')'"));
+                }
+                break;
+            }
+            kind => match kind.ident_name() {
+                Some(param) => params.push(param),
+                None => return Err(error(&t, "expected a parameter name")),
+            },
+        }
+        let t = next()?;
+        match t.kind {
+            Comma => {}
+            RightParen => break,
+            _ => return Err(error(&t, "expected ',' or ')'")),
+        }
+    }
+    *body = tokens.collect();
+    Ok((params, variadic))
+}
+
+// the tokens between the parentheses of `__VA_OPT__( ... )`, and the index after them
+fn va_opt(body: &[Token], open: usize, t: &Token) -> Result<(Vec<Token>, usize), Error> {
+    let mut depth = 0;
+    for (i, tok) in body.iter().enumerate().skip(open) {
+        match tok.kind {
+            LeftParen => depth += 1,
+            RightParen if depth == 1 => return Ok((body[open + 1..i].to_vec(), i + 1)),
+            RightParen => depth -= 1,
+            _ => {}
+        }
+    }
+    Err(error(t, "unterminated __VA_OPT__"))
+}
+
+// `#x` turns the argument into a string literal, with whitespace reduced to single spaces
+fn stringize(hash: &Token, arg: &[Token]) -> Token {
+    let mut s = String::new();
+    for (i, t) in arg.iter().enumerate() {
+        if i > 0 && t.has_space {
+            s.push(' ');
+        }
+        s.push_str(&t.kind.spelling());
+    }
+    Token {
+        kind: Str(s.into_bytes()),
+        ..hash.clone()
+    }
+}
+
+// `a ## b` joins the spellings of both tokens, which must form a single token
+fn paste(lhs: &Token, rhs: &Token) -> Result<Token, Error> {
+    let s = lhs.kind.spelling() + &rhs.kind.spelling();
+    let file = SourceFile::new(lhs.file.name.clone(), s);
+    let invalid = || error(lhs, "pasting does not give a valid token");
+    let mut tokens = Tokenizer::new(Rc::new(file)).run().map_err(|_| invalid())?;
+    // without the `Eof` first
+    tokens.remove(0);
+    match (tokens.pop(), tokens.is_empty()) {
+        (Some(t), true) => Ok(Token {
+            kind: t.kind,
+            ..lhs.clone()
+        }),
+        _ => Err(invalid()),
+    }
+}
@@ -0,0 +1,224 @@
+//! Register allocation by linear scan over live intervals, as in "Linear Scan Register
+//! Allocation", by Poletto and Sarkar.
+//!
+//! Each value has a single place, a register or a stack slot, from its first definition to
+//! its last use. %rax is left to the emitter, which also stages the operands of some
+//! instructions in %rcx, %rdx, %rdi or %r11: a value live across such an instruction takes
+//! another register. A value live across a call takes a callee-saved register, the others
+//! take caller-saved ones first, as those need no saving in the prologue.
+
+use crate::ir::{BinOp, Function, Op, Value};
+use std::collections::HashSet;
+
+// those the emitter never stages operands in first
+pub const CALLER_SAVED: [&str; 8] = ["%rsi", "%r8", "%r9", "%r10", "%rcx", "%rdx", "%rdi", "%r11"];
+pub const CALLEE_SAVED: [&str; 5] = ["%rbx", "%r12", "%r13", "%r14", "%r15"];
+
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Location {
+    Reg(&'static str),
+    // the n-th 8 bytes of the spill area of the frame
+    Spill(usize),
+}
+
+pub struct Allocation {
+    // none for the values never defined
+    pub locations: Vec<Option<Location>>,
+    pub spills: usize,
+    // the callee-saved registers used, to be saved in the prologue
+    pub callee_saved: Vec<&'static str>,
+}
+
+// where a value is live, in positions of the instructions in the order of the blocks
+struct Interval {
+    value: Value,
+    start: usize,
+    end: usize,
+    across_call: bool,
+    // the registers the emitter stages operands in while the value is live
+    staged: HashSet<&'static str>,
+}
+
+pub fn allocate(func: &Function) -> Allocation {
+    let mut intervals = intervals(func);
+    intervals.sort_by_key(|interval| (interval.start, interval.value));
+    let mut locations = vec![None; func.value_count];
+    let mut spills = 0;
+    let mut spill = |locations: &mut Vec<Option<Location>>, value: Value| {
+        locations[value.0] = Some(Location::Spill(spills));
+        spills += 1;
+    };
+    // the intervals with a register, and the register
+    let mut active: Vec<(&Interval, &'static str)> = vec![];
+    for interval in &intervals {
+        // a value last read by an instruction can share its register with the result
+        active.retain(|(other, _)| other.end > interval.start);
+        let allowed = |reg: &str| {
+            (!interval.across_call || CALLEE_SAVED.contains(&reg)) && !interval.staged.contains(reg)
+        };
+        let free = CALLER_SAVED
+            .iter()
+            .chain(CALLEE_SAVED.iter())
+            .find(|reg| allowed(reg) && active.iter().all(|(_, used)| used != *reg));
+        if let Some(reg) = free {
+            locations[interval.value.0] = Some(Location::Reg(reg));
+            active.push((interval, reg));
+            continue;
+        }
+        // the interval that ends last goes to the stack, as it would block a register longest
+        let victim = active
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, reg))| allowed(reg))
+            .max_by_key(|(_, (other, _))| other.end);
+        match victim {
+            Some((i, (other, reg))) if other.end > interval.end => {
+                let (other, reg) = (*other, *reg);
+                spill(&mut locations, other.value);
+                locations[interval.value.0] = Some(Location::Reg(reg));
+                active[i] = (interval, reg);
+            }
+            _ => spill(&mut locations, interval.value),
+        }
+    }
+    let used: HashSet<_> = locations.iter().filter_map(|loc| *loc).collect();
+    let callee_saved = CALLEE_SAVED
+        .iter()
+        .copied()
+        .filter(|reg| used.contains(&Location::Reg(reg)))
+        .collect();
+    Allocation {
+        locations,
+        spills,
+        callee_saved,
+    }
+}
+
+// A value is live from a definition to a use, and through the blocks it is live into or out
+// of. Each block has a position per instruction, and one for its terminator.
+fn intervals(func: &Function) -> Vec<Interval> {
+    let mut starts = vec![];
+    let mut pos = 0;
+    for block in &func.blocks {
+        starts.push(pos);
+        pos += block.insts.len() + 1;
+    }
+    let (live_in, live_out) = liveness(func);
+
+    let mut start = vec![usize::MAX; func.value_count];
+    let mut end = vec![0; func.value_count];
+    let mut cover = |v: Value, pos: usize| {
+        start[v.0] = start[v.0].min(pos);
+        end[v.0] = end[v.0].max(pos);
+    };
+    // the arguments are all in their registers on entry, and moved to their places at once
+    let params = func.blocks[0]
+        .insts
+        .iter()
+        .take_while(|inst| matches!(inst.op, Op::Param(_)))
+        .count();
+    let mut calls = vec![];
+    // the parameters may be moved to their places through %r11
+    let mut staging = vec![(params, &["%r11"][..])];
+    for (b, block) in func.blocks.iter().enumerate() {
+        let term = starts[b] + block.insts.len();
+        live_in[b].iter().for_each(|v| cover(*v, starts[b]));
+        live_out[b].iter().for_each(|v| cover(*v, term));
+        for (i, inst) in block.insts.iter().enumerate() {
+            let pos = starts[b] + i;
+            inst.op.operands().into_iter().for_each(|v| cover(v, pos));
+            if let Some(v) = inst.result {
+                cover(v, pos);
+            }
+            if let Op::Param(_) = inst.op {
+                cover(inst.result.unwrap(), 0);
+                cover(inst.result.unwrap(), params);
+            }
+            if let Op::Call { .. } = inst.op {
+                calls.push(pos);
+            }
+            staging.push((pos, staged(&inst.op)));
+        }
+        block
+            .term
+            .operands()
+            .into_iter()
+            .for_each(|v| cover(v, term));
+    }
+    (0..func.value_count)
+        .filter(|v| start[*v] != usize::MAX)
+        .map(|v| Interval {
+            value: Value(v),
+            start: start[v],
+            end: end[v],
+            across_call: calls.iter().any(|call| start[v] < *call && *call < end[v]),
+            // the result is only written once the operands are read
+            staged: staging
+                .iter()
+                .filter(|(pos, _)| start[v] < *pos && *pos <= end[v])
+                .flat_map(|(_, regs)| regs.iter().copied())
+                .collect(),
+        })
+        .collect()
+}
+
+// the registers the emitter stages the operands of `op` in, besides %rax
+fn staged(op: &Op) -> &'static [&'static str] {
+    match op {
+        Op::Binary(BinOp::SDiv, ..)
+        | Op::Binary(BinOp::SRem, ..)
+        | Op::Binary(BinOp::UDiv, ..)
+        | Op::Binary(BinOp::URem, ..) => &["%rdx", "%rdi"],
+        Op::Binary(BinOp::Shl, ..) | Op::Binary(BinOp::LShr, ..) | Op::Binary(BinOp::AShr, ..) => {
+            &["%rcx"]
+        }
+        Op::Store { .. } => &["%rdi"],
+        Op::Copy { .. } | Op::Zero { .. } => &["%rdi", "%rcx"],
+        _ => &[],
+    }
+}
+
+// the values live on entry to each block, and on exit from it
+fn liveness(func: &Function) -> (Vec<HashSet<Value>>, Vec<HashSet<Value>>) {
+    let count = func.blocks.len();
+    // the values read before being defined in each block, and those defined in it
+    let mut uses = vec![HashSet::new(); count];
+    let mut defs = vec![HashSet::new(); count];
+    for (b, block) in func.blocks.iter().enumerate() {
+        for inst in &block.insts {
+            for v in inst.op.operands() {
+                if !defs[b].contains(&v) {
+                    uses[b].insert(v);
+                }
+            }
+            if let Some(v) = inst.result {
+                defs[b].insert(v);
+            }
+        }
+        for v in block.term.operands() {
+            if !defs[b].contains(&v) {
+                uses[b].insert(v);
+            }
+        }
+    }
+    let mut live_in = uses.clone();
+    let mut live_out = vec![HashSet::new(); count];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for b in (0..count).rev() {
+            let mut out = HashSet::new();
+            for succ in func.blocks[b].term.successors() {
+                out.extend(live_in[succ.0].iter().copied());
+            }
+            let mut in_ = uses[b].clone();
+            in_.extend(out.difference(&defs[b]).copied());
+            if in_.len() != live_in[b].len() || out.len() != live_out[b].len() {
+                changed = true;
+            }
+            live_in[b] = in_;
+            live_out[b] = out;
+        }
+    }
+    (live_in, live_out)
+}
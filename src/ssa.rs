@@ -0,0 +1,429 @@
+//! Static single assignment form, where locals are values as the results of instructions are.
+//!
+//! The slots whose address is only loaded from and stored to are promoted: each store gives
+//! the local a new value, each load reads the last one, and where paths with other values
+//! meet, a phi picks the one of the path taken. A value stored to a slot narrower than 64
+//! bits is extended there, as the loads would extend it, so that it is extended once however
+//! often it is read. Out of SSA form, each phi is a value moved to at the end of the
+//! predecessors.
+
+use crate::cfg::{self, DomTree};
+use crate::ir::{Block, BlockId, Function, Inst, Op, Slot, Terminator, Ty, Value};
+use std::collections::{HashMap, HashSet};
+use std::iter;
+use std::mem;
+
+/// Promotes what locals it can to values, with phis where their values meet. The blocks
+/// that cannot be reached are dropped: they have no dominator.
+pub fn construct(func: &mut Function) {
+    cfg::remove_unreachable(func);
+    let (promoted, signed) = promotable(func);
+    let mut addr_slot = HashMap::new();
+    for inst in func.blocks.iter().flat_map(|block| &block.insts) {
+        if let (Some(v), Op::SlotAddr(slot)) = (inst.result, &inst.op) {
+            if promoted[slot.0] {
+                addr_slot.insert(v, *slot);
+            }
+        }
+    }
+
+    let dom = DomTree::new(func);
+    let phi_slot = insert_phis(func, &dom, &addr_slot);
+    let undef = Value(func.value_count);
+    func.value_count += 1;
+    let mut renaming = Renaming {
+        func,
+        dom: &dom,
+        addr_slot,
+        phi_slot,
+        signed,
+        stacks: vec![vec![]; promoted.len()],
+        replaced: HashMap::new(),
+        undef,
+    };
+    renaming.rename(BlockId(0));
+
+    remove_dead_phis(func);
+    // a local read before it is written to is 0, rather than what was on the stack
+    if uses(func).contains(&undef) {
+        let entry = &mut func.blocks[0].insts;
+        let params = entry
+            .iter()
+            .take_while(|inst| matches!(inst.op, Op::Param(_)))
+            .count();
+        let inst = Inst {
+            result: Some(undef),
+            op: Op::Const(0),
+            span: None,
+        };
+        entry.insert(params, inst);
+    }
+    remove_slots(func, &promoted);
+}
+
+// The slots whose address is only loaded from and stored to, always as the same type, and
+// whether their loads sign extend, if there are any. The area `va_arg` reads the registers
+// from is written to by the prologue.
+fn promotable(func: &Function) -> (Vec<bool>, Vec<Option<bool>>) {
+    let mut addr_slot = HashMap::new();
+    for inst in func.blocks.iter().flat_map(|block| &block.insts) {
+        if let (Some(v), Op::SlotAddr(slot)) = (inst.result, &inst.op) {
+            addr_slot.insert(v, *slot);
+        }
+    }
+    let mut promoted = vec![true; func.slots.len()];
+    let mut tys: Vec<Option<Ty>> = vec![None; func.slots.len()];
+    let mut signed: Vec<Option<bool>> = vec![None; func.slots.len()];
+    let mut access = |promoted: &mut Vec<bool>, addr: Value, ty: Ty, sign: Option<bool>| {
+        if let Some(slot) = addr_slot.get(&addr) {
+            if tys[slot.0].map_or(false, |other| other != ty) {
+                promoted[slot.0] = false;
+            }
+            tys[slot.0] = Some(ty);
+            if let Some(sign) = sign {
+                if signed[slot.0].map_or(false, |other| other != sign) {
+                    promoted[slot.0] = false;
+                }
+                signed[slot.0] = Some(sign);
+            }
+        }
+    };
+    for block in &func.blocks {
+        for inst in &block.insts {
+            let escaping = match &inst.op {
+                Op::Load { ty, signed, addr } => {
+                    access(&mut promoted, *addr, *ty, Some(*signed));
+                    vec![]
+                }
+                Op::Store { ty, addr, value } => {
+                    access(&mut promoted, *addr, *ty, None);
+                    vec![*value]
+                }
+                op => op.operands(),
+            };
+            for v in escaping {
+                if let Some(slot) = addr_slot.get(&v) {
+                    promoted[slot.0] = false;
+                }
+            }
+        }
+        for v in block.term.operands() {
+            if let Some(slot) = addr_slot.get(&v) {
+                promoted[slot.0] = false;
+            }
+        }
+    }
+    if let Some(slot) = func.va_area {
+        promoted[slot.0] = false;
+    }
+    (promoted, signed)
+}
+
+// Puts phis where the stores to each promoted slot reach along with other values, as in the
+// iterated dominance frontier of the blocks with a store. They are filled in by the renaming.
+fn insert_phis(
+    func: &mut Function,
+    dom: &DomTree,
+    addr_slot: &HashMap<Value, Slot>,
+) -> HashMap<Value, Slot> {
+    let frontiers = dom.frontiers(func);
+    let mut stores: HashMap<Slot, Vec<BlockId>> = HashMap::new();
+    for (b, block) in func.blocks.iter().enumerate() {
+        for inst in &block.insts {
+            if let Op::Store { addr, .. } = inst.op {
+                if let Some(slot) = addr_slot.get(&addr) {
+                    stores.entry(*slot).or_default().push(BlockId(b));
+                }
+            }
+        }
+    }
+    let mut stores: Vec<_> = stores.into_iter().collect();
+    // in order, for the values to be numbered the same from one run to the next
+    stores.sort();
+    let mut phi_slot = HashMap::new();
+    for (slot, mut work) in stores {
+        let mut has_phi = vec![false; func.blocks.len()];
+        while let Some(block) = work.pop() {
+            for frontier in &frontiers[block.0] {
+                if has_phi[frontier.0] {
+                    continue;
+                }
+                has_phi[frontier.0] = true;
+                let phi = Value(func.value_count);
+                func.value_count += 1;
+                let inst = Inst {
+                    result: Some(phi),
+                    op: Op::Phi(vec![]),
+                    span: None,
+                };
+                func.blocks[frontier.0].insts.insert(0, inst);
+                phi_slot.insert(phi, slot);
+                // the phi is a new value of the local, which reaches further
+                work.push(*frontier);
+            }
+        }
+    }
+    phi_slot
+}
+
+struct Renaming<'a> {
+    func: &'a mut Function,
+    dom: &'a DomTree,
+    // the addresses of the promoted slots
+    addr_slot: HashMap<Value, Slot>,
+    phi_slot: HashMap<Value, Slot>,
+    // whether the loads of each slot sign extend, if there are any
+    signed: Vec<Option<bool>>,
+    // the values of each slot along the path from the entry, the current one last
+    stacks: Vec<Vec<Value>>,
+    // the loads, and the values they read
+    replaced: HashMap<Value, Value>,
+    // the value of a local never written to
+    undef: Value,
+}
+
+impl Renaming<'_> {
+    // Goes through the dominator tree from `block`: the values of a block are those of its
+    // immediate dominator, as changed by its phis and stores.
+    fn rename(&mut self, block: BlockId) {
+        let mut pushed = vec![];
+        let insts = mem::take(&mut self.func.blocks[block.0].insts);
+        let mut kept = Vec::with_capacity(insts.len());
+        for mut inst in insts {
+            for v in inst.op.operands_mut() {
+                if let Some(value) = self.replaced.get(v) {
+                    *v = *value;
+                }
+            }
+            let phi_slot = inst.result.and_then(|v| self.phi_slot.get(&v).copied());
+            match inst.op {
+                Op::Phi(_) if phi_slot.is_some() => {
+                    let slot = phi_slot.unwrap();
+                    self.stacks[slot.0].push(inst.result.unwrap());
+                    pushed.push(slot);
+                }
+                Op::SlotAddr(_) if self.addr_slot.contains_key(&inst.result.unwrap()) => {
+                    continue;
+                }
+                Op::Load { addr, .. } if self.addr_slot.contains_key(&addr) => {
+                    let current = self.current(self.addr_slot[&addr]);
+                    self.replaced.insert(inst.result.unwrap(), current);
+                    continue;
+                }
+                Op::Store { ty, addr, value } if self.addr_slot.contains_key(&addr) => {
+                    let slot = self.addr_slot[&addr];
+                    pushed.push(slot);
+                    // what is stored is truncated to the size of the slot, and extended by loads
+                    match self.signed[slot.0] {
+                        Some(signed) if ty != Ty::I64 => {
+                            let extended = Value(self.func.value_count);
+                            self.func.value_count += 1;
+                            self.stacks[slot.0].push(extended);
+                            inst.result = Some(extended);
+                            inst.op = Op::Extend {
+                                ty,
+                                signed,
+                                arg: value,
+                            };
+                        }
+                        _ => {
+                            self.stacks[slot.0].push(value);
+                            continue;
+                        }
+                    }
+                }
+                _ => {}
+            }
+            kept.push(inst);
+        }
+        let term = &mut self.func.blocks[block.0].term;
+        for v in term.operands_mut() {
+            if let Some(value) = self.replaced.get(v) {
+                *v = *value;
+            }
+        }
+        self.func.blocks[block.0].insts = kept;
+
+        // each edge into a successor gives its phis a value
+        for succ in self.func.blocks[block.0].term.successors() {
+            let slots: Vec<_> = self.func.blocks[succ.0]
+                .insts
+                .iter()
+                .filter_map(|inst| inst.result.and_then(|v| self.phi_slot.get(&v)))
+                .copied()
+                .collect();
+            let values: Vec<_> = slots.iter().map(|slot| self.current(*slot)).collect();
+            let phis = self.func.blocks[succ.0].insts.iter_mut();
+            for (inst, value) in phis
+                .filter(|inst| matches!(inst.op, Op::Phi(_)))
+                .zip(values)
+            {
+                if let Op::Phi(incoming) = &mut inst.op {
+                    incoming.push((block, value));
+                }
+            }
+        }
+
+        let dom = self.dom;
+        for child in dom.children(block) {
+            self.rename(*child);
+        }
+        for slot in pushed {
+            self.stacks[slot.0].pop();
+        }
+    }
+
+    fn current(&self, slot: Slot) -> Value {
+        self.stacks[slot.0].last().copied().unwrap_or(self.undef)
+    }
+}
+
+// the values read by an instruction other than the one defining them
+fn uses(func: &Function) -> HashSet<Value> {
+    let mut used = HashSet::new();
+    for block in &func.blocks {
+        for inst in &block.insts {
+            let operands = inst.op.operands().into_iter();
+            used.extend(operands.filter(|v| Some(*v) != inst.result));
+        }
+        used.extend(block.term.operands());
+    }
+    used
+}
+
+// Phis are put wherever values of a local meet, whether it is read after or not.
+fn remove_dead_phis(func: &mut Function) {
+    loop {
+        let used = uses(func);
+        let mut removed = false;
+        for block in &mut func.blocks {
+            block.insts.retain(|inst| {
+                let dead = matches!(inst.op, Op::Phi(_)) && !used.contains(&inst.result.unwrap());
+                removed |= dead;
+                !dead
+            });
+        }
+        if !removed {
+            return;
+        }
+    }
+}
+
+fn remove_slots(func: &mut Function, promoted: &[bool]) {
+    let mut number = vec![None; func.slots.len()];
+    let slots = mem::take(&mut func.slots);
+    for (i, slot) in slots.into_iter().enumerate() {
+        if !promoted[i] {
+            number[i] = Some(Slot(func.slots.len()));
+            func.slots.push(slot);
+        }
+    }
+    for inst in func.blocks.iter_mut().flat_map(|block| &mut block.insts) {
+        if let Op::SlotAddr(slot) = &mut inst.op {
+            *slot = number[slot.0].expect("the address of a promoted slot");
+        }
+    }
+    func.va_area = func.va_area.map(|slot| number[slot.0].unwrap());
+}
+
+/// Replaces the phis by moves at the end of the predecessors. The moves of an edge happen
+/// all at once, as the phis read their values on entry to the block: a phi may read
+/// another one of the same block, as when two locals are swapped in a loop.
+pub fn destruct(func: &mut Function) {
+    split_critical_edges(func);
+    let mut moves: Vec<Vec<(Value, Value)>> = vec![vec![]; func.blocks.len()];
+    for block in &mut func.blocks {
+        let phis = block
+            .insts
+            .iter()
+            .take_while(|inst| matches!(inst.op, Op::Phi(_)))
+            .count();
+        for inst in block.insts.drain(..phis) {
+            if let Op::Phi(incoming) = inst.op {
+                for (pred, value) in incoming {
+                    moves[pred.0].push((inst.result.unwrap(), value));
+                }
+            }
+        }
+    }
+    for (b, moves) in moves.into_iter().enumerate() {
+        let insts = sequentialize(moves, &mut func.value_count);
+        func.blocks[b].insts.extend(insts);
+    }
+}
+
+// The moves for a phi go at the end of the predecessor, where they must happen on the way
+// to the block of the phi only: a branch to it from a block with other successors goes
+// through a block of its own.
+fn split_critical_edges(func: &mut Function) {
+    let count = func.blocks.len();
+    let has_phis = |block: &Block| matches!(block.insts.first(), Some(Inst { op: Op::Phi(_), .. }));
+    let mut split = vec![vec![]; count];
+    for (pred, edges) in split.iter_mut().enumerate() {
+        let succs = func.blocks[pred].term.successors();
+        if succs.len() < 2 {
+            continue;
+        }
+        for (i, succ) in succs.into_iter().enumerate() {
+            if !has_phis(&func.blocks[succ.0]) {
+                continue;
+            }
+            let edge = BlockId(func.blocks.len());
+            func.blocks.push(Block {
+                insts: vec![],
+                term: Terminator::Jump(succ),
+            });
+            *func.blocks[pred].term.successors_mut()[i] = edge;
+            for inst in &mut func.blocks[succ.0].insts {
+                if let Op::Phi(incoming) = &mut inst.op {
+                    let from = incoming.iter_mut().find(|(b, _)| b.0 == pred);
+                    from.expect("a phi without a value for an edge").0 = edge;
+                }
+            }
+            edges.push(edge);
+        }
+    }
+    // each edge block comes right after its predecessor
+    let order: Vec<_> = (0..count)
+        .flat_map(|b| iter::once(BlockId(b)).chain(split[b].iter().copied()))
+        .collect();
+    func.reorder(&order);
+}
+
+// Orders moves that happen at once, `(dst, src)`, so that none overwrites a value another
+// still reads. In a cycle, as in a swap, one of the values is saved in a new one first.
+fn sequentialize(moves: Vec<(Value, Value)>, value_count: &mut usize) -> Vec<Inst> {
+    let mut pending: Vec<_> = moves.into_iter().filter(|(dst, src)| dst != src).collect();
+    let mut insts = vec![];
+    while !pending.is_empty() {
+        let ready = pending
+            .iter()
+            .position(|(dst, _)| pending.iter().all(|(_, src)| src != dst));
+        match ready {
+            Some(i) => {
+                let (dst, src) = pending.remove(i);
+                insts.push(Inst {
+                    result: Some(dst),
+                    op: Op::Move(src),
+                    span: None,
+                });
+            }
+            None => {
+                let saved = pending[0].0;
+                let temp = Value(*value_count);
+                *value_count += 1;
+                insts.push(Inst {
+                    result: Some(temp),
+                    op: Op::Move(saved),
+                    span: None,
+                });
+                for (_, src) in &mut pending {
+                    if *src == saved {
+                        *src = temp;
+                    }
+                }
+            }
+        }
+    }
+    insts
+}
@@ -1,37 +1,100 @@
+use crate::{Error, ErrorKind, Span};
+use std::rc::Rc;
+
+/// A file being compiled, shared by the tokens read from it.
+#[derive(Debug)]
+pub struct SourceFile {
+    // as given on the command line or found in an include path
+    pub name: String,
+    pub contents: String,
+}
+
+impl SourceFile {
+    pub fn new(name: String, contents: String) -> Self {
+        Self { name, contents }
+    }
+}
+
 // NOTE(chrde): it does not support UTF-8
-pub struct Tokenizer<'a> {
-    code: &'a str,
+pub struct Tokenizer {
+    file: Rc<SourceFile>,
     pos: usize,
     tokens: Vec<Token>,
+    // the line of `line_pos`, counted lazily
+    line: usize,
+    line_pos: usize,
+    // whether the next token starts a line, and whether whitespace precedes it
+    at_bol: bool,
+    has_space: bool,
 }
 
-impl<'a> Tokenizer<'a> {
-    pub fn new(code: &'a str) -> Self {
+impl Tokenizer {
+    pub fn new(file: Rc<SourceFile>) -> Self {
         Self {
-            code,
+            file,
             pos: 0,
             tokens: vec![],
+            line: 1,
+            line_pos: 0,
+            at_bol: true,
+            has_space: false,
         }
     }
 
-    pub fn run(mut self) -> Vec<Token> {
-        while self.pos < self.code.len() {
-            self.whitespace();
+    /// The tokens of the file in reverse order, so that the next one can be popped.
+    /// The last token of the file, and so the first one returned, is `Eof`.
+    pub fn run(mut self) -> Result<Vec<Token>, Error> {
+        while self.pos < self.file.contents.len() {
+            let pos = self.pos;
+            self.whitespace()?;
             self.digit();
+            self.string()?;
+            self.character()?;
             self.punctuator();
             self.ident();
+            if pos == self.pos {
+                return Err(self.error(pos, "unexpected character"));
+            }
         }
+        self.at_bol = true;
+        // errors at the end of the input point after its last line, not below it
+        let end = self.file.contents.trim_end().len();
+        self.push(end, TokenKind::Eof);
         self.tokens.reverse();
-        self.tokens
+        Ok(self.tokens)
+    }
+
+    fn error(&self, pos: usize, msg: &str) -> Error {
+        Error {
+            kind: ErrorKind::Generic(msg.to_string()),
+            span: Some(Span::new(self.file.clone(), pos)),
+        }
     }
 
+    // a punctuator that was just consumed
     pub fn push_token(&mut self, kind: TokenKind) {
+        self.push(self.pos - kind.len(), kind);
+    }
+
+    fn push(&mut self, start: usize, kind: TokenKind) {
+        let lines = self.file.contents[self.line_pos..start]
+            .matches('\n')
+            .count();
+        self.line += lines;
+        self.line_pos = start;
         let t = Token {
-            start: self.pos,
-            end: kind.len(),
+            start,
+            end: self.pos,
             kind,
+            file: self.file.clone(),
+            line: self.line,
+            at_bol: self.at_bol,
+            has_space: self.has_space,
+            hideset: vec![],
         };
         self.tokens.push(t);
+        self.at_bol = false;
+        self.has_space = false;
     }
 
     fn punctuator(&mut self) {
@@ -45,6 +108,12 @@ impl<'a> Tokenizer<'a> {
                 if self.peek() == Some('=') {
                     self.advance();
                     self.push_token(TokenKind::GreaterEqual);
+                } else if self.rest().starts_with(">=") {
+                    self.pos += 2;
+                    self.push_token(TokenKind::GreaterGreaterEqual);
+                } else if self.peek() == Some('>') {
+                    self.advance();
+                    self.push_token(TokenKind::GreaterGreater);
                 } else {
                     self.push_token(TokenKind::Greater);
                 }
@@ -54,6 +123,12 @@ impl<'a> Tokenizer<'a> {
                 if self.peek() == Some('=') {
                     self.advance();
                     self.push_token(TokenKind::LowerEqual);
+                } else if self.rest().starts_with("<=") {
+                    self.pos += 2;
+                    self.push_token(TokenKind::LowerLowerEqual);
+                } else if self.peek() == Some('<') {
+                    self.advance();
+                    self.push_token(TokenKind::LowerLower);
                 } else {
                     self.push_token(TokenKind::Lower);
                 }
@@ -78,23 +153,112 @@ impl<'a> Tokenizer<'a> {
             }
             Some('+') => {
                 self.advance();
-                self.push_token(TokenKind::Plus);
+                if self.peek() == Some('+') {
+                    self.advance();
+                    self.push_token(TokenKind::PlusPlus);
+                } else if self.peek() == Some('=') {
+                    self.advance();
+                    self.push_token(TokenKind::PlusEqual);
+                } else {
+                    self.push_token(TokenKind::Plus);
+                }
             }
             Some('-') => {
                 self.advance();
-                self.push_token(TokenKind::Minus)
+                if self.peek() == Some('>') {
+                    self.advance();
+                    self.push_token(TokenKind::Arrow);
+                } else if self.peek() == Some('-') {
+                    self.advance();
+                    self.push_token(TokenKind::MinusMinus);
+                } else if self.peek() == Some('=') {
+                    self.advance();
+                    self.push_token(TokenKind::MinusEqual);
+                } else {
+                    self.push_token(TokenKind::Minus)
+                }
+            }
+            Some('.') => {
+                self.advance();
+                if self.rest().starts_with("..") {
+                    self.advance();
+                    self.advance();
+                    self.push_token(TokenKind::Ellipsis);
+                } else {
+                    self.push_token(TokenKind::Dot);
+                }
             }
             Some('*') => {
                 self.advance();
-                self.push_token(TokenKind::Star);
+                if self.peek() == Some('=') {
+                    self.advance();
+                    self.push_token(TokenKind::StarEqual);
+                } else {
+                    self.push_token(TokenKind::Star);
+                }
+            }
+            Some('%') => {
+                self.advance();
+                if self.peek() == Some('=') {
+                    self.advance();
+                    self.push_token(TokenKind::PercentEqual);
+                } else {
+                    self.push_token(TokenKind::Percent);
+                }
+            }
+            Some('|') => {
+                self.advance();
+                if self.peek() == Some('|') {
+                    self.advance();
+                    self.push_token(TokenKind::PipePipe);
+                } else if self.peek() == Some('=') {
+                    self.advance();
+                    self.push_token(TokenKind::PipeEqual);
+                } else {
+                    self.push_token(TokenKind::Pipe);
+                }
+            }
+            Some('^') => {
+                self.advance();
+                if self.peek() == Some('=') {
+                    self.advance();
+                    self.push_token(TokenKind::CaretEqual);
+                } else {
+                    self.push_token(TokenKind::Caret);
+                }
+            }
+            Some('~') => {
+                self.advance();
+                self.push_token(TokenKind::Tilde);
+            }
+            Some('?') => {
+                self.advance();
+                self.push_token(TokenKind::Question);
+            }
+            Some(':') => {
+                self.advance();
+                self.push_token(TokenKind::Colon);
             }
             Some('/') => {
                 self.advance();
-                self.push_token(TokenKind::Slash);
+                if self.peek() == Some('=') {
+                    self.advance();
+                    self.push_token(TokenKind::SlashEqual);
+                } else {
+                    self.push_token(TokenKind::Slash);
+                }
             }
             Some('&') => {
                 self.advance();
-                self.push_token(TokenKind::Amp);
+                if self.peek() == Some('&') {
+                    self.advance();
+                    self.push_token(TokenKind::AmpAmp);
+                } else if self.peek() == Some('=') {
+                    self.advance();
+                    self.push_token(TokenKind::AmpEqual);
+                } else {
+                    self.push_token(TokenKind::Amp);
+                }
             }
             Some('}') => {
                 self.advance();
@@ -112,6 +276,27 @@ impl<'a> Tokenizer<'a> {
                 self.advance();
                 self.push_token(TokenKind::LeftParen);
             }
+            Some(']') => {
+                self.advance();
+                self.push_token(TokenKind::RightBracket);
+            }
+            Some('[') => {
+                self.advance();
+                self.push_token(TokenKind::LeftBracket);
+            }
+            Some(',') => {
+                self.advance();
+                self.push_token(TokenKind::Comma);
+            }
+            Some('#') => {
+                self.advance();
+                if self.peek() == Some('#') {
+                    self.advance();
+                    self.push_token(TokenKind::HashHash);
+                } else {
+                    self.push_token(TokenKind::Hash);
+                }
+            }
             _ => {}
         }
     }
@@ -121,7 +306,7 @@ impl<'a> Tokenizer<'a> {
     }
 
     fn rest(&self) -> &str {
-        &self.code[self.pos..]
+        &self.file.contents[self.pos..]
     }
 
     fn advance(&mut self) {
@@ -129,16 +314,43 @@ impl<'a> Tokenizer<'a> {
     }
 
     fn token_from(&self, pos: usize) -> &str {
-        &self.code[pos..self.pos]
+        &self.file.contents[pos..self.pos]
     }
 
-    fn whitespace(&mut self) {
-        if self
-            .peek()
-            .map(|c| c.is_ascii_whitespace())
-            .unwrap_or(false)
-        {
-            self.advance();
+    // A backslash before a newline joins the lines. Comments count as whitespace.
+    fn whitespace(&mut self) -> Result<(), Error> {
+        loop {
+            if self.rest().starts_with("//") {
+                let len = self.rest().find('\n').unwrap_or_else(|| self.rest().len());
+                self.pos += len;
+                self.has_space = true;
+                continue;
+            }
+            if self.rest().starts_with("/*") {
+                let len = match self.rest()[2..].find("*/") {
+                    Some(len) => len + 4,
+                    None => return Err(self.error(self.pos, "unclosed block comment")),
+                };
+                self.pos += len;
+                self.has_space = true;
+                continue;
+            }
+            match self.peek() {
+                Some('\\') if self.rest().starts_with("\\\n") => {
+                    self.pos += 2;
+                    self.has_space = true;
+                }
+                Some('\n') => {
+                    self.advance();
+                    self.at_bol = true;
+                    self.has_space = true;
+                }
+                Some(c) if c.is_ascii_whitespace() => {
+                    self.advance();
+                    self.has_space = true;
+                }
+                _ => return Ok(()),
+            }
         }
     }
 
@@ -159,30 +371,153 @@ impl<'a> Tokenizer<'a> {
             }
         }
         if start != self.pos {
-            let token = Token {
-                start,
-                end: self.pos,
-                kind: identifier(self.token_from(start)),
-            };
-            self.tokens.push(token)
+            let kind = identifier(self.token_from(start));
+            self.push(start, kind);
+        }
+    }
+
+    fn string(&mut self) -> Result<(), Error> {
+        if self.peek() != Some('"') {
+            return Ok(());
+        }
+        let start = self.pos;
+        self.advance();
+        let mut bytes = vec![];
+        loop {
+            match self.peek() {
+                Some('"') => break,
+                Some('\\') => {
+                    self.advance();
+                    bytes.push(self.escape(start)?);
+                }
+                Some('\n') | None => return Err(self.error(start, "unclosed string literal")),
+                Some(c) => {
+                    self.advance();
+                    bytes.push(c as u8);
+                }
+            }
+        }
+        self.advance();
+        self.push(start, TokenKind::Str(bytes));
+        Ok(())
+    }
+
+    // `'a'` is an `int`, with the value of a `char`
+    fn character(&mut self) -> Result<(), Error> {
+        if self.peek() != Some('\'') {
+            return Ok(());
+        }
+        let start = self.pos;
+        self.advance();
+        let c = match self.peek() {
+            Some('\\') => {
+                self.advance();
+                self.escape(start)?
+            }
+            Some(c) if c != '\n' => {
+                self.advance();
+                c as u8
+            }
+            _ => return Err(self.error(start, "unclosed character literal")),
+        };
+        if self.peek() != Some('\'') {
+            return Err(self.error(start, "unclosed character literal"));
         }
+        self.advance();
+        self.push(start, TokenKind::CharLit(c));
+        Ok(())
+    }
+
+    // the character after a backslash, in the literal at `start`
+    fn escape(&mut self, start: usize) -> Result<u8, Error> {
+        let c = match self.peek() {
+            Some(c) => c,
+            None => return Err(self.error(start, "unclosed string literal")),
+        };
+        self.advance();
+        let c = match c {
+            'a' => 7,
+            'b' => 8,
+            't' => b'\t',
+            'n' => b'\n',
+            'v' => 11,
+            'f' => 12,
+            'r' => b'\r',
+            'e' => 27,
+            '0'..='7' => {
+                let mut val = c.to_digit(8).unwrap();
+                for _ in 0..2 {
+                    match self.peek().and_then(|c| c.to_digit(8)) {
+                        Some(d) => {
+                            self.advance();
+                            val = val * 8 + d;
+                        }
+                        None => break,
+                    }
+                }
+                val as u8
+            }
+            'x' => {
+                let mut val = 0;
+                while let Some(d) = self.peek().and_then(|c| c.to_digit(16)) {
+                    self.advance();
+                    val = val * 16 + d;
+                }
+                val as u8
+            }
+            c => c as u8,
+        };
+        Ok(c)
     }
 
+    // A preprocessing number: a digit, then letters, digits, `_`, `.` and the signs of exponents,
+    // as in `1e+5`. It is kept as spelled for `integer_constant` to read, so that pasting `0x` and
+    // `ff`, or `12` and `_z`, gives a single token.
     fn digit(&mut self) {
         let start = self.pos;
-        while self.peek().map(|c| c.is_ascii_digit()).unwrap_or(false) {
-            self.advance();
+        if !self.peek().map(|c| c.is_ascii_digit()).unwrap_or(false) {
+            return;
         }
-        if start != self.pos {
-            let val: usize = self.token_from(start).parse().unwrap();
-            let token = Token {
-                start,
-                end: self.pos,
-                kind: TokenKind::Num(val),
-            };
-            self.tokens.push(token)
+        loop {
+            let sign = self.rest().get(..2).map(str::to_ascii_lowercase);
+            if let Some("e+") | Some("e-") | Some("p+") | Some("p-") = sign.as_deref() {
+                self.pos += 2;
+            } else if self
+                .peek()
+                .map_or(false, |c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+            {
+                self.advance();
+            } else {
+                break;
+            }
         }
+        let spelling = self.token_from(start).to_string();
+        self.push(start, TokenKind::Num(spelling));
+    }
+}
+
+/// The value and the lowercase suffix, as `ul`, of an integer constant spelled as `spelling`, or
+/// `None` if it is not a valid one.
+pub fn integer_constant(spelling: &str) -> Option<(usize, String)> {
+    let spelling = spelling.to_ascii_lowercase();
+    let (radix, digits) = if let Some(digits) = spelling.strip_prefix("0x") {
+        (16, digits)
+    } else if let Some(digits) = spelling.strip_prefix("0b") {
+        (2, digits)
+    } else if spelling.starts_with('0') {
+        (8, &spelling[..])
+    } else {
+        (10, &spelling[..])
+    };
+    let len = digits
+        .find(|c: char| !c.is_digit(radix))
+        .unwrap_or_else(|| digits.len());
+    let suffix = &digits[len..];
+    if len == 0 || !["", "u", "l", "ul", "lu", "ll", "ull", "llu"].contains(&suffix) {
+        return None;
     }
+    let value = usize::from_str_radix(&digits[..len], radix).ok()?;
+    Some((value, suffix.to_string()))
 }
 
 fn identifier(ident: &str) -> TokenKind {
@@ -193,16 +528,49 @@ fn identifier(ident: &str) -> TokenKind {
         "else" => Else,
         "for" => For,
         "while" => While,
+        "do" => Do,
+        "switch" => Switch,
+        "case" => Case,
+        "default" => Default,
+        "break" => Break,
+        "continue" => Continue,
+        "goto" => Goto,
+        "void" => Void,
+        "_Bool" => Bool,
+        "char" => Char,
+        "short" => Short,
+        "int" => Int,
+        "long" => Long,
+        "float" => Float,
+        "double" => Double,
+        "signed" | "__signed" | "__signed__" => Signed,
+        "unsigned" => Unsigned,
+        "struct" => Struct,
+        "union" => Union,
+        "enum" => Enum,
+        "typedef" => Typedef,
+        "const" | "__const" | "__const__" => Const,
+        "volatile" | "__volatile" | "__volatile__" => Volatile,
+        "restrict" | "__restrict" | "__restrict__" => Restrict,
+        "inline" | "__inline" | "__inline__" => Inline,
+        "_Noreturn" => Noreturn,
+        "static" => Static,
+        "extern" => Extern,
+        "sizeof" => Sizeof,
         i => Ident(i.to_string()),
     }
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum TokenKind {
-    Num(usize),
+    // as spelled, with its prefix and suffix
+    Num(String),
     Ident(String),
+    Str(Vec<u8>),
+    CharLit(u8),
     LeftCurly,
     LeftParen,
+    LeftBracket,
     Star,
     Slash,
     Plus,
@@ -210,6 +578,23 @@ pub enum TokenKind {
     Amp,
     RightParen,
     RightCurly,
+    RightBracket,
+    Comma,
+    Dot,
+    Ellipsis,
+    Arrow,
+    Hash,
+    HashHash,
+    Percent,
+    Pipe,
+    PipePipe,
+    AmpAmp,
+    Caret,
+    Tilde,
+    Question,
+    Colon,
+    LowerLower,
+    GreaterGreater,
     Equal,
     EqualEqual,
     Not,
@@ -218,6 +603,18 @@ pub enum TokenKind {
     Greater,
     LowerEqual,
     GreaterEqual,
+    PlusPlus,
+    MinusMinus,
+    PlusEqual,
+    MinusEqual,
+    StarEqual,
+    SlashEqual,
+    PercentEqual,
+    AmpEqual,
+    PipeEqual,
+    CaretEqual,
+    LowerLowerEqual,
+    GreaterGreaterEqual,
     Semicolon,
     Eof,
 
@@ -227,14 +624,46 @@ pub enum TokenKind {
     Else,
     For,
     While,
+    Do,
+    Switch,
+    Case,
+    Default,
+    Break,
+    Continue,
+    Goto,
+    Void,
+    Bool,
+    Char,
+    Short,
+    Int,
+    Long,
+    Float,
+    Double,
+    Signed,
+    Unsigned,
+    Struct,
+    Union,
+    Enum,
+    Typedef,
+    Const,
+    Volatile,
+    Restrict,
+    Inline,
+    Noreturn,
+    Static,
+    Extern,
+    Sizeof,
 }
 
 impl TokenKind {
     fn len(&self) -> usize {
         use TokenKind::*;
         match self {
-            EqualEqual | LowerEqual | GreaterEqual => 2,
-            Num(_) => unreachable!(),
+            EqualEqual | LowerEqual | GreaterEqual | Arrow | HashHash | PipePipe | AmpAmp
+            | LowerLower | GreaterGreater | PlusPlus | MinusMinus | PlusEqual | MinusEqual
+            | StarEqual | SlashEqual | PercentEqual | AmpEqual | PipeEqual | CaretEqual => 2,
+            Ellipsis | LowerLowerEqual | GreaterGreaterEqual => 3,
+            Num(_) | Str(_) | CharLit(_) => unreachable!(),
             Eof => 0,
             _ => 1,
         }
@@ -242,26 +671,177 @@ impl TokenKind {
 }
 
 impl TokenKind {
+    /// The source text of the token, as used by the preprocessor to stringify and paste tokens.
+    pub fn spelling(&self) -> String {
+        use TokenKind::*;
+        let s = match self {
+            Num(spelling) => return spelling.clone(),
+            Ident(name) => return name.clone(),
+            Str(bytes) => return string_spelling(bytes),
+            CharLit(c) => return char_spelling(*c),
+            LeftCurly => "{",
+            LeftParen => "(",
+            LeftBracket => "[",
+            Star => "*",
+            Slash => "/",
+            Plus => "+",
+            Minus => "-",
+            Amp => "&",
+            RightParen => ")",
+            RightCurly => "}",
+            RightBracket => "]",
+            Comma => ",",
+            Dot => ".",
+            Ellipsis => "...",
+            Arrow => "->",
+            Hash => "#",
+            HashHash => "##",
+            Percent => "%",
+            Pipe => "|",
+            PipePipe => "||",
+            AmpAmp => "&&",
+            Caret => "^",
+            Tilde => "~",
+            Question => "?",
+            Colon => ":",
+            LowerLower => "<<",
+            GreaterGreater => ">>",
+            Equal => "=",
+            EqualEqual => "==",
+            Not => "!",
+            NotEqual => "!=",
+            Lower => "<",
+            Greater => ">",
+            LowerEqual => "<=",
+            GreaterEqual => ">=",
+            PlusPlus => "++",
+            MinusMinus => "--",
+            PlusEqual => "+=",
+            MinusEqual => "-=",
+            StarEqual => "*=",
+            SlashEqual => "/=",
+            PercentEqual => "%=",
+            AmpEqual => "&=",
+            PipeEqual => "|=",
+            CaretEqual => "^=",
+            LowerLowerEqual => "<<=",
+            GreaterGreaterEqual => ">>=",
+            Semicolon => ";",
+            Eof => "",
+            Return => "return",
+            If => "if",
+            Else => "else",
+            For => "for",
+            While => "while",
+            Do => "do",
+            Switch => "switch",
+            Case => "case",
+            Default => "default",
+            Break => "break",
+            Continue => "continue",
+            Goto => "goto",
+            Void => "void",
+            Bool => "_Bool",
+            Char => "char",
+            Short => "short",
+            Int => "int",
+            Long => "long",
+            Float => "float",
+            Double => "double",
+            Signed => "signed",
+            Unsigned => "unsigned",
+            Struct => "struct",
+            Union => "union",
+            Enum => "enum",
+            Typedef => "typedef",
+            Const => "const",
+            Volatile => "volatile",
+            Restrict => "restrict",
+            Inline => "inline",
+            Noreturn => "_Noreturn",
+            Static => "static",
+            Extern => "extern",
+            Sizeof => "sizeof",
+        };
+        s.to_string()
+    }
+
+    /// Identifiers and keywords, which can both be macro names.
+    pub fn ident_name(&self) -> Option<String> {
+        use TokenKind::*;
+        match self {
+            Ident(name) => Some(name.clone()),
+            Num(_) | Str(_) | CharLit(_) => None,
+            k if identifier(&k.spelling()) == *k => Some(k.spelling()),
+            _ => None,
+        }
+    }
+
     pub fn binary(&self) -> bool {
         use TokenKind::*;
         matches!(
             self,
             Star | Slash
+                | Percent
                 | Plus
                 | Minus
+                | LowerLower
+                | GreaterGreater
+                | Amp
+                | Pipe
+                | Caret
                 | EqualEqual
                 | NotEqual
                 | Lower
                 | Greater
                 | LowerEqual
                 | GreaterEqual
+                | AmpAmp
+                | PipePipe
+                | Comma
         )
     }
 }
 
+// a string literal, quoted and escaped again
+fn string_spelling(bytes: &[u8]) -> String {
+    let mut s = String::from("\"");
+    for &b in bytes {
+        match b {
+            b'"' | b'\\' => {
+                s.push('\\');
+                s.push(b as char);
+            }
+            b'\n' => s.push_str("\\n"),
+            b'\t' => s.push_str("\\t"),
+            0x20..=0x7e => s.push(b as char),
+            b => s.push_str(&format!("\\{:03o}", b)),
+        }
+    }
+    s.push('"');
+    s
+}
+
+fn char_spelling(c: u8) -> String {
+    match c {
+        b'\'' | b'\\' => format!("'\\{}'", c as char),
+        b'\n' => "'\\n'".to_string(),
+        b'\t' => "'\\t'".to_string(),
+        0x20..=0x7e => format!("'{}'", c as char),
+        c => format!("'\\{:03o}'", c),
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Token {
     pub start: usize,
     pub end: usize,
     pub kind: TokenKind,
+    pub file: Rc<SourceFile>,
+    pub line: usize,
+    // first token of a line, where preprocessor directives start
+    pub at_bol: bool,
+    pub has_space: bool,
+    // the macros this token came from, which must not expand again
+    pub hideset: Vec<String>,
 }
@@ -0,0 +1,292 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Type {
+    Void,
+    Bool,
+    Char,
+    Short,
+    Int,
+    Long,
+    UChar,
+    UShort,
+    UInt,
+    ULong,
+    // floating-point types are only declared, as in the prototypes of the C library
+    Float,
+    Double,
+    LongDouble,
+    Ptr(Box<Type>),
+    Array(Box<Type>, usize),
+    Struct(Rc<StructType>),
+    Func(Rc<FuncType>),
+}
+
+#[derive(Clone, Debug)]
+pub struct FuncType {
+    pub ret: Type,
+    pub params: Vec<Param>,
+    // takes arguments after `...`
+    pub variadic: bool,
+}
+
+/// Parameter names are kept for the definition of a function, they are not part of its type.
+#[derive(Clone, Debug)]
+pub struct Param {
+    pub name: Option<String>,
+    pub ty: Type,
+}
+
+impl PartialEq for FuncType {
+    fn eq(&self, other: &Self) -> bool {
+        self.ret == other.ret
+            && self.variadic == other.variadic
+            && self.params.len() == other.params.len()
+            && self
+                .params
+                .iter()
+                .zip(&other.params)
+                .all(|(a, b)| a.ty == b.ty)
+    }
+}
+
+/// A struct or union. Its members are known once it is defined, which may come after
+/// its tag is first used, as in `struct node { struct node *next; }`.
+pub struct StructType {
+    pub tag: Option<String>,
+    pub is_union: bool,
+    layout: RefCell<Option<Layout>>,
+}
+
+struct Layout {
+    members: Vec<Member>,
+    size: usize,
+    align: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Member {
+    pub name: String,
+    pub ty: Type,
+    pub offset: usize,
+}
+
+// each definition of a struct is a distinct type
+impl PartialEq for StructType {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+// members are left out, they may refer to the struct itself
+impl fmt::Debug for StructType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = if self.is_union { "union" } else { "struct" };
+        match &self.tag {
+            Some(tag) => write!(f, "{} {}", kind, tag),
+            None => write!(f, "{} <anonymous>", kind),
+        }
+    }
+}
+
+impl StructType {
+    pub fn new(tag: Option<String>, members: Vec<(String, Type)>) -> Self {
+        let st = Self::declare(tag, false);
+        st.define(members);
+        st
+    }
+
+    /// An incomplete type, as declared by `struct tag;`.
+    pub fn declare(tag: Option<String>, is_union: bool) -> Self {
+        Self {
+            tag,
+            is_union,
+            layout: RefCell::new(None),
+        }
+    }
+
+    /// Lays out `members` in declaration order, each one at its natural alignment.
+    /// The members of a union all start at its beginning.
+    pub fn define(&self, members: Vec<(String, Type)>) {
+        let mut offset = 0;
+        let mut size = 0;
+        let mut align = 1;
+        let members = members
+            .into_iter()
+            .map(|(name, ty)| {
+                if !self.is_union {
+                    offset = align_to(offset, ty.align());
+                }
+                align = align.max(ty.align());
+                size = size.max(offset + ty.size());
+                let member = Member { name, offset, ty };
+                if !self.is_union {
+                    offset += member.ty.size();
+                }
+                member
+            })
+            .collect();
+        *self.layout.borrow_mut() = Some(Layout {
+            members,
+            size: align_to(size, align),
+            align,
+        });
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.layout.borrow().is_some()
+    }
+
+    pub fn members(&self) -> Vec<Member> {
+        self.layout
+            .borrow()
+            .as_ref()
+            .map_or(vec![], |l| l.members.clone())
+    }
+
+    pub fn member(&self, name: &str) -> Option<Member> {
+        self.members().into_iter().find(|m| m.name == name)
+    }
+
+    pub fn size(&self) -> usize {
+        self.layout.borrow().as_ref().map_or(0, |l| l.size)
+    }
+
+    pub fn align(&self) -> usize {
+        self.layout.borrow().as_ref().map_or(1, |l| l.align)
+    }
+}
+
+impl Type {
+    pub fn pointer_to(base: Type) -> Self {
+        Type::Ptr(Box::new(base))
+    }
+
+    pub fn array_of(base: Type, len: usize) -> Self {
+        Type::Array(Box::new(base), len)
+    }
+
+    pub fn func(ret: Type, params: Vec<Param>, variadic: bool) -> Self {
+        Type::Func(Rc::new(FuncType {
+            ret,
+            params,
+            variadic,
+        }))
+    }
+
+    /// `va_list`, as laid out by the System V x86-64 ABI: a one element array of
+    /// `struct { int gp_offset; int fp_offset; char *overflow_arg_area; char *reg_save_area; }`
+    pub fn va_list() -> Self {
+        thread_local! {
+            static VA_LIST_TAG: Rc<StructType> = va_list_tag();
+        }
+        let tag = VA_LIST_TAG.with(|tag| tag.clone());
+        Type::array_of(Type::Struct(tag), 1)
+    }
+
+    pub fn size(&self) -> usize {
+        match self {
+            // as in GNU C, so that `void *` arithmetic is byte-wise
+            Type::Void => 1,
+            Type::Bool | Type::Char | Type::UChar => 1,
+            Type::Short | Type::UShort => 2,
+            Type::Int | Type::UInt | Type::Float => 4,
+            Type::Long | Type::ULong | Type::Double | Type::Ptr(_) => 8,
+            Type::LongDouble => 16,
+            Type::Array(base, len) => base.size() * len,
+            Type::Struct(s) => s.size(),
+            // as in GNU C
+            Type::Func(_) => 1,
+        }
+    }
+
+    pub fn align(&self) -> usize {
+        match self {
+            Type::Array(base, _) => base.align(),
+            Type::Struct(s) => s.align(),
+            t => t.size(),
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            Type::Bool
+                | Type::Char
+                | Type::Short
+                | Type::Int
+                | Type::Long
+                | Type::UChar
+                | Type::UShort
+                | Type::UInt
+                | Type::ULong
+        )
+    }
+
+    pub fn is_unsigned(&self) -> bool {
+        matches!(
+            self,
+            Type::Bool | Type::UChar | Type::UShort | Type::UInt | Type::ULong
+        )
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, Type::Float | Type::Double | Type::LongDouble)
+    }
+
+    /// The pointee of a pointer, or the element type of an array.
+    pub fn base(&self) -> Option<&Type> {
+        match self {
+            Type::Ptr(base) | Type::Array(base, _) => Some(base),
+            _ => None,
+        }
+    }
+
+    /// Arrays used as values are converted to a pointer to their first element,
+    /// functions to a pointer to the function.
+    pub fn decay(&self) -> Type {
+        match self {
+            Type::Array(base, _) => Type::pointer_to(base.as_ref().clone()),
+            Type::Func(_) => Type::pointer_to(self.clone()),
+            t => t.clone(),
+        }
+    }
+
+    /// Arrays and structs are not held in registers: their value is their address.
+    pub fn is_aggregate(&self) -> bool {
+        matches!(self, Type::Array(..) | Type::Struct(_))
+    }
+
+    /// The type of the function called through an expression of this type,
+    /// either a function or a pointer to one.
+    pub fn callee(&self) -> Option<&FuncType> {
+        match self {
+            Type::Func(f) => Some(f),
+            Type::Ptr(base) => match base.as_ref() {
+                Type::Func(f) => Some(f),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+pub fn align_to(n: usize, align: usize) -> usize {
+    align * ((n + align - 1) / align)
+}
+
+fn va_list_tag() -> Rc<StructType> {
+    let members = vec![
+        ("gp_offset".to_string(), Type::Int),
+        ("fp_offset".to_string(), Type::Int),
+        (
+            "overflow_arg_area".to_string(),
+            Type::pointer_to(Type::Char),
+        ),
+        ("reg_save_area".to_string(), Type::pointer_to(Type::Char)),
+    ];
+    let tag = Some("__va_list_tag".to_string());
+    Rc::new(StructType::new(tag, members))
+}
@@ -0,0 +1,285 @@
+//! The x86-64 code of a function as the code generator emits it, an instruction per line, and
+//! what each instruction reads and writes, for the peephole optimiser.
+
+use std::fmt;
+
+/// The code of a function, from its label to its `ret`.
+pub struct Listing {
+    pub lines: Vec<Line>,
+}
+
+#[derive(Clone)]
+pub enum Line {
+    Label(String),
+    Insn(Insn),
+}
+
+/// An instruction in AT&T syntax: the operands are as written, the destination last.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Insn {
+    pub op: String,
+    pub args: Vec<String>,
+}
+
+impl Insn {
+    pub fn new(op: &str, args: &[&str]) -> Self {
+        Self {
+            op: op.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        }
+    }
+
+    // a `jcc`, with its condition code
+    pub fn jcc(&self) -> Option<&str> {
+        self.op.strip_prefix('j').filter(|cc| *cc != "mp")
+    }
+
+    pub fn setcc(&self) -> Option<&str> {
+        self.op.strip_prefix("set")
+    }
+
+    // ends a run of straight-line code
+    pub fn is_jump(&self) -> bool {
+        self.op.starts_with('j') || self.op == "ret"
+    }
+
+    /// The registers the instruction reads, and those it writes, flags included. A write to
+    /// the low byte or word of a register keeps the rest of it, so it reads the register too.
+    pub fn effects(&self) -> (Regs, Regs) {
+        let mut uses = Regs::default();
+        let mut defs = Regs::default();
+        // the registers in an operand: itself, or those of an address
+        let read = |uses: &mut Regs, arg: &str| {
+            for reg in arg.split(|c| c == '(' || c == ',' || c == ')') {
+                if let Some((reg, _)) = register(reg.trim_start_matches('*')) {
+                    uses.0 |= reg;
+                }
+            }
+        };
+        let write = |uses: &mut Regs, defs: &mut Regs, arg: &str| match register(arg) {
+            Some((reg, width)) => {
+                defs.0 |= reg;
+                if width < 4 {
+                    uses.0 |= reg;
+                }
+            }
+            None => read(uses, arg),
+        };
+        let (src, dst) = match self.args.as_slice() {
+            [src, dst] => (Some(src.as_str()), Some(dst.as_str())),
+            [dst] => (None, Some(dst.as_str())),
+            _ => (None, None),
+        };
+        src.into_iter().for_each(|src| read(&mut uses, src));
+        let op = self.op.as_str();
+        match op {
+            "mov" | "movq" | "movl" | "movw" | "movb" | "lea" => {
+                write(&mut uses, &mut defs, dst.unwrap())
+            }
+            _ if op.starts_with("movz") || op.starts_with("movs") && op != "movsd" => {
+                write(&mut uses, &mut defs, dst.unwrap())
+            }
+            // `xor %eax, %eax` does not depend on %eax
+            "xor" if src == dst => {
+                uses = Regs::default();
+                write(&mut uses, &mut defs, dst.unwrap());
+                defs.0 |= FLAGS;
+            }
+            "add" | "sub" | "imul" | "and" | "or" | "xor" | "shl" | "shr" | "sar" | "neg" => {
+                read(&mut uses, dst.unwrap());
+                write(&mut uses, &mut defs, dst.unwrap());
+                defs.0 |= FLAGS;
+            }
+            "not" => {
+                read(&mut uses, dst.unwrap());
+                write(&mut uses, &mut defs, dst.unwrap());
+            }
+            "cmp" | "test" => {
+                read(&mut uses, dst.unwrap());
+                defs.0 |= FLAGS;
+            }
+            "cqo" => {
+                uses.0 |= reg("%rax");
+                defs.0 |= reg("%rdx");
+            }
+            "idiv" | "div" => {
+                read(&mut uses, dst.unwrap());
+                uses.0 |= reg("%rax") | reg("%rdx");
+                defs.0 |= reg("%rax") | reg("%rdx") | FLAGS;
+            }
+            "push" => {
+                read(&mut uses, dst.unwrap());
+                uses.0 |= reg("%rsp");
+                defs.0 |= reg("%rsp");
+            }
+            "pop" => {
+                uses.0 |= reg("%rsp");
+                defs.0 |= reg("%rsp");
+                write(&mut uses, &mut defs, dst.unwrap());
+            }
+            // `rep stosb`
+            "rep" => {
+                uses.0 |= reg("%rax") | reg("%rcx") | reg("%rdi");
+                defs.0 |= reg("%rcx") | reg("%rdi");
+            }
+            // the arguments, %al for a variadic callee, and the target; anything the callee
+            // may change is written
+            "call" => {
+                read(&mut uses, dst.unwrap());
+                for arg in &["%rdi", "%rsi", "%rdx", "%rcx", "%r8", "%r9", "%rax", "%rsp"] {
+                    uses.0 |= reg(arg);
+                }
+                for saved in &[
+                    "%rax", "%rcx", "%rdx", "%rsi", "%rdi", "%r8", "%r9", "%r10", "%r11",
+                ] {
+                    defs.0 |= reg(saved);
+                }
+                defs.0 |= FLAGS;
+            }
+            // the result, possibly in %rdx too, and what the caller expects kept
+            "ret" => {
+                for kept in &[
+                    "%rax", "%rdx", "%rbx", "%rbp", "%rsp", "%r12", "%r13", "%r14", "%r15",
+                ] {
+                    uses.0 |= reg(kept);
+                }
+            }
+            "jmp" => {}
+            _ if self.jcc().is_some() => uses.0 |= FLAGS,
+            _ if self.setcc().is_some() => {
+                uses.0 |= FLAGS;
+                write(&mut uses, &mut defs, dst.unwrap());
+            }
+            // anything else reads everything it names, and the flags
+            _ => {
+                dst.into_iter().for_each(|dst| read(&mut uses, dst));
+                uses.0 |= FLAGS;
+            }
+        }
+        (uses, defs)
+    }
+}
+
+/// A set of registers, by the 64-bit register they are part of, and the flags.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct Regs(pub u32);
+
+pub const FLAGS: u32 = 1 << 16;
+
+impl Regs {
+    pub fn contains(self, regs: u32) -> bool {
+        self.0 & regs != 0
+    }
+}
+
+// the 64-bit registers, in the order of their bits in `Regs`
+const REGS_64: [&str; 16] = [
+    "%rax", "%rbx", "%rcx", "%rdx", "%rsi", "%rdi", "%rbp", "%rsp", "%r8", "%r9", "%r10", "%r11",
+    "%r12", "%r13", "%r14", "%r15",
+];
+
+// the bit of a register known to be one
+fn reg(name: &str) -> u32 {
+    register(name).expect("not a register").0
+}
+
+/// The bit of the 64-bit register an operand is part of, and how many bytes of it the
+/// operand is; none if it is not a general purpose register.
+pub fn register(operand: &str) -> Option<(u32, usize)> {
+    let name = operand.strip_prefix('%')?;
+    let (base, width) = if let Some(n) = name
+        .strip_prefix('r')
+        .filter(|n| n.starts_with(|c: char| c.is_ascii_digit()))
+    {
+        // %r8 to %r15, and their %r8d, %r8w and %r8b
+        match n.find(|c: char| !c.is_ascii_digit()) {
+            Some(i) => (
+                format!("r{}", &n[..i]),
+                match &n[i..] {
+                    "d" => 4,
+                    "w" => 2,
+                    "b" => 1,
+                    _ => return None,
+                },
+            ),
+            None => (format!("r{}", n), 8),
+        }
+    } else {
+        let (width, rest) = match name.len() {
+            3 if name.starts_with('r') => (8, &name[1..]),
+            3 if name.starts_with('e') => (4, &name[1..]),
+            // %sil and %dil
+            3 => (1, &name[..2]),
+            2 if name.ends_with('l') || name.ends_with('h') => (1, &name[..1]),
+            2 => (2, name),
+            _ => return None,
+        };
+        let base = match rest {
+            "a" | "ax" => "rax",
+            "b" | "bx" => "rbx",
+            "c" | "cx" => "rcx",
+            "d" | "dx" => "rdx",
+            "si" => "rsi",
+            "di" => "rdi",
+            "bp" => "rbp",
+            "sp" => "rsp",
+            _ => return None,
+        };
+        (base.to_string(), width)
+    };
+    let i = REGS_64.iter().position(|r| r[1..] == base)?;
+    Some((1 << i, width))
+}
+
+/// The 32-bit part of a 64-bit register.
+pub fn low32(reg64: &str) -> String {
+    match reg64.strip_prefix("%r") {
+        Some(n) if n.starts_with(|c: char| c.is_ascii_digit()) => format!("{}d", reg64),
+        Some(n) => format!("%e{}", n),
+        None => reg64.to_string(),
+    }
+}
+
+impl Listing {
+    /// Reads back the code as the code generator writes it: each line a label, or an
+    /// indented instruction with its operands separated by commas.
+    pub fn parse(text: &str) -> Self {
+        let lines = text
+            .lines()
+            .map(|line| match line.strip_suffix(':') {
+                Some(label) if !line.starts_with(' ') => Line::Label(label.to_string()),
+                _ => {
+                    let line = line.trim();
+                    let (op, args) = match line.find(' ') {
+                        Some(i) => (&line[..i], line[i + 1..].split(", ").collect()),
+                        None => (line, vec![]),
+                    };
+                    Line::Insn(Insn::new(op, &args))
+                }
+            })
+            .collect();
+        Self { lines }
+    }
+}
+
+impl fmt::Display for Listing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            match line {
+                Line::Label(label) => writeln!(f, "{}:", label)?,
+                Line::Insn(insn) => writeln!(f, "{}", insn)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Insn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "  {}", self.op)?;
+        if !self.args.is_empty() {
+            write!(f, " {}", self.args.join(", "))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+//! Compares the code generated for each program in `tests/asm` with the snapshots next to it:
+//! `<name>.s` for the assembly and `<name>.ir` for the intermediate representation. The programs
+//! are compiled at `-O2`, or at the level a `// OPT-LEVEL: <n>` line in them says. After an
+//! intended change to the generated code, `TOYCC_BLESS=1 cargo test` writes the new output to
+//! the snapshots, to be reviewed with the change.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toycc::{compile_file, Options};
+
+const CORPUS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/asm");
+
+// unchanged lines shown around each change
+const CONTEXT: usize = 3;
+
+#[test]
+fn assembly_snapshots() {
+    let bless = env::var_os("TOYCC_BLESS").map_or(false, |v| v != "0");
+    let mut programs: Vec<PathBuf> = fs::read_dir(CORPUS)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "c"))
+        .collect();
+    programs.sort();
+
+    let mut failures = vec![];
+    for program in &programs {
+        if let Err(e) = check(program, bless) {
+            failures.push(e);
+        }
+    }
+    if !failures.is_empty() {
+        panic!(
+            "{} of {} snapshots differ, run with TOYCC_BLESS=1 to update them:\n\n{}",
+            failures.len(),
+            programs.len(),
+            failures.join("\n")
+        );
+    }
+}
+
+fn check(program: &Path, bless: bool) -> Result<(), String> {
+    let source = fs::read_to_string(program).map_err(|e| e.to_string())?;
+    let level = source
+        .lines()
+        .find_map(|line| line.strip_prefix("// OPT-LEVEL: "))
+        .map_or(Ok(2), str::parse)
+        .map_err(|e| format!("{}: invalid level: {}", program.display(), e))?;
+    let options = Options {
+        opt_level: level,
+        ..Options::default()
+    };
+    let output = match compile_file(program, &options) {
+        Ok(output) => output,
+        Err(diagnostics) => {
+            let messages: Vec<_> = diagnostics.iter().map(ToString::to_string).collect();
+            return Err(format!("{}: {}", program.display(), messages.concat()));
+        }
+    };
+    let mut errors = vec![];
+    for (ext, actual) in [("s", output.assembly), ("ir", output.ir)].iter() {
+        let snapshot = program.with_extension(ext);
+        if bless {
+            fs::write(&snapshot, actual).map_err(|e| e.to_string())?;
+            continue;
+        }
+        let expected = fs::read_to_string(&snapshot)
+            .map_err(|e| format!("cannot read {}: {}", snapshot.display(), e))?;
+        if expected == *actual {
+            continue;
+        }
+        let expected: Vec<_> = expected.lines().collect();
+        let actual: Vec<_> = actual.lines().collect();
+        errors.push(format!(
+            "{} differs from the snapshot (-) in the generated code (+)\n{}",
+            snapshot.display(),
+            diff(&expected, &actual)
+        ));
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("\n"))
+    }
+}
+
+enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+// The changes from `old` to `new` along a longest common subsequence of their lines, with
+// `-` before removed lines, `+` before added ones, and a few unchanged lines around them.
+fn diff(old: &[&str], new: &[&str]) -> String {
+    // lcs[i][j]: the length of the longest common subsequence of old[i..] and new[j..]
+    let mut lcs = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut lines = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push(Line::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push(Line::Removed(old[i]));
+            i += 1;
+        } else {
+            lines.push(Line::Added(new[j]));
+            j += 1;
+        }
+    }
+
+    let changed: Vec<_> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, Line::Same(_)))
+        .map(|(n, _)| n)
+        .collect();
+    let near_change = |n: usize| {
+        changed
+            .iter()
+            .any(|&c| c + CONTEXT >= n && n + CONTEXT >= c)
+    };
+    let mut out = String::new();
+    let mut skipped = false;
+    for (n, line) in lines.iter().enumerate() {
+        if !near_change(n) {
+            skipped = true;
+            continue;
+        }
+        if skipped {
+            out.push_str("  ...\n");
+            skipped = false;
+        }
+        let (mark, text) = match line {
+            Line::Same(text) => (' ', text),
+            Line::Removed(text) => ('-', text),
+            Line::Added(text) => ('+', text),
+        };
+        out.push(mark);
+        out.push_str(text);
+        out.push('\n');
+    }
+    out
+}
@@ -0,0 +1,12 @@
+  .globl main
+  .text
+main:
+  push %rbp
+  mov %rsp, %rbp
+  mov $27, %rsi
+  mov %rsi, %rax
+.L.return.main:
+  mov %rbp, %rsp
+  pop %rbp
+  ret
+  .section .note.GNU-stack,"",@progbits
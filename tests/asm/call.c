@@ -0,0 +1,9 @@
+int add8(int a, int b, int c, int d, int e, int f, int g, int h) {
+    return a + b + c + d + e + f + g + h;
+}
+
+int apply(int (*f)(int), int x) { return f(x); }
+
+int sq(int x) { return x * x; }
+
+int main() { return add8(1, 2, 3, 4, 5, 6, 7, 8) + apply(sq, 3); }
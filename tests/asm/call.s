@@ -0,0 +1,159 @@
+  .globl add8
+  .text
+add8:
+  push %rbp
+  mov %rsp, %rbp
+  push %rbx
+  sub $8, %rsp
+  mov %rcx, %r10
+  mov %r8, %rcx
+  mov %rsi, %r8
+  mov %rdi, %rsi
+  mov 16(%rbp), %rdi
+  mov 24(%rbp), %rbx
+  mov %r9, %r11
+  mov %rdx, %r9
+  mov %r11, %rdx
+  mov %rsi, %rax
+  movslq %eax, %rax
+  mov %rax, %rsi
+  mov %r8, %rax
+  movslq %eax, %rax
+  mov %rax, %r8
+  mov %r9, %rax
+  movslq %eax, %rax
+  mov %rax, %r9
+  mov %r10, %rax
+  movslq %eax, %rax
+  mov %rax, %r10
+  mov %rcx, %rax
+  movslq %eax, %rax
+  mov %rax, %rcx
+  mov %rdx, %rax
+  movslq %eax, %rax
+  mov %rax, %rdx
+  mov %rdi, %rax
+  movslq %eax, %rax
+  mov %rax, %rdi
+  mov %rbx, %rax
+  movslq %eax, %rax
+  mov %rax, %r11
+  mov %rsi, %rax
+  add %r8, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+  add %r9, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+  add %r10, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+  add %rcx, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+  add %rdx, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+  add %rdi, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+  add %r11, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+.L.return.add8:
+  lea -8(%rbp), %rsp
+  pop %rbx
+  pop %rbp
+  ret
+  .globl apply
+  .text
+apply:
+  push %rbp
+  mov %rsp, %rbp
+  mov %rsi, %r8
+  mov %rdi, %rsi
+  mov %r8, %rax
+  movslq %eax, %rax
+  mov %rax, %r8
+  mov %r8, %rdi
+  mov %rsi, %r11
+  xor %eax, %eax
+  call *%r11
+  mov %rax, %rsi
+  mov %rsi, %rax
+  movslq %eax, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+.L.return.apply:
+  mov %rbp, %rsp
+  pop %rbp
+  ret
+  .globl sq
+  .text
+sq:
+  push %rbp
+  mov %rsp, %rbp
+  mov %rdi, %rsi
+  mov %rsi, %rax
+  movslq %eax, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+  imul %rsi, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+.L.return.sq:
+  mov %rbp, %rsp
+  pop %rbp
+  ret
+  .globl main
+  .text
+main:
+  push %rbp
+  mov %rsp, %rbp
+  push %rbx
+  sub $8, %rsp
+  mov $1, %rsi
+  mov $2, %r8
+  mov $3, %r9
+  mov $4, %r10
+  mov $5, %rcx
+  mov $6, %rdx
+  mov $7, %rdi
+  mov $8, %r11
+  push %r11
+  push %rdi
+  mov %rsi, %rdi
+  mov %r8, %rsi
+  mov %rcx, %r8
+  mov %r10, %rcx
+  mov %rdx, %rax
+  mov %r9, %rdx
+  mov %rax, %r9
+  xor %eax, %eax
+  call add8
+  add $16, %rsp
+  mov %rax, %rsi
+  mov %rsi, %rax
+  movslq %eax, %rax
+  mov %rax, %rbx
+  lea sq(%rip), %rax
+  mov %rax, %rsi
+  mov $3, %r8
+  mov %r8, %rdi
+  mov %rsi, %r11
+  xor %eax, %eax
+  call *%r11
+  mov %rax, %rsi
+  mov %rsi, %rax
+  movslq %eax, %rax
+  mov %rax, %rsi
+  mov %rbx, %rax
+  add %rsi, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+.L.return.main:
+  lea -8(%rbp), %rsp
+  pop %rbx
+  pop %rbp
+  ret
+  .section .note.GNU-stack,"",@progbits
@@ -0,0 +1,8 @@
+int main() {
+    char c = 200;
+    unsigned char uc = 200;
+    short s = -2;
+    unsigned u = 3;
+    _Bool b = 5;
+    return (char)(c + uc) + (long)s + (unsigned long)u + b;
+}
@@ -0,0 +1,5 @@
+int main() {
+    unsigned long u = 1;
+    long s = -1;
+    return (s < 0) + (u > 0) + (s == u) + (s != u);
+}
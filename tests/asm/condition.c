@@ -0,0 +1,11 @@
+int in_range(int x, int lo, int hi) {
+    if (x < lo || x > hi)
+        return 0;
+    while (x > lo && !(x == hi))
+        x--;
+    return x;
+}
+
+int main() {
+    return in_range(5, 2, 9);
+}
@@ -0,0 +1,84 @@
+  .globl in_range
+  .text
+in_range:
+  push %rbp
+  mov %rsp, %rbp
+  mov %rsi, %r8
+  mov %rdi, %rsi
+  mov %rdx, %r9
+  mov %rsi, %rax
+  movslq %eax, %rax
+  mov %rax, %rsi
+  mov %r8, %rax
+  movslq %eax, %rax
+  mov %rax, %r8
+  mov %r9, %rax
+  movslq %eax, %rax
+  mov %rax, %r9
+  cmp %r8, %rsi
+  jl .L.block.in_range.2
+.L.block.in_range.1:
+  cmp %r9, %rsi
+  jle .L.block.in_range.3
+.L.block.in_range.2:
+  xor %r10d, %r10d
+  mov %r10, %rax
+  jmp .L.return.in_range
+.L.block.in_range.3:
+  cmp %r8, %rsi
+  jle .L.block.in_range.5
+.L.block.in_range.4:
+  mov %rsi, %r10
+  jmp .L.block.in_range.6
+.L.block.in_range.5:
+  jmp .L.block.in_range.11
+.L.block.in_range.6:
+  cmp %r9, %r10
+  jne .L.block.in_range.8
+.L.block.in_range.7:
+  mov %r10, %rsi
+  jmp .L.block.in_range.11
+.L.block.in_range.8:
+  mov $1, %rcx
+  mov %r10, %rax
+  sub %rcx, %rax
+  mov %rax, %rcx
+  mov %rcx, %rax
+  movslq %eax, %rax
+  mov %rax, %rcx
+  cmp %r8, %rcx
+  jle .L.block.in_range.10
+.L.block.in_range.9:
+  mov %rcx, %r10
+  jmp .L.block.in_range.6
+.L.block.in_range.10:
+  mov %rcx, %rsi
+.L.block.in_range.11:
+  mov %rsi, %rax
+.L.return.in_range:
+  mov %rbp, %rsp
+  pop %rbp
+  ret
+  .globl main
+  .text
+main:
+  push %rbp
+  mov %rsp, %rbp
+  mov $5, %rsi
+  mov $2, %r8
+  mov $9, %r9
+  mov %rsi, %rdi
+  mov %r8, %rsi
+  mov %r9, %rdx
+  xor %eax, %eax
+  call in_range
+  mov %rax, %rsi
+  mov %rsi, %rax
+  movslq %eax, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+.L.return.main:
+  mov %rbp, %rsp
+  pop %rbp
+  ret
+  .section .note.GNU-stack,"",@progbits
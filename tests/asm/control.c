@@ -0,0 +1,14 @@
+int main() {
+    int s = 0;
+    for (int i = 0; i < 10; i++) {
+        if (i % 2)
+            continue;
+        s += i;
+    }
+    while (s > 20)
+        s -= 1;
+    do {
+        s++;
+    } while (0);
+    return s;
+}
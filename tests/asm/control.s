@@ -0,0 +1,83 @@
+  .globl main
+  .text
+main:
+  push %rbp
+  mov %rsp, %rbp
+  xor %esi, %esi
+  xor %r8d, %r8d
+.L.block.main.1:
+  mov $2, %r9
+  mov %r8, %rax
+  mov %r9, %rdi
+  cqo
+  idiv %rdi
+  mov %rdx, %rax
+  mov %rax, %r9
+  test %r9, %r9
+  je .L.block.main.3
+.L.block.main.2:
+  mov %rsi, %r9
+  jmp .L.block.main.4
+.L.block.main.3:
+  mov %rsi, %rax
+  add %r8, %rax
+  mov %rax, %r10
+  mov %r10, %rax
+  movslq %eax, %rax
+  mov %rax, %r10
+  mov %r10, %r9
+.L.block.main.4:
+  mov $1, %r10
+  mov %r8, %rax
+  add %r10, %rax
+  mov %rax, %r10
+  mov %r10, %rax
+  movslq %eax, %rax
+  mov %rax, %r10
+  mov $10, %rcx
+  cmp %rcx, %r10
+  jge .L.block.main.6
+.L.block.main.5:
+  mov %r10, %r8
+  mov %r9, %rsi
+  jmp .L.block.main.1
+.L.block.main.6:
+  mov $20, %rsi
+  cmp %rsi, %r9
+  jle .L.block.main.8
+.L.block.main.7:
+  mov %r9, %rsi
+  jmp .L.block.main.9
+.L.block.main.8:
+  mov %r9, %r8
+  jmp .L.block.main.12
+.L.block.main.9:
+  mov $1, %r9
+  mov %rsi, %rax
+  sub %r9, %rax
+  mov %rax, %r9
+  mov %r9, %rax
+  movslq %eax, %rax
+  mov %rax, %r9
+  mov $20, %r10
+  cmp %r10, %r9
+  jle .L.block.main.11
+.L.block.main.10:
+  mov %r9, %rsi
+  jmp .L.block.main.9
+.L.block.main.11:
+  mov %r9, %r8
+.L.block.main.12:
+  mov $1, %rsi
+  mov %r8, %rax
+  add %rsi, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+  movslq %eax, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+.L.return.main:
+  mov %rbp, %rsp
+  pop %rbp
+  ret
+  .section .note.GNU-stack,"",@progbits
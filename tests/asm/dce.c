@@ -0,0 +1,27 @@
+struct point {
+    int x, y;
+};
+
+int unused(int a, int b) {
+    int c = a * b;
+    a + b;
+    c = a - b;
+    return c;
+}
+
+int overwritten(int a) {
+    struct point p;
+    struct point q;
+    q.x = 1;
+    q.y = a;
+    p = q;
+    p.x = a;
+    p.x = 2;
+    return p.x + p.y;
+}
+
+int main() {
+    return 1;
+    2;
+    3;
+}
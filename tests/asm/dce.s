@@ -0,0 +1,106 @@
+  .globl unused
+  .text
+unused:
+  push %rbp
+  mov %rsp, %rbp
+  mov %rsi, %r8
+  mov %rdi, %rsi
+  mov %rsi, %rax
+  movslq %eax, %rax
+  mov %rax, %rsi
+  mov %r8, %rax
+  movslq %eax, %rax
+  mov %rax, %r8
+  mov %rsi, %rax
+  sub %r8, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+  movslq %eax, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+.L.return.unused:
+  mov %rbp, %rsp
+  pop %rbp
+  ret
+  .globl overwritten
+  .text
+overwritten:
+  push %rbp
+  mov %rsp, %rbp
+  sub $16, %rsp
+  mov %rdi, %rsi
+  mov %rsi, %rax
+  movslq %eax, %rax
+  mov %rax, %rsi
+  lea -8(%rbp), %rax
+  mov %rax, %r8
+  mov $1, %r9
+  mov %r9, %rax
+  mov %eax, (%r8)
+  lea -8(%rbp), %rax
+  mov %rax, %r8
+  mov $4, %r9
+  mov %r8, %rax
+  add %r9, %rax
+  mov %rax, %r8
+  mov %rsi, %rax
+  mov %eax, (%r8)
+  lea -16(%rbp), %rax
+  mov %rax, %rsi
+  lea -8(%rbp), %rax
+  mov %rax, %r8
+  mov %rsi, %rdi
+  mov %r8, %rax
+  mov 0(%rax), %cl
+  mov %cl, 0(%rdi)
+  mov 1(%rax), %cl
+  mov %cl, 1(%rdi)
+  mov 2(%rax), %cl
+  mov %cl, 2(%rdi)
+  mov 3(%rax), %cl
+  mov %cl, 3(%rdi)
+  mov 4(%rax), %cl
+  mov %cl, 4(%rdi)
+  mov 5(%rax), %cl
+  mov %cl, 5(%rdi)
+  mov 6(%rax), %cl
+  mov %cl, 6(%rdi)
+  mov 7(%rax), %cl
+  mov %cl, 7(%rdi)
+  lea -16(%rbp), %rax
+  mov %rax, %rsi
+  mov $2, %r8
+  mov %r8, %rax
+  mov %eax, (%rsi)
+  lea -16(%rbp), %rax
+  mov %rax, %rsi
+  movslq (%rsi), %rax
+  mov %rax, %rsi
+  lea -16(%rbp), %rax
+  mov %rax, %r8
+  mov $4, %r9
+  mov %r8, %rax
+  add %r9, %rax
+  mov %rax, %r8
+  movslq (%r8), %rax
+  mov %rax, %r8
+  mov %rsi, %rax
+  add %r8, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+.L.return.overwritten:
+  mov %rbp, %rsp
+  pop %rbp
+  ret
+  .globl main
+  .text
+main:
+  push %rbp
+  mov %rsp, %rbp
+  mov $1, %rsi
+  mov %rsi, %rax
+.L.return.main:
+  mov %rbp, %rsp
+  pop %rbp
+  ret
+  .section .note.GNU-stack,"",@progbits
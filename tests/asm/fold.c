@@ -0,0 +1,9 @@
+int main() {
+    int x = 5 + 6 * 7;
+    unsigned char c = 255 + 2;
+    if (x > 40)
+        x = x * 4 + 0;
+    while (0)
+        x--;
+    return x + c;
+}
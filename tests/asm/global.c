@@ -0,0 +1,12 @@
+int counter;
+int values[3] = {1, 2, 3};
+char *name = "toycc";
+int *last = values + 2;
+
+static int next() {
+    static int n = 10;
+    n = n + 1;
+    return n;
+}
+
+int main() { return counter + *last + name[0] + next(); }
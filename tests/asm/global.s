@@ -0,0 +1,79 @@
+  .globl main
+  .text
+main:
+  push %rbp
+  mov %rsp, %rbp
+  lea counter(%rip), %rax
+  mov %rax, %rsi
+  movslq (%rsi), %rax
+  mov %rax, %rsi
+  lea last(%rip), %rax
+  mov %rax, %r8
+  mov (%r8), %rax
+  mov %rax, %r8
+  movslq (%r8), %rax
+  mov %rax, %r8
+  mov %rsi, %rax
+  add %r8, %rax
+  mov %rax, %rsi
+  lea name(%rip), %rax
+  mov %rax, %r8
+  mov (%r8), %rax
+  mov %rax, %r8
+  movsbq (%r8), %rax
+  mov %rax, %r8
+  mov %rsi, %rax
+  add %r8, %rax
+  mov %rax, %rsi
+  lea n.4(%rip), %rax
+  mov %rax, %r8
+  lea n.4(%rip), %rax
+  mov %rax, %r9
+  movslq (%r9), %rax
+  mov %rax, %r9
+  mov $1, %r10
+  mov %r9, %rax
+  add %r10, %rax
+  mov %rax, %r9
+  mov %r9, %rax
+  mov %eax, (%r8)
+  lea n.4(%rip), %rax
+  mov %rax, %r8
+  movslq (%r8), %rax
+  mov %rax, %r8
+  mov %rsi, %rax
+  add %r8, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+.L.return.main:
+  mov %rbp, %rsp
+  pop %rbp
+  ret
+  .globl counter
+  .bss
+  .align 4
+counter:
+  .zero 4
+  .globl values
+  .data
+  .align 4
+values:
+  .byte 1,0,0,0,2,0,0,0,3,0,0,0
+  .globl name
+  .data
+  .align 8
+name:
+  .quad .L.str.0+0
+  .globl last
+  .data
+  .align 8
+last:
+  .quad values+8
+  .data
+  .align 4
+n.4:
+  .byte 10,0,0,0
+  .data
+.L.str.0:
+  .byte 116,111,121,99,99,0
+  .section .note.GNU-stack,"",@progbits
@@ -0,0 +1,15 @@
+static int square(int x) {
+    return x * x;
+}
+
+__attribute__((noinline)) static int cube(int x) {
+    return x * x * x;
+}
+
+static int fib(int n) {
+    return n < 2 ? n : fib(n - 1) + fib(n - 2);
+}
+
+int main() {
+    return square(3) + cube(2) + fib(5);
+}
@@ -0,0 +1,110 @@
+  .text
+cube:
+  push %rbp
+  mov %rsp, %rbp
+  mov %rdi, %rsi
+  mov %rsi, %rax
+  movslq %eax, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+  imul %rsi, %rax
+  mov %rax, %r8
+  mov %r8, %rax
+  imul %rsi, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+.L.return.cube:
+  mov %rbp, %rsp
+  pop %rbp
+  ret
+  .text
+fib:
+  push %rbp
+  mov %rsp, %rbp
+  push %rbx
+  push %r12
+  push %r13
+  sub $8, %rsp
+  mov %rdi, %rsi
+  mov %rsi, %rax
+  movslq %eax, %rax
+  mov %rax, %rbx
+  mov $2, %rsi
+  cmp %rsi, %rbx
+  jge .L.block.fib.2
+.L.block.fib.1:
+  mov %rbx, %r12
+  jmp .L.block.fib.3
+.L.block.fib.2:
+  mov $1, %rsi
+  mov %rbx, %rax
+  sub %rsi, %rax
+  mov %rax, %rsi
+  mov %rsi, %rdi
+  xor %eax, %eax
+  call fib
+  mov %rax, %rsi
+  mov %rsi, %rax
+  movslq %eax, %rax
+  mov %rax, %r13
+  mov $2, %rsi
+  mov %rbx, %rax
+  sub %rsi, %rax
+  mov %rax, %rsi
+  mov %rsi, %rdi
+  xor %eax, %eax
+  call fib
+  mov %rax, %rsi
+  mov %rsi, %rax
+  movslq %eax, %rax
+  mov %rax, %rsi
+  mov %r13, %rax
+  add %rsi, %rax
+  mov %rax, %rsi
+  mov %rsi, %r12
+.L.block.fib.3:
+  mov %r12, %rax
+.L.return.fib:
+  lea -24(%rbp), %rsp
+  pop %r13
+  pop %r12
+  pop %rbx
+  pop %rbp
+  ret
+  .globl main
+  .text
+main:
+  push %rbp
+  mov %rsp, %rbp
+  push %rbx
+  sub $8, %rsp
+  mov $9, %rbx
+  mov $2, %rsi
+  mov %rsi, %rdi
+  xor %eax, %eax
+  call cube
+  mov %rax, %rsi
+  mov %rsi, %rax
+  movslq %eax, %rax
+  mov %rax, %rsi
+  mov %rbx, %rax
+  add %rsi, %rax
+  mov %rax, %rbx
+  mov $5, %rsi
+  mov %rsi, %rdi
+  xor %eax, %eax
+  call fib
+  mov %rax, %rsi
+  mov %rsi, %rax
+  movslq %eax, %rax
+  mov %rax, %rsi
+  mov %rbx, %rax
+  add %rsi, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+.L.return.main:
+  lea -8(%rbp), %rsp
+  pop %rbx
+  pop %rbp
+  ret
+  .section .note.GNU-stack,"",@progbits
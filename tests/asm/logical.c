@@ -0,0 +1,4 @@
+int main() {
+    int x = 2;
+    return !x || (x && ~x);
+}
@@ -0,0 +1,14 @@
+int scaled_sum(int *a, int n, int k) {
+    int s = 0;
+    for (int i = 0; i < n; i++)
+        s += a[i] * (k + 1);
+    return s;
+}
+
+void fill(long *a, long n, long x) {
+    long i = 0;
+    while (i < n) {
+        a[i] = x;
+        i += 2;
+    }
+}
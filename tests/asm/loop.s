@@ -0,0 +1,104 @@
+  .globl scaled_sum
+  .text
+scaled_sum:
+  push %rbp
+  mov %rsp, %rbp
+  push %rbx
+  push %r12
+  mov %rsi, %r8
+  mov %rdi, %rsi
+  mov %rdx, %r9
+  mov %r8, %rax
+  movslq %eax, %rax
+  mov %rax, %r8
+  mov %r9, %rax
+  movslq %eax, %rax
+  mov %rax, %r9
+  xor %r10d, %r10d
+  xor %ecx, %ecx
+  mov $1, %rdx
+  mov %r9, %rax
+  add %rdx, %rax
+  mov %rax, %r9
+  cmp %r8, %rcx
+  jge .L.block.scaled_sum.2
+.L.block.scaled_sum.1:
+  mov %r10, %rdx
+  jmp .L.block.scaled_sum.3
+.L.block.scaled_sum.2:
+  jmp .L.block.scaled_sum.6
+.L.block.scaled_sum.3:
+  movslq (%rsi), %rax
+  mov %rax, %rdi
+  mov %rdi, %rax
+  imul %r9, %rax
+  mov %rax, %rdi
+  mov %rdx, %rax
+  add %rdi, %rax
+  mov %rax, %rdi
+  mov %rdi, %rax
+  movslq %eax, %rax
+  mov %rax, %rdi
+  mov $1, %r11
+  mov %rcx, %rax
+  add %r11, %rax
+  mov %rax, %r11
+  mov %r11, %rax
+  movslq %eax, %rax
+  mov %rax, %r11
+  mov $4, %rbx
+  mov %rsi, %rax
+  add %rbx, %rax
+  mov %rax, %rbx
+  cmp %r8, %r11
+  jge .L.block.scaled_sum.5
+.L.block.scaled_sum.4:
+  mov %rbx, %rsi
+  mov %r11, %rcx
+  mov %rdi, %rdx
+  jmp .L.block.scaled_sum.3
+.L.block.scaled_sum.5:
+  mov %rdi, %r10
+.L.block.scaled_sum.6:
+  mov %r10, %rax
+.L.return.scaled_sum:
+  lea -16(%rbp), %rsp
+  pop %r12
+  pop %rbx
+  pop %rbp
+  ret
+  .globl fill
+  .text
+fill:
+  push %rbp
+  mov %rsp, %rbp
+  mov %rsi, %r8
+  mov %rdi, %rsi
+  mov %rdx, %r9
+  xor %r10d, %r10d
+  cmp %r8, %r10
+  jge .L.block.fill.4
+.L.block.fill.1:
+.L.block.fill.2:
+  mov %r9, %rax
+  mov %rax, (%rsi)
+  mov $2, %rcx
+  mov %r10, %rax
+  add %rcx, %rax
+  mov %rax, %rcx
+  mov $16, %rdx
+  mov %rsi, %rax
+  add %rdx, %rax
+  mov %rax, %rdx
+  cmp %r8, %rcx
+  jge .L.block.fill.4
+.L.block.fill.3:
+  mov %rdx, %rsi
+  mov %rcx, %r10
+  jmp .L.block.fill.2
+.L.block.fill.4:
+.L.return.fill:
+  mov %rbp, %rsp
+  pop %rbp
+  ret
+  .section .note.GNU-stack,"",@progbits
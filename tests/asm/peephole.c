@@ -0,0 +1,14 @@
+int count(unsigned a, unsigned b) {
+    int n = 0;
+    while (a < b) {
+        a++;
+        n++;
+    }
+    if (n != 0)
+        n = n > 3;
+    return n;
+}
+
+int main() {
+    return count(1, 5);
+}
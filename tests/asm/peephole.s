@@ -0,0 +1,85 @@
+  .globl count
+  .text
+count:
+  push %rbp
+  mov %rsp, %rbp
+  mov %rsi, %r8
+  mov %rdi, %rsi
+  mov %rsi, %rax
+  mov %eax, %eax
+  mov %rax, %rsi
+  mov %r8, %rax
+  mov %eax, %eax
+  mov %rax, %r8
+  xor %r9d, %r9d
+  mov $1, %r10
+  cmp %r8, %rsi
+  jae .L.block.count.2
+.L.block.count.1:
+  mov %r9, %rcx
+  jmp .L.block.count.3
+.L.block.count.2:
+  jmp .L.block.count.6
+.L.block.count.3:
+  mov %rsi, %rax
+  add %r10, %rax
+  mov %rax, %rdx
+  mov %rdx, %rax
+  mov %eax, %eax
+  mov %rax, %rdx
+  mov $1, %rdi
+  mov %rcx, %rax
+  add %rdi, %rax
+  mov %rax, %rdi
+  mov %rdi, %rax
+  movslq %eax, %rax
+  mov %rax, %rdi
+  cmp %r8, %rdx
+  jae .L.block.count.5
+.L.block.count.4:
+  mov %rdi, %rcx
+  mov %rdx, %rsi
+  jmp .L.block.count.3
+.L.block.count.5:
+  mov %rdi, %r9
+.L.block.count.6:
+  xor %esi, %esi
+  cmp %rsi, %r9
+  jne .L.block.count.8
+.L.block.count.7:
+  mov %r9, %rsi
+  jmp .L.block.count.9
+.L.block.count.8:
+  mov $3, %r8
+  cmp %r8, %r9
+  setg %al
+  movzb %al, %rax
+  mov %rax, %r8
+  mov %r8, %rsi
+.L.block.count.9:
+  mov %rsi, %rax
+.L.return.count:
+  mov %rbp, %rsp
+  pop %rbp
+  ret
+  .globl main
+  .text
+main:
+  push %rbp
+  mov %rsp, %rbp
+  mov $1, %rsi
+  mov $5, %r8
+  mov %rsi, %rdi
+  mov %r8, %rsi
+  xor %eax, %eax
+  call count
+  mov %rax, %rsi
+  mov %rsi, %rax
+  movslq %eax, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+.L.return.main:
+  mov %rbp, %rsp
+  pop %rbp
+  ret
+  .section .note.GNU-stack,"",@progbits
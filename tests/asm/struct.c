@@ -0,0 +1,12 @@
+struct point {
+    char tag;
+    int x;
+    long y;
+};
+
+int main() {
+    struct point p = {1, 2, 3};
+    struct point q = p;
+    struct point *r = &q;
+    return r->x + r->y + sizeof(p);
+}
@@ -0,0 +1,93 @@
+  .globl main
+  .text
+main:
+  push %rbp
+  mov %rsp, %rbp
+  sub $32, %rsp
+  lea -32(%rbp), %rax
+  mov %rax, %rsi
+  mov %rsi, %rdi
+  movq $0, 0(%rdi)
+  movq $0, 8(%rdi)
+  mov $1, %r8
+  mov %r8, %rax
+  mov %al, (%rsi)
+  mov $4, %r8
+  mov %rsi, %rax
+  add %r8, %rax
+  mov %rax, %r8
+  mov $2, %r9
+  mov %r9, %rax
+  mov %eax, (%r8)
+  mov $8, %r8
+  mov %rsi, %rax
+  add %r8, %rax
+  mov %rax, %rsi
+  mov $3, %r8
+  mov %r8, %rax
+  mov %rax, (%rsi)
+  lea -16(%rbp), %rax
+  mov %rax, %rsi
+  lea -32(%rbp), %rax
+  mov %rax, %r8
+  mov %rsi, %rdi
+  mov %r8, %rax
+  mov 0(%rax), %cl
+  mov %cl, 0(%rdi)
+  mov 1(%rax), %cl
+  mov %cl, 1(%rdi)
+  mov 2(%rax), %cl
+  mov %cl, 2(%rdi)
+  mov 3(%rax), %cl
+  mov %cl, 3(%rdi)
+  mov 4(%rax), %cl
+  mov %cl, 4(%rdi)
+  mov 5(%rax), %cl
+  mov %cl, 5(%rdi)
+  mov 6(%rax), %cl
+  mov %cl, 6(%rdi)
+  mov 7(%rax), %cl
+  mov %cl, 7(%rdi)
+  mov 8(%rax), %cl
+  mov %cl, 8(%rdi)
+  mov 9(%rax), %cl
+  mov %cl, 9(%rdi)
+  mov 10(%rax), %cl
+  mov %cl, 10(%rdi)
+  mov 11(%rax), %cl
+  mov %cl, 11(%rdi)
+  mov 12(%rax), %cl
+  mov %cl, 12(%rdi)
+  mov 13(%rax), %cl
+  mov %cl, 13(%rdi)
+  mov 14(%rax), %cl
+  mov %cl, 14(%rdi)
+  mov 15(%rax), %cl
+  mov %cl, 15(%rdi)
+  lea -16(%rbp), %rax
+  mov %rax, %rsi
+  mov $4, %r8
+  mov %rsi, %rax
+  add %r8, %rax
+  mov %rax, %r8
+  movslq (%r8), %rax
+  mov %rax, %r8
+  mov $8, %r9
+  mov %rsi, %rax
+  add %r9, %rax
+  mov %rax, %rsi
+  mov (%rsi), %rax
+  mov %rax, %rsi
+  mov %r8, %rax
+  add %rsi, %rax
+  mov %rax, %rsi
+  mov $16, %r8
+  mov %rsi, %rax
+  add %r8, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+.L.return.main:
+  mov %rbp, %rsp
+  pop %rbp
+  ret
+  .section .note.GNU-stack,"",@progbits
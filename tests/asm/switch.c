@@ -0,0 +1,22 @@
+int classify(int x) {
+    switch (x) {
+    case 0:
+        return 10;
+    case 1:
+    case 2:
+        x = 20;
+        break;
+    default:
+        x = 30;
+    }
+    return x;
+}
+
+int main() {
+    int n = 0;
+again:
+    n++;
+    if (n < 3)
+        goto again;
+    return classify(n);
+}
@@ -0,0 +1,91 @@
+  .globl classify
+  .text
+classify:
+  push %rbp
+  mov %rsp, %rbp
+  mov %rdi, %rsi
+  mov %rsi, %rax
+  movslq %eax, %rax
+  mov %rax, %rsi
+  xor %r8d, %r8d
+  cmp %r8, %rsi
+  je .L.block.classify.4
+.L.block.classify.1:
+  mov $1, %r8
+  cmp %r8, %rsi
+  je .L.block.classify.5
+.L.block.classify.2:
+  mov $2, %r8
+  cmp %r8, %rsi
+  je .L.block.classify.6
+.L.block.classify.3:
+  mov $30, %rsi
+  jmp .L.block.classify.7
+.L.block.classify.4:
+  mov $10, %r8
+  mov %r8, %rax
+  jmp .L.return.classify
+.L.block.classify.5:
+.L.block.classify.6:
+  mov $20, %r8
+  mov %r8, %rsi
+.L.block.classify.7:
+  mov %rsi, %rax
+.L.return.classify:
+  mov %rbp, %rsp
+  pop %rbp
+  ret
+  .globl main
+  .text
+main:
+  push %rbp
+  mov %rsp, %rbp
+  mov $1, %rsi
+.L.block.main.1:
+  mov $1, %r8
+  mov %rsi, %rax
+  add %r8, %rax
+  mov %rax, %r8
+  mov %r8, %rax
+  movslq %eax, %rax
+  mov %rax, %r8
+  mov $3, %r9
+  cmp %r9, %r8
+  jge .L.block.main.3
+.L.block.main.2:
+  mov %r8, %rsi
+  jmp .L.block.main.1
+.L.block.main.3:
+  xor %esi, %esi
+  cmp %rsi, %r8
+  je .L.block.main.7
+.L.block.main.4:
+  mov $1, %rsi
+  cmp %rsi, %r8
+  je .L.block.main.8
+.L.block.main.5:
+  mov $2, %rsi
+  cmp %rsi, %r8
+  je .L.block.main.9
+.L.block.main.6:
+  mov $30, %rsi
+  jmp .L.block.main.10
+.L.block.main.7:
+  mov $10, %r8
+  jmp .L.block.main.11
+.L.block.main.8:
+.L.block.main.9:
+  mov $20, %r9
+  mov %r9, %rsi
+.L.block.main.10:
+  mov %rsi, %r8
+.L.block.main.11:
+  mov %r8, %rax
+  movslq %eax, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+.L.return.main:
+  mov %rbp, %rsp
+  pop %rbp
+  ret
+  .section .note.GNU-stack,"",@progbits
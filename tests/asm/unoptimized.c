@@ -0,0 +1,13 @@
+// OPT-LEVEL: 0
+int square(int x) {
+    int y = x * x;
+    return y + 0;
+}
+
+int main() {
+    int n = 3;
+    if (1)
+        n = square(n);
+    return n;
+    n++;
+}
@@ -0,0 +1,107 @@
+  .globl square
+  .text
+square:
+  push %rbp
+  mov %rsp, %rbp
+  sub $16, %rsp
+  mov %rdi, %rsi
+  lea -8(%rbp), %rax
+  mov %rax, %r8
+  mov %rsi, %rax
+  mov %eax, (%r8)
+.L.block.square.1:
+  lea -4(%rbp), %rax
+  mov %rax, %rsi
+  lea -8(%rbp), %rax
+  mov %rax, %r8
+  movslq (%r8), %rax
+  mov %rax, %r8
+  lea -8(%rbp), %rax
+  mov %rax, %r9
+  movslq (%r9), %rax
+  mov %rax, %r9
+  mov %r8, %rax
+  imul %r9, %rax
+  mov %rax, %r8
+  mov %r8, %rax
+  mov %eax, (%rsi)
+  lea -4(%rbp), %rax
+  mov %rax, %rsi
+  movslq (%rsi), %rax
+  mov %rax, %rsi
+  mov $0, %r8
+  mov %rsi, %rax
+  add %r8, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+.L.return.square:
+  mov %rbp, %rsp
+  pop %rbp
+  ret
+  .globl main
+  .text
+main:
+  push %rbp
+  mov %rsp, %rbp
+  push %rbx
+  sub $8, %rsp
+.L.block.main.1:
+  lea -12(%rbp), %rax
+  mov %rax, %rsi
+  mov $3, %r8
+  mov %r8, %rax
+  mov %eax, (%rsi)
+  mov $1, %rsi
+  cmp $0, %rsi
+  je .L.block.main.3
+.L.block.main.2:
+  lea -12(%rbp), %rax
+  mov %rax, %rbx
+  lea -12(%rbp), %rax
+  mov %rax, %rsi
+  movslq (%rsi), %rax
+  mov %rax, %rsi
+  mov %rsi, %rdi
+  mov $0, %rax
+  call square
+  mov %rax, %rsi
+  mov %rsi, %rax
+  movslq %eax, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+  mov %eax, (%rbx)
+.L.block.main.3:
+  lea -12(%rbp), %rax
+  mov %rax, %rsi
+  movslq (%rsi), %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+  jmp .L.return.main
+.L.block.main.4:
+  lea -12(%rbp), %rax
+  mov %rax, %rsi
+  lea -12(%rbp), %rax
+  mov %rax, %r8
+  movslq (%r8), %rax
+  mov %rax, %r8
+  mov $1, %r9
+  mov %r8, %rax
+  add %r9, %rax
+  mov %rax, %r8
+  mov %r8, %rax
+  mov %eax, (%rsi)
+  mov $1, %rsi
+  mov %r8, %rax
+  sub %rsi, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+  movslq %eax, %rax
+  mov %rax, %rsi
+  mov $0, %rsi
+  mov %rsi, %rax
+.L.return.main:
+  lea -8(%rbp), %rsp
+  pop %rbx
+  pop %rbp
+  ret
+  .section .note.GNU-stack,"",@progbits
@@ -0,0 +1,161 @@
+  .globl sum
+  .text
+sum:
+  push %rbp
+  mov %rsp, %rbp
+  push %rbx
+  push %r12
+  push %r13
+  sub $200, %rsp
+  mov %rdi, -224(%rbp)
+  mov %rsi, -216(%rbp)
+  mov %rdx, -208(%rbp)
+  mov %rcx, -200(%rbp)
+  mov %r8, -192(%rbp)
+  mov %r9, -184(%rbp)
+  movsd %xmm0, -176(%rbp)
+  movsd %xmm1, -160(%rbp)
+  movsd %xmm2, -144(%rbp)
+  movsd %xmm3, -128(%rbp)
+  movsd %xmm4, -112(%rbp)
+  movsd %xmm5, -96(%rbp)
+  movsd %xmm6, -80(%rbp)
+  movsd %xmm7, -64(%rbp)
+  mov %rdi, %rsi
+  mov %rsi, %rax
+  movslq %eax, %rax
+  mov %rax, %rsi
+  lea -48(%rbp), %rax
+  mov %rax, %r8
+  mov $8, %r9
+  mov %r9, %rax
+  mov %eax, (%r8)
+  mov $48, %r9
+  mov $4, %r10
+  mov %r8, %rax
+  add %r10, %rax
+  mov %rax, %r10
+  mov %r9, %rax
+  mov %eax, (%r10)
+  lea 16(%rbp), %rax
+  mov %rax, %r9
+  mov $8, %r10
+  mov %r8, %rax
+  add %r10, %rax
+  mov %rax, %r10
+  mov %r9, %rax
+  mov %rax, (%r10)
+  lea -224(%rbp), %rax
+  mov %rax, %r9
+  mov $16, %r10
+  mov %r8, %rax
+  add %r10, %rax
+  mov %rax, %r8
+  mov %r9, %rax
+  mov %rax, (%r8)
+  xor %r8d, %r8d
+  xor %r9d, %r9d
+  lea -48(%rbp), %rax
+  mov %rax, %r10
+  mov $16, %rcx
+  mov %r10, %rax
+  add %rcx, %rax
+  mov %rax, %rcx
+  mov $8, %rdx
+  mov %r10, %rax
+  add %rdx, %rax
+  mov %rax, %rdx
+  cmp %rsi, %r9
+  jge .L.block.sum.2
+.L.block.sum.1:
+  mov %r8, %r11
+  jmp .L.block.sum.3
+.L.block.sum.2:
+  jmp .L.block.sum.9
+.L.block.sum.3:
+  movl (%r10), %eax
+  mov %rax, %rdi
+  mov $48, %rbx
+  cmp %rbx, %rdi
+  jae .L.block.sum.5
+.L.block.sum.4:
+  mov (%rcx), %rax
+  mov %rax, %rbx
+  mov %rbx, %rax
+  add %rdi, %rax
+  mov %rax, %rbx
+  mov $8, %r12
+  mov %rdi, %rax
+  add %r12, %rax
+  mov %rax, %r12
+  mov %r12, %rax
+  mov %eax, (%r10)
+  jmp .L.block.sum.6
+.L.block.sum.5:
+  mov (%rdx), %rax
+  mov %rax, %r12
+  mov $8, %rdi
+  mov %r12, %rax
+  add %rdi, %rax
+  mov %rax, %r13
+  mov %r13, %rax
+  mov %rax, (%rdx)
+  mov %r12, %rbx
+.L.block.sum.6:
+  movslq (%rbx), %rax
+  mov %rax, %rdi
+  mov %r11, %rax
+  add %rdi, %rax
+  mov %rax, %rdi
+  mov %rdi, %rax
+  movslq %eax, %rax
+  mov %rax, %rdi
+  mov $1, %rbx
+  mov %r9, %rax
+  add %rbx, %rax
+  mov %rax, %rbx
+  mov %rbx, %rax
+  movslq %eax, %rax
+  mov %rax, %rbx
+  cmp %rsi, %rbx
+  jge .L.block.sum.8
+.L.block.sum.7:
+  mov %rbx, %r9
+  mov %rdi, %r11
+  jmp .L.block.sum.3
+.L.block.sum.8:
+  mov %rdi, %r8
+.L.block.sum.9:
+  mov %r8, %rax
+.L.return.sum:
+  lea -24(%rbp), %rsp
+  pop %r13
+  pop %r12
+  pop %rbx
+  pop %rbp
+  ret
+  .globl main
+  .text
+main:
+  push %rbp
+  mov %rsp, %rbp
+  mov $3, %rsi
+  mov $1, %r8
+  mov $2, %r9
+  mov $3, %r10
+  mov %rsi, %rdi
+  mov %r8, %rsi
+  mov %r9, %rdx
+  mov %r10, %rcx
+  xor %eax, %eax
+  call sum
+  mov %rax, %rsi
+  mov %rsi, %rax
+  movslq %eax, %rax
+  mov %rax, %rsi
+  mov %rsi, %rax
+.L.return.main:
+  mov %rbp, %rsp
+  pop %rbp
+  ret
+  .section .note.GNU-stack,"",@progbits
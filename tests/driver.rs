@@ -0,0 +1,174 @@
+//! Runs the `toycc` binary as a compiler driver: reading the standard input, writing
+//! objects, and linking several translation units.
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command, Output, Stdio};
+
+const TOYCC: &str = env!("CARGO_BIN_EXE_toycc");
+const LINK: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/programs/link");
+
+// a directory of its own for each test, as they run in parallel
+fn work_dir(test: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("toycc-driver-{}-{}", process::id(), test));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("twice.c"),
+        fs::read(Path::new(LINK).join("twice.c")).unwrap(),
+    )
+    .unwrap();
+    fs::write(
+        dir.join("main.c"),
+        "extern int shared;\nint twice(int x);\nstatic int hidden() { return 2; }\n\
+         int main() { return twice(shared) + hidden(); }\n",
+    )
+    .unwrap();
+    dir
+}
+
+fn toycc(dir: &Path, args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(TOYCC)
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn exit_code(exe: &Path) -> Option<i32> {
+    Command::new(exe).status().unwrap().code()
+}
+
+#[test]
+fn links_translation_units_in_any_order() {
+    let dir = work_dir("order");
+    for inputs in &[["main.c", "twice.c"], ["twice.c", "main.c"]] {
+        let output = toycc(&dir, &["-o", "prog", inputs[0], inputs[1]], "");
+        assert!(output.status.success(), "{:?}", output);
+        assert_eq!(Some(8), exit_code(&dir.join("prog")));
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn links_objects_compiled_separately() {
+    let dir = work_dir("objects");
+    let output = toycc(&dir, &["-c", "twice.c", "-o", "twice.o"], "");
+    assert!(output.status.success(), "{:?}", output);
+    let output = toycc(&dir, &["-o", "prog", "main.c", "twice.o"], "");
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(Some(8), exit_code(&dir.join("prog")));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn compiles_the_standard_input_to_the_standard_output() {
+    let dir = work_dir("stdin");
+    let output = toycc(&dir, &["-S", "-"], "int main() { return 0; }\n");
+    assert!(output.status.success(), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("main:"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn writes_the_intermediate_representation_next_to_the_source() {
+    let dir = work_dir("ir");
+    let output = toycc(&dir, &["-emit-ir", "twice.c"], "");
+    assert!(output.status.success(), "{:?}", output);
+    let ir = fs::read_to_string(dir.join("twice.ir")).unwrap();
+    assert!(ir.contains("function twice(1) {"), "{}", ir);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+// with libc headers, which define `__attribute__` away for compilers other than GCC
+#[test]
+fn inlines_as_the_attributes_ask() {
+    let dir = work_dir("attributes");
+    let source = "#include <stdio.h>\n\
+                  __attribute__((noinline)) static int small(void) { return 1; }\n\
+                  __attribute__((always_inline)) static int large(int n) {\n\
+                  for (int i = 0; i < n; i++) { n ^= i * 3; n %= 1000; n += i << 2; }\n\
+                  for (int i = 0; i < n; i++) { n -= i & 5; n |= i % 7; n /= 3; }\n\
+                  return n;\n}\n\
+                  int main() { return small() + large(4); }\n";
+    let output = toycc(&dir, &["-O2", "-emit-ir", "-o", "-", "-"], source);
+    assert!(output.status.success(), "{:?}", output);
+    let ir = String::from_utf8_lossy(&output.stdout);
+    assert!(ir.contains("call @small()"), "{}", ir);
+    assert!(!ir.contains("call @large("), "{}", ir);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn rejects_missing_inputs() {
+    let dir = work_dir("missing");
+    let output = toycc(&dir, &[], "");
+    assert!(!output.status.success());
+    assert_eq!(
+        "toycc: no input files\n",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let output = toycc(&dir, &["-S", "-o", "out.s", "main.c", "twice.c"], "");
+    assert!(!output.status.success());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn rejects_invalid_macro_options() {
+    let dir = work_dir("macros");
+    let cases: &[(&[&str], &str)] = &[
+        (&["-D1=2"], "macro name must be an identifier"),
+        (&["-DX=\"abc"], "<command line>"),
+        (&["-D", "-S"], "toycc: missing macro name after -D\n"),
+        (&["-D"], "toycc: missing macro name after -D\n"),
+        (&["-o"], "toycc: missing file after -o\n"),
+        (&["-I"], "toycc: missing directory after -I\n"),
+    ];
+    for (args, message) in cases {
+        // last, so that an option missing its argument is at the end
+        let output = toycc(&dir, &[&["-E", "main.c"][..], args].concat(), "");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(Some(1), output.status.code(), "{:?}: {}", args, stderr);
+        assert!(stderr.contains(message), "{:?}: {}", args, stderr);
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn dumps_the_passes_that_run() {
+    let dir = work_dir("passes");
+    let args = [
+        "-O2",
+        "-fno-fold",
+        "-fdump-fold",
+        "-fdump-dce",
+        "-fdump-peephole",
+        "-S",
+        "twice.c",
+    ];
+    let output = toycc(&dir, &args, "");
+    assert!(output.status.success(), "{:?}", output);
+    let dumps = String::from_utf8_lossy(&output.stderr);
+    assert!(dumps.starts_with("; before dce\n"), "{}", dumps);
+    assert!(
+        dumps.contains("; after dce\nfunction twice(1) {"),
+        "{}",
+        dumps
+    );
+    assert!(dumps.contains("; after peephole\ntwice:\n"), "{}", dumps);
+    assert!(!dumps.contains("fold"), "{}", dumps);
+    let output = toycc(&dir, &["-fno-inlining", "twice.c"], "");
+    assert!(!output.status.success());
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,183 @@
+//! Checks each rewrite of the peephole optimiser on listings of its own: the code it gives,
+//! and the code it leaves alone where the rewrite would change what the code does.
+
+use toycc::optimize_assembly;
+
+// (name, code, expected code), as lines
+const CASES: &[(&str, &[&str], &[&str])] = &[
+    (
+        "push and pop",
+        &[
+            "f:",
+            "  push %rax",
+            "  pop %rdi",
+            "  push %rsi",
+            "  pop %rsi",
+            "  mov %rdi, %rax",
+            "  ret",
+        ],
+        &["f:", "  mov %rax, %rdi", "  mov %rdi, %rax", "  ret"],
+    ),
+    (
+        "push and pop from memory to memory",
+        &["f:", "  push (%rdi)", "  pop 8(%rdi)", "  ret"],
+        &["f:", "  push (%rdi)", "  pop 8(%rdi)", "  ret"],
+    ),
+    (
+        "zero with xor",
+        &["f:", "  mov $0, %rax", "  ret"],
+        &["f:", "  xor %eax, %eax", "  ret"],
+    ),
+    (
+        "zero with xor, but for the flags read after it",
+        &[
+            "f:",
+            "  cmp %rsi, %rdi",
+            "  mov $0, %rax",
+            "  jl .L.less",
+            "  ret",
+            ".L.less:",
+            "  mov $1, %rax",
+            "  ret",
+        ],
+        &[
+            "f:",
+            "  cmp %rsi, %rdi",
+            "  mov $0, %rax",
+            "  jl .L.less",
+            "  ret",
+            ".L.less:",
+            "  mov $1, %rax",
+            "  ret",
+        ],
+    ),
+    (
+        "compare with zero as test",
+        &[
+            "f:",
+            "  cmp $0, %rdi",
+            "  je .L.zero",
+            "  mov $1, %rax",
+            "  ret",
+            ".L.zero:",
+            "  mov $2, %rax",
+            "  ret",
+        ],
+        &[
+            "f:",
+            "  test %rdi, %rdi",
+            "  je .L.zero",
+            "  mov $1, %rax",
+            "  ret",
+            ".L.zero:",
+            "  mov $2, %rax",
+            "  ret",
+        ],
+    ),
+    (
+        "compare and jump fused",
+        &[
+            "f:",
+            "  cmp %rsi, %rdi",
+            "  setl %al",
+            "  movzb %al, %rax",
+            "  mov %rax, %rcx",
+            "  cmp $0, %rcx",
+            "  je .L.else",
+            "  mov $1, %rax",
+            "  ret",
+            ".L.else:",
+            "  mov $2, %rax",
+            "  ret",
+        ],
+        &[
+            "f:",
+            "  cmp %rsi, %rdi",
+            "  jge .L.else",
+            "  mov $1, %rax",
+            "  ret",
+            ".L.else:",
+            "  mov $2, %rax",
+            "  ret",
+        ],
+    ),
+    (
+        "compare and jump fused, but for the value read after it",
+        &[
+            "f:",
+            "  cmp %rsi, %rdi",
+            "  setl %al",
+            "  movzb %al, %rax",
+            "  cmp $0, %rax",
+            "  jne .L.less",
+            "  ret",
+            ".L.less:",
+            "  add $1, %rax",
+            "  ret",
+        ],
+        &[
+            "f:",
+            "  cmp %rsi, %rdi",
+            "  setl %al",
+            "  movzb %al, %rax",
+            "  jl .L.less",
+            "  ret",
+            ".L.less:",
+            "  add $1, %rax",
+            "  ret",
+        ],
+    ),
+    (
+        "dead moves",
+        &[
+            "f:",
+            "  mov %rdi, %rcx",
+            "  lea 8(%rdi), %r10",
+            "  mov %rsi, %rax",
+            "  ret",
+        ],
+        &["f:", "  mov %rsi, %rax", "  ret"],
+    ),
+    (
+        "moves read through a loop",
+        &[
+            "f:",
+            "  mov %rdi, %rcx",
+            ".L.loop:",
+            "  sub $1, %rcx",
+            "  test %rcx, %rcx",
+            "  jne .L.loop",
+            "  mov %rcx, %rax",
+            "  ret",
+        ],
+        &[
+            "f:",
+            "  mov %rdi, %rcx",
+            ".L.loop:",
+            "  sub $1, %rcx",
+            "  test %rcx, %rcx",
+            "  jne .L.loop",
+            "  mov %rcx, %rax",
+            "  ret",
+        ],
+    ),
+];
+
+#[test]
+fn rewrites() {
+    let mut failures = vec![];
+    for (name, code, expected) in CASES {
+        let code = code.join("\n") + "\n";
+        let expected = expected.join("\n") + "\n";
+        let actual = optimize_assembly(&code);
+        if actual != expected {
+            failures.push(format!(
+                "{}: expected\n{}but got\n{}",
+                name, expected, actual
+            ));
+        }
+    }
+    if !failures.is_empty() {
+        panic!("{}", failures.join("\n"));
+    }
+}
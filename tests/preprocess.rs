@@ -0,0 +1,76 @@
+//! Checks the output of `toycc -E`, line markers included.
+
+use toycc::{preprocess, Options, SourceFile};
+
+// (source, expected output); includes are found from the directory of the package, where
+// cargo runs the tests
+const CASES: &[(&str, &str)] = &[
+    ("#define N 3\nint x = N;\n", "# 2 \"<stdin>\"\nint x = 3;\n"),
+    (
+        "#include \"tests/programs/include/sub/value.h\"\nint x = VALUE;\n",
+        "# 2 \"<stdin>\"\nint x = 7;\n",
+    ),
+    (
+        "int n;\n#include \"tests/programs/include/incr.h\"\nint m;\n",
+        "# 1 \"<stdin>\"\nint n;\n# 1 \"tests/programs/include/incr.h\"\nn = n + 1;\n# 3 \"<stdin>\"\nint m;\n",
+    ),
+    (
+        "#define S(x) #x\nS( a  b ) /* c */ x\n\n  y // d\n",
+        "# 2 \"<stdin>\"\n\"a b\" x\n\n  y\n",
+    ),
+    (
+        "int a = 1 + \\\n2;\nint b;\n",
+        "# 1 \"<stdin>\"\nint a = 1 + 2;\nint b;\n",
+    ),
+    ("#define X a ## b\nX\n", "# 2 \"<stdin>\"\nab\n"),
+    // lines starting with a macro
+    (
+        "#define S signed\n#define F(x) x\nint a;\nS int b;\nnext line\nF(2) more\n",
+        "# 3 \"<stdin>\"\nint a;\nsigned int b;\nnext line\n2 more\n",
+    ),
+    // the example of C11 6.10.3.3p4
+    (
+        "#define hash_hash # ## #\n#define mkstr(a) # a\n#define in_between(a) mkstr(a)\n\
+         #define join(c, d) in_between(c hash_hash d)\nchar p[] = join(x, y);\n",
+        "# 5 \"<stdin>\"\nchar p[] = \"x ## y\";\n",
+    ),
+    // pasting gives preprocessing numbers, with their suffixes
+    (
+        "#define CAT(a, b) a ## b\n#define XCAT(a, b) CAT(a, b)\n\
+         CAT(1, u) CAT(1, L) XCAT(__LINE__, _z) CAT(0x, ff) CAT(1e, +)\n",
+        "# 3 \"<stdin>\"\n1u 1L 3_z 0xff 1e+\n",
+    ),
+    // numbers keep their spelling
+    (
+        "#define V 201112L\n#define S(x) #x\nlong a = 1UL + 0x10u + 0777 + V;\nS(0x1f)\n",
+        "# 3 \"<stdin>\"\nlong a = 1UL + 0x10u + 0777 + 201112L;\n\"0x1f\"\n",
+    ),
+    // only the system headers have their definitions of `__attribute__` ignored
+    (
+        "#define __attribute__(x)
+int f() __attribute__((noinline));
+",
+        "# 2 \"<stdin>\"\nint f();\n",
+    ),
+];
+
+#[test]
+fn preprocessed_output() {
+    let mut failures = vec![];
+    for (source, expected) in CASES {
+        let file = SourceFile::new("<stdin>".to_string(), source.to_string());
+        let actual = match preprocess(file, &Options::default()) {
+            Ok(output) => output,
+            Err(diagnostics) => diagnostics.iter().map(ToString::to_string).collect(),
+        };
+        if actual != *expected {
+            failures.push(format!(
+                "{}=> expected\n{}but got\n{}",
+                source, expected, actual
+            ));
+        }
+    }
+    if !failures.is_empty() {
+        panic!("{}", failures.join("\n"));
+    }
+}
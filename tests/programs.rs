@@ -0,0 +1,240 @@
+//! Runs the programs in `tests/programs`: each one is compiled with toycc, assembled and
+//! linked with gcc, then run. Comments in the program say what to expect:
+//!
+//! - `// EXIT: <code>`: its exit code, 0 if not given
+//! - `// STDOUT: <line>`: a line of its output, in order; the output is not checked without them
+//! - `// DEFINE: <name>[=<value>]` and `// UNDEFINE: <name>`, as `-D` and `-U`
+//! - `// INCLUDE-PATH: <dir>` and `// SYSTEM-INCLUDE-PATH: <dir>`, as `-I` and `-isystem`
+//! - `// LINK: <file>`: another translation unit, compiled with toycc and linked in
+//! - `// OPT-LEVEL: <n>`: the only level to compile it at
+//!
+//! Paths are relative to the directory of the program. Every program is also linked with
+//! `support/ext.c`, compiled by gcc, to check calls across compilers. Each one is run twice,
+//! compiled at `-O0` and at `-O2`, unless it says the level.
+
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use toycc::{compile_file, Options};
+
+const PROGRAMS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/programs");
+
+// when `RUST_TEST_THREADS` does not say otherwise
+const DEFAULT_JOBS: usize = 8;
+
+// without any pass, and with all of them
+const OPT_LEVELS: [u32; 2] = [0, 2];
+
+#[derive(Default)]
+struct Expectations {
+    exit: i32,
+    stdout: Option<String>,
+    options: Options,
+    links: Vec<PathBuf>,
+    opt_level: Option<u32>,
+}
+
+#[test]
+fn programs() {
+    let dir = Path::new(PROGRAMS);
+    let work = env::temp_dir().join(format!("toycc-programs-{}", process::id()));
+    fs::create_dir_all(&work).unwrap();
+    let support = work.join("ext.o");
+    if let Err(e) = gcc(&[
+        "-c".into(),
+        "-o".into(),
+        support.clone().into_os_string(),
+        dir.join("support/ext.c").into_os_string(),
+    ]) {
+        panic!("support/ext.c: {}", e);
+    }
+
+    let mut programs: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "c"))
+        .collect();
+    programs.sort();
+    let total = programs.len();
+
+    let failures = {
+        let (work, support) = (work.clone(), support);
+        run_parallel(programs, move |program| {
+            run(program, &work, &support).map_err(|e| format!("{}: {}", program.display(), e))
+        })
+    };
+    drop(fs::remove_dir_all(&work));
+    if !failures.is_empty() {
+        panic!(
+            "{} of {} programs failed:\n\n{}",
+            failures.len(),
+            total,
+            failures.join("\n\n")
+        );
+    }
+}
+
+// Runs `check` on each program from a pool of threads, and returns the errors sorted by program.
+fn run_parallel<F>(programs: Vec<PathBuf>, check: F) -> Vec<String>
+where
+    F: Fn(&Path) -> Result<(), String> + Send + Sync + 'static,
+{
+    let jobs = env::var("RUST_TEST_THREADS")
+        .ok()
+        .and_then(|jobs| jobs.parse().ok())
+        .unwrap_or(DEFAULT_JOBS);
+    let programs = Arc::new(programs);
+    let check = Arc::new(check);
+    let next = Arc::new(AtomicUsize::new(0));
+    let failures = Arc::new(Mutex::new(vec![]));
+    let workers: Vec<_> = (0..jobs)
+        .map(|_| {
+            let (programs, check) = (programs.clone(), check.clone());
+            let (next, failures) = (next.clone(), failures.clone());
+            thread::spawn(move || loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+                let program = match programs.get(i) {
+                    Some(program) => program,
+                    None => break,
+                };
+                if let Err(e) = check(program) {
+                    failures.lock().unwrap().push((i, e));
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    let mut failures = failures.lock().unwrap().split_off(0);
+    failures.sort();
+    failures.into_iter().map(|(_, e)| e).collect()
+}
+
+fn run(program: &Path, work: &Path, support: &Path) -> Result<(), String> {
+    let source = fs::read_to_string(program).map_err(|e| e.to_string())?;
+    let dir = program.parent().unwrap();
+    let mut expected = expectations(&source, dir)?;
+    let levels = match expected.opt_level {
+        Some(level) => vec![level],
+        None => OPT_LEVELS.to_vec(),
+    };
+    for level in levels {
+        expected.options.opt_level = level;
+        run_at(program, &expected, work, support).map_err(|e| format!("at -O{}: {}", level, e))?;
+    }
+    Ok(())
+}
+
+fn run_at(
+    program: &Path,
+    expected: &Expectations,
+    work: &Path,
+    support: &Path,
+) -> Result<(), String> {
+    // each program and level gets its own files, as they run in parallel
+    let level = expected.options.opt_level;
+    let name = format!(
+        "{}-O{}",
+        program.file_stem().unwrap().to_string_lossy(),
+        level
+    );
+    let exe = work.join(&*name);
+    let mut args = vec!["-static".into(), "-o".into(), exe.clone().into_os_string()];
+    for (i, unit) in std::iter::once(program)
+        .chain(expected.links.iter().map(PathBuf::as_path))
+        .enumerate()
+    {
+        let output = compile_file(unit, &expected.options).map_err(|diagnostics| {
+            let messages: Vec<_> = diagnostics.iter().map(ToString::to_string).collect();
+            messages.concat()
+        })?;
+        let asm = work.join(format!("{}.{}.s", name, i));
+        fs::write(&asm, output.assembly).map_err(|e| e.to_string())?;
+        args.push(asm.into_os_string());
+    }
+    args.push(support.into());
+    gcc(&args)?;
+
+    let output = Command::new(&exe).output().map_err(|e| e.to_string())?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match output.status.code() {
+        Some(code) if code == expected.exit => {}
+        Some(code) => return Err(format!("exited with {}, expected {}", code, expected.exit)),
+        None => return Err(format!("killed by a signal, expected {}", expected.exit)),
+    }
+    match &expected.stdout {
+        Some(lines) if *lines != stdout => Err(format!(
+            "printed\n{}\nexpected\n{}",
+            stdout.trim_end(),
+            lines.trim_end()
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn expectations(source: &str, dir: &Path) -> Result<Expectations, String> {
+    let mut expected = Expectations::default();
+    for line in source.lines() {
+        let annotation = match line.strip_prefix("// ") {
+            Some(annotation) => annotation,
+            None => continue,
+        };
+        let (key, value) = match annotation.find(": ") {
+            Some(i) => (&annotation[..i], &annotation[i + 2..]),
+            None => continue,
+        };
+        match key {
+            "EXIT" => {
+                expected.exit = value
+                    .parse()
+                    .map_err(|_| format!("invalid exit code {:?}", value))?
+            }
+            "STDOUT" => {
+                let stdout = expected.stdout.get_or_insert_with(String::new);
+                stdout.push_str(value);
+                stdout.push('\n');
+            }
+            "DEFINE" => {
+                let (name, value) = match value.find('=') {
+                    Some(i) => (&value[..i], &value[i + 1..]),
+                    None => (value, "1"),
+                };
+                let value = Some(value.to_string());
+                expected.options.macros.push((name.to_string(), value));
+            }
+            "UNDEFINE" => expected.options.macros.push((value.to_string(), None)),
+            "INCLUDE-PATH" => expected.options.include_paths.push(dir.join(value)),
+            "SYSTEM-INCLUDE-PATH" => {
+                let paths = &mut expected.options.system_include_paths;
+                paths.insert(0, dir.join(value));
+            }
+            "LINK" => expected.links.push(dir.join(value)),
+            "OPT-LEVEL" => {
+                let level = value
+                    .parse()
+                    .map_err(|_| format!("invalid level {:?}", value))?;
+                expected.opt_level = Some(level);
+            }
+            _ => {}
+        }
+    }
+    Ok(expected)
+}
+
+fn gcc(args: &[OsString]) -> Result<(), String> {
+    let output = Command::new("gcc")
+        .args(args)
+        .output()
+        .map_err(|e| format!("cannot run gcc: {}", e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).into_owned())
+    }
+}
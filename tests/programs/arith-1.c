@@ -0,0 +1,2 @@
+// EXIT: 21
+{ return 5+20-4; }
@@ -0,0 +1,2 @@
+// EXIT: 22
+int scale(int x) { return x * 8 + x * 1 + 0 - (x | 0) * 2 + x * 0; } int main() { return scale(3) + scale(-1) + (7 & -1) + 1; }
@@ -0,0 +1,17 @@
+// EXIT: 0
+// the usual arithmetic conversions make the `int` operand unsigned
+int main() {
+    int x = -1;
+    unsigned y = 1;
+    unsigned u = 1;
+    int i = -8;
+    unsigned k = 2;
+    if (x < y) return 1;
+    if (u > -1) return 2;
+    if (i / k != 2147483644) return 3;
+    if (i % k != 0) return 4;
+    if ((i >> 1) != -4 || (x + u - 1) >> 28 != 15) return 5;
+    if ((x < 0 ? x : y) < 1) return 6;
+    if ((long)x < (unsigned long)0) return 7;
+    return 0;
+}
@@ -0,0 +1,28 @@
+// EXIT: 0
+// STDOUT: 3700 31 29 31 3323 35 3610
+// many values live across divisions, shifts, stores and copies, which stage operands in registers
+int printf(const char *, ...);
+struct S { long a, b, c; };
+long g;
+long f(long a, long b, long c, long d, long e, long h, struct S *p, long *q) {
+    long x = a * 3, y = b + 7, z = c - 2, w = d ^ 5, v = e | 1, u = h + a;
+    long r = x / y + z % w;
+    long s = (v << (u & 7)) + (x >> (z & 3));
+    *q = r + s;
+    struct S t = *p;
+    t.a += x; t.b += y; t.c += z;
+    *p = t;
+    struct S zero = {0};
+    long k = x / (w | 1) + y % (v | 1) + (u >> (y & 3)) + zero.b;
+    unsigned long m = (unsigned long)x / (unsigned long)(y | 1);
+    q[1] = k;
+    g = x + y + z + w + v + u + r + s + k + (long)m;
+    return x + y + z + w + v + u + r + s + t.a + t.b + t.c + k;
+}
+int main() {
+    struct S p = {1, 2, 3};
+    long q[2];
+    long r = f(10, 20, 30, 40, 50, 60, &p, q);
+    printf("%ld %ld %ld %ld %ld %ld %ld\n", r, p.a, p.b, p.c, q[0], q[1], g);
+    return 0;
+}
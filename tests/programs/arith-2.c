@@ -0,0 +1,2 @@
+// EXIT: 41
+{ return  12 + 34 - 5; }
@@ -0,0 +1,2 @@
+// EXIT: 47
+{ return 5+6*7; }
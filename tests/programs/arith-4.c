@@ -0,0 +1,2 @@
+// EXIT: 15
+{ return 5*(9-6); }
@@ -0,0 +1,2 @@
+// EXIT: 4
+{ return (3+5)/2; }
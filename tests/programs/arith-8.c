@@ -0,0 +1,2 @@
+// EXIT: 10
+{ return - - +10; }
@@ -0,0 +1,2 @@
+// EXIT: 9
+{ unsigned int u = 0; u = u - 1; unsigned char c = 250 + 15; long big = 4294967296 * 2; return (u == 4294967295) + (u / 2 == 2147483647) + (c == 9) + (big >> 33) + (-7 / 2 == -3) + (-7 % 2 == -1) + ((unsigned)-1 >> 28 == 15) + ((unsigned)1 << 31 == 2147483648) + (-1 >> 40 == -1); }
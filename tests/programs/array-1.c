@@ -0,0 +1,2 @@
+// EXIT: 3
+{ int a[2]; *a=1; *(a+1)=2; int *p; p=a; return *p+*(p+1); }
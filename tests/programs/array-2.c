@@ -0,0 +1,2 @@
+// EXIT: 5
+{ int a[3]; a[0]=1; a[1]=2; a[2]=3; return a[0]+a[2]+1; }
@@ -0,0 +1,2 @@
+// EXIT: 2
+{ int a[3]; 1[a]=2; return a[1]; }
@@ -0,0 +1,2 @@
+// EXIT: 12
+{ int a[2][3]; int i, j; for (i=0; i<2; i=i+1) for (j=0; j<3; j=j+1) a[i][j]=i+j; return a[0][2]+a[1][0]+a[1][1]+a[1][2]+4; }
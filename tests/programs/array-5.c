@@ -0,0 +1,2 @@
+// EXIT: 5
+{ int a[2][3]; int *p; p=a[1]; p[2]=5; return a[1][2]; }
@@ -0,0 +1,2 @@
+// EXIT: 3
+{ int a[2][3]; return &a[1]-&a[0]+(a[1]-a[0])-1; }
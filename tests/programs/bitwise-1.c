@@ -0,0 +1,2 @@
+// EXIT: 11
+{ return 6 & 3 | 8 ^ 1; }
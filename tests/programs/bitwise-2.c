@@ -0,0 +1,2 @@
+// EXIT: 252
+{ return -16 >> 2; }
@@ -0,0 +1,2 @@
+// EXIT: 40
+{ return 5 << 3; }
@@ -0,0 +1,2 @@
+// EXIT: 1
+{ unsigned long x = 0xff00000000000000; return x >> 63; }
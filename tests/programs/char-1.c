@@ -0,0 +1,2 @@
+// EXIT: 97
+{ return 'a'; }
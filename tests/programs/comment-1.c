@@ -0,0 +1,5 @@
+// EXIT: 3
+// comment
+int main() { /* a
+ b */ return 3; // end
+}
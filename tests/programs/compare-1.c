@@ -0,0 +1 @@
+{ return 0==1; }
@@ -0,0 +1,4 @@
+// EXIT: 128
+int pick(int a, int b) { int lt = a < b; if (lt) return lt + 10; return a >= b ? 20 : 30; }
+int span(unsigned a, unsigned b) { int n = 0; while (a < b) { a++; n++; } if (a == b) n += 100; if (a != 0) n++; return n; }
+int main() { int x = 0; if (pick(1, 2) != 11) return 1; if (pick(2, 2) != 20) return 2; if (-1 < 0) x += 1; if ((unsigned)-1 > 0) x += 2; return x + span(3, 7) + pick(3, 1); }
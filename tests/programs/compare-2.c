@@ -0,0 +1,2 @@
+// EXIT: 1
+{ return 42==42; }
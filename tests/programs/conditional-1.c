@@ -0,0 +1,4 @@
+// EXIT: 5
+#if 1
+int main() { return 5; }
+#endif
@@ -0,0 +1,13 @@
+// EXIT: 169
+int calls;
+int hit(int x) { calls++; return x; }
+int main() {
+    int n = 0;
+    if (hit(0) && hit(1)) n += 1;
+    if (hit(1) || hit(1)) n += 2;
+    if (!(hit(1) && hit(0))) n += 4;
+    if (!hit(0) || hit(1)) n += 8;
+    for (int i = 0; i < 10 && !(i > 5 || i == 3); i++) n += 16;
+    n += hit(2) > 1 && hit(0) == 0 ? 100 : 200;
+    return n + calls;
+}
@@ -0,0 +1,25 @@
+// EXIT: 63
+// the arithmetic of #if is that of intmax_t and uintmax_t
+#define ZERO 0u
+int main() {
+    int r = 0;
+#if -1 > 0u
+    r += 1;
+#endif
+#if -1 > ZERO && -1 < 0
+    r += 2;
+#endif
+#if 0xffffffffffffffff > 0 && 18446744073709551615 == -1
+    r += 4;
+#endif
+#if -2 / 2u == 0x7fffffffffffffff && -1 % 10u == 5
+    r += 8;
+#endif
+#if (-1u >> 63) == 1 && (-1 >> 63) == -1 && (-1 << 1u) < 0
+    r += 16;
+#endif
+#if (1 ? -1 : 0u) > 0 && !(-1 < 0) == 0 && ~0u > 0
+    r += 32;
+#endif
+    return r;
+}
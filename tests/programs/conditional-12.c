@@ -0,0 +1,6 @@
+// EXIT: 9
+// OPT-LEVEL: 1
+// the first of two phis folds to a constant, which goes after the other
+long g(long x, long y) { return x * 2 + y; }
+long f(long a) { long y = 0; long x = 1; if (a) { x = 1; y = a; } return g(x, y); }
+int main() { return f(5) + f(0); }
@@ -0,0 +1,6 @@
+// EXIT: 5
+#if 0
+int main() { return 4; }
+#else
+int main() { return 5; }
+#endif
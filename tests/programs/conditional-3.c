@@ -0,0 +1,10 @@
+// EXIT: 6
+#if 0
+#if 1
+#error nested
+#endif
+#elif 2 + 2 == 4
+int main() { return 6; }
+#else
+#error else
+#endif
@@ -0,0 +1,9 @@
+// EXIT: 7
+#define X 3
+#if X == 1
+int main() { return 1; }
+#elif X == 2
+int main() { return 2; }
+#elif X == 3
+int main() { return 7; }
+#endif
@@ -0,0 +1,9 @@
+// EXIT: 2
+#define A
+#ifdef A
+int a = 2;
+#endif
+#ifdef B
+int a = 3;
+#endif
+int main() { return a; }
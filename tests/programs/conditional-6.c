@@ -0,0 +1,6 @@
+// EXIT: 2
+#ifndef B
+int main() { return 2; }
+#else
+int main() { return 3; }
+#endif
@@ -0,0 +1,5 @@
+// EXIT: 1
+#define A 0
+#if defined A && !defined(B) && UNDEFINED == 0
+int main() { return 1; }
+#endif
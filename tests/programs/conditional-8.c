@@ -0,0 +1,4 @@
+// EXIT: 4
+#if (1 << 3 | 2) % 5 == 0 && -1 < 0 && ~0 == -1 && (0 ? 1 : 2) == 2 && 6 / 3 == 2
+int main() { return 4; }
+#endif
@@ -0,0 +1,5 @@
+// EXIT: 3
+#define F(x) (x * 2)
+#if F(2) > 3 || 1 / 0
+int main() { return 3; }
+#endif
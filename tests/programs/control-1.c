@@ -0,0 +1,2 @@
+// EXIT: 5
+{ int i = 0; do i++; while (i < 5); return i; }
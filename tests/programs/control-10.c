@@ -0,0 +1,2 @@
+// EXIT: 13
+{ int x = 1; if (0) x = 5; else x = x + 2; if (2 > 1) x = x * 4; while (0) x = 100; for (;1;) { x++; if (x > 11) break; } do x = x; while (1 < 0); return x; }
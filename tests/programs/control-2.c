@@ -0,0 +1,2 @@
+// EXIT: 11
+{ int i = 10; do { i++; } while (0); return i; }
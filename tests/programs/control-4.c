@@ -0,0 +1,2 @@
+// EXIT: 12
+{ int i = 0; int s = 0; while (i < 5) { i++; if (i == 3) continue; s += i; } return s; }
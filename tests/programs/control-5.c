@@ -0,0 +1,2 @@
+// EXIT: 3
+{ int i = 3; for (int i = 0; i < 10; i++) ; return i; }
@@ -0,0 +1,3 @@
+// EXIT: 60
+int f(int x) { switch (x) { case 1: return 10; case 2: case 3: x = 20; break; default: x = 30; } return x; }
+int main() { return f(1) + f(3) + f(9); }
@@ -0,0 +1,2 @@
+// EXIT: 5
+{ int s = 0; switch (2) { case 1: s += 1; case 2: s += 2; case 3: s += 3; } switch (9) { case 1: s = 100; } return s; }
@@ -0,0 +1,2 @@
+// EXIT: 5
+{ int s = 0; for (int i = 0; i < 4; i++) { switch (i) { case 1: continue; default: break; } s += i; } return s; }
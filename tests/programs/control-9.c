@@ -0,0 +1,2 @@
+// EXIT: 4
+{ int i = 0; again: i++; if (i < 4) goto again; goto done; i = 100; done: return i; }
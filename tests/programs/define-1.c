@@ -0,0 +1,3 @@
+// EXIT: 3
+// DEFINE: N=3
+{ return N; }
@@ -0,0 +1,3 @@
+// EXIT: 1
+// DEFINE: N
+{ return N; }
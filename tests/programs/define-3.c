@@ -0,0 +1,3 @@
+// EXIT: 8
+// DEFINE: TWICE(x)=((x) * 2)
+{ return TWICE(4); }
@@ -0,0 +1,7 @@
+// EXIT: 2
+// UNDEFINE: __toycc__
+#ifdef __toycc__
+int main() { return 1; }
+#else
+int main() { return 2; }
+#endif
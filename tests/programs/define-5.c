@@ -0,0 +1,8 @@
+// EXIT: 5
+// DEFINE: N=3
+// UNDEFINE: N
+#ifdef N
+int main() { return N; }
+#else
+int main() { return 5; }
+#endif
@@ -0,0 +1,2 @@
+// EXIT: 6
+{ enum { A, B = 5, C }; return C; }
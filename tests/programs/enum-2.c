@@ -0,0 +1,3 @@
+// EXIT: 3
+enum color { RED = 1, GREEN = RED + 2 };
+int main() { enum color c = GREEN; return c; }
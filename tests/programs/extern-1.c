@@ -0,0 +1,2 @@
+// EXIT: 5
+extern int ext_var; int main() { return ext_var; }
@@ -0,0 +1,2 @@
+// EXIT: 6
+int main() { extern int ext_var; return ext_var + 1; }
@@ -0,0 +1,2 @@
+// EXIT: 3
+extern int ext_arr[]; int main() { return ext_arr[2]; }
@@ -0,0 +1,2 @@
+// EXIT: 8
+extern int ext_fn(int); int main() { return ext_fn(4); }
@@ -0,0 +1,2 @@
+// EXIT: 8
+int main() { int ext_fn(int); return ext_fn(4); }
@@ -0,0 +1,2 @@
+// EXIT: 1
+int f(int x) { return x; } int main() { int (*fp)(int) = f; return fp == &f; }
@@ -0,0 +1,2 @@
+// EXIT: 7
+int add(int x, int y) { return x+y; } int main() { int (*fp)(int, int) = &add; return (*fp)(3, 4); }
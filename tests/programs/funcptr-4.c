@@ -0,0 +1,2 @@
+// EXIT: 8
+int main() { int (*fp)(int, int); return sizeof(fp) + sizeof(int (*)(int)) - 8; }
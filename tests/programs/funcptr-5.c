@@ -0,0 +1,2 @@
+// EXIT: 2
+int add(int x, int y) { return x+y; } int sub(int x, int y) { return x-y; } int main() { int (*ops[2])(int, int) = {add, sub}; return ops[1](5, 3); }
@@ -0,0 +1,2 @@
+// EXIT: 12
+int mul(int x, int y) { return x*y; } struct Ops { int (*op)(int, int); int arg; }; int main() { struct Ops o = {mul, 4}; struct Ops *p = &o; return p->op(p->arg, 3); }
@@ -0,0 +1,2 @@
+// EXIT: 9
+int sq(int x) { return x*x; } int apply(int (*f)(int), int x) { return f(x); } int main() { return apply(sq, 3); }
@@ -0,0 +1,2 @@
+// EXIT: 6
+int inc(int x) { return x+1; } int (*pick(int n))(int) { return inc; } int main() { return pick(0)(5); }
@@ -0,0 +1,2 @@
+// EXIT: 3
+int ret3() { return 3; } int main() { return ret3(); }
@@ -0,0 +1,23 @@
+// EXIT: 115
+#include <stdarg.h>
+struct point { int x, y; };
+static inline int get_x(struct point *p) { return p->x; }
+static inline void set_y(struct point *p, int y) { p->y = y; }
+static int sign(int x) { if (x < 0) return -1; if (x > 0) return 1; return 0; }
+static int fact(int n) { return n <= 1 ? 1 : n * fact(n - 1); }
+static int sum(int n, ...) { va_list ap; va_start(ap, n); int s = 0; for (int i = 0; i < n; i++) s += va_arg(ap, int); va_end(ap); return s; }
+static int twice(int x) { return 2 * x; }
+static int (*op)(int) = twice;
+static int even(int n);
+static int odd(int n) { return n == 0 ? 0 : even(n - 1); }
+static int even(int n) { return n == 0 ? 1 : odd(n - 1); }
+static int total(int *a, int n) { int buf[4] = {0}; for (int i = 0; i < n; i++) buf[i & 3] += a[i]; return buf[0] + buf[1] + buf[2] + buf[3]; }
+int main() {
+    struct point p = {3, 4};
+    set_y(&p, get_x(&p) + 5);
+    int a[5] = {1, 2, 3, 4, 5};
+    int n = p.y + sign(-7) + sign(0) + sign(9);
+    n += fact(4) + sum(3, 1, 2, 3) + op(5) + twice(6) + odd(7) + even(7);
+    for (int i = 0; i < 3; i++) n += total(a, 5) + get_x(&p);
+    return n;
+}
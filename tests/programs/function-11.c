@@ -0,0 +1,23 @@
+// EXIT: 90
+int calls;
+__attribute__((noinline)) static int one(void) { calls++; return 1; }
+static inline __attribute__((always_inline)) int big(int x) {
+    int s = 0;
+    for (int i = 0; i < x; i++) {
+        if (i % 3 == 0) s += i * 2;
+        else if (i % 3 == 1) s -= i;
+        else s ^= i;
+        s += one();
+    }
+    switch (x) { case 1: s += 10; break; case 2: s += 20; break; default: s += 30; }
+    return s;
+}
+static void bump(int *p) { if (!p) return; *p += 1; }
+static int first_neg(int *a, int n) { for (int i = 0; i < n; i++) if (a[i] < 0) return i; return -1; }
+int main() {
+    int x = 0;
+    bump(&x); bump(0); bump(&x);
+    int a[4] = {3, 1, -2, 5};
+    int r = big(7) + big(2) + first_neg(a, 4) + first_neg(a, 2);
+    return r + x + calls;
+}
@@ -0,0 +1,2 @@
+// EXIT: 7
+int add(int x, int y) { return x+y; } int main() { return add(3, 4); }
@@ -0,0 +1,2 @@
+// EXIT: 55
+int fib(int x) { if (x<=1) return x; return fib(x-1)+fib(x-2); } int main() { return fib(10); }
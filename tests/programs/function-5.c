@@ -0,0 +1,2 @@
+// EXIT: 8
+int sz(int a[10]) { return sizeof(a); } int main() { int a[10]; return sz(a); }
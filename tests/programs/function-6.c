@@ -0,0 +1,2 @@
+// EXIT: 6
+int sum(int a[], int n) { int i, s; s=0; for (i=0; i<n; i=i+1) s=s+a[i]; return s; } int main() { int a[3]; a[0]=1; a[1]=2; a[2]=3; return sum(a, 3); }
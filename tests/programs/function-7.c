@@ -0,0 +1,2 @@
+// EXIT: 9
+int get(int m[][3], int i, int j) { return m[i][j]; } int main() { int m[2][3]; m[1][2]=9; return get(m, 1, 2); }
@@ -0,0 +1,2 @@
+// EXIT: 36
+int id(int x) { return x; } int main() { int a=id(1); int b=id(2); int c=id(3); int d=id(4); int e=id(5); int f=id(6); int g=id(7); return a+b+c+d+e+f+g+id(8); }
@@ -0,0 +1,2 @@
+// EXIT: 32
+int sub(int a, int b, int c) { return a*100-b*10-c; } int rot(int a, int b, int c) { return sub(c, a, b); } int main() { return rot(1, 2, 3) - 256; }
@@ -0,0 +1,2 @@
+// EXIT: 3
+int x; int main() { x = 3; return x; }
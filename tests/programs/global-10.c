@@ -0,0 +1,2 @@
+// EXIT: 6
+int add(int a, int b) { return a+b; } int (*fp)(int, int) = add; int main() { return fp(2, 4); }
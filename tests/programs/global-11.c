@@ -0,0 +1,2 @@
+// EXIT: 4
+int x, y = 4; int main() { return x + y; }
@@ -0,0 +1,2 @@
+// EXIT: 8
+int x; int x = 8; extern int x; int main() { return x; }
@@ -0,0 +1 @@
+int x; int main() { return x; }
@@ -0,0 +1,2 @@
+// EXIT: 7
+int x = 7; int *p = &x; int main() { return *p; }
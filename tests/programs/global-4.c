@@ -0,0 +1,2 @@
+// EXIT: 5
+int a[] = {1, 2, 3, 4, 5}; int main() { return sizeof(a) / sizeof(a[0]); }
@@ -0,0 +1,2 @@
+// EXIT: 3
+int a[3] = {1, 2, 3}; int *p = a + 2; int main() { return *p; }
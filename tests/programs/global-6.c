@@ -0,0 +1,2 @@
+// EXIT: 98
+char *s = "abc"; int main() { return s[1]; }
@@ -0,0 +1,2 @@
+// EXIT: 99
+char s[] = "abc"; int main() { return s[2]; }
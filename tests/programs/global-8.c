@@ -0,0 +1,2 @@
+// EXIT: 2
+struct { int a; char *s; } g = {2, "x"}; int main() { return g.a; }
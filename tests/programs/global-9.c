@@ -0,0 +1,2 @@
+// EXIT: 121
+struct { int a; char *s; } g = {2, "xyz"}; int main() { return g.s[1]; }
@@ -0,0 +1,3 @@
+// EXIT: 3
+int __attribute__((unused)) x __attribute__((aligned(8))) = 3;
+int main() { return x; }
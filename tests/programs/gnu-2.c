@@ -0,0 +1,4 @@
+// EXIT: 4
+__extension__ typedef long long ll;
+static __inline int f(const char *__restrict s) { return sizeof(ll) / 2; }
+int main() { return f(0); }
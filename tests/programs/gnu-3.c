@@ -0,0 +1,3 @@
+// EXIT: 3
+int my_abs(int) __asm__("abs");
+int main() { return my_abs(-3); }
@@ -0,0 +1,2 @@
+// EXIT: 3
+{ if (0) return 2; return 3; }
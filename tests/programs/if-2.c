@@ -0,0 +1,2 @@
+// EXIT: 3
+{ if (1-1) return 2; return 3; }
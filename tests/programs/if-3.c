@@ -0,0 +1,2 @@
+// EXIT: 2
+{ if (1) return 2; return 3; }
@@ -0,0 +1,2 @@
+// EXIT: 4
+{ if (0) { 1; 2; return 3; } else { return 4; } }
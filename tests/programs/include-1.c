@@ -0,0 +1,3 @@
+// EXIT: 3
+#include "include/add.h"
+int main() { return add(1, 2); }
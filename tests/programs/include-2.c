@@ -0,0 +1,5 @@
+// EXIT: 3
+// INCLUDE-PATH: include
+#include <add.h>
+#include "add.h"
+int main() { return add(1, 2); }
@@ -0,0 +1,4 @@
+// EXIT: 3
+// INCLUDE-PATH: include
+#include <add.h>
+int main() { return add(1, 2); }
@@ -0,0 +1,5 @@
+// EXIT: 1
+// SYSTEM-INCLUDE-PATH: include
+#include <once.h>
+#include <once.h>
+int main() { return one(); }
@@ -0,0 +1,6 @@
+// EXIT: 3
+int main() { int n = 0;
+#include "include/incr.h"
+#include "include/incr.h"
+#include "include/incr.h"
+return n; }
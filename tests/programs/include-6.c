@@ -0,0 +1,3 @@
+// EXIT: 7
+#include "include/sub/nested.h"
+int main() { return VALUE; }
@@ -0,0 +1,4 @@
+// EXIT: 3
+#define HEADER "include/add.h"
+#include HEADER
+int main() { return add(1, 2); }
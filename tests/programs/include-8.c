@@ -0,0 +1,5 @@
+// EXIT: 7
+// INCLUDE-PATH: include
+#define HEADER <sub/nested.h>
+#include HEADER
+int main() { return VALUE; }
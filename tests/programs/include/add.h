@@ -0,0 +1,5 @@
+// include guard
+#ifndef ADD_H
+#define ADD_H
+int add(int x, int y) { return x + y; }
+#endif
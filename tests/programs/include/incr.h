@@ -0,0 +1 @@
+n = n + 1;
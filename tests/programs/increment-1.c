@@ -0,0 +1,2 @@
+// EXIT: 60
+{ int i = 5; int j = i++; int k = --i; return j * 10 + k + i; }
@@ -0,0 +1,2 @@
+// EXIT: 32
+{ int a[3]; a[0] = 1; a[1] = 2; a[2] = 3; int *p = a; p++; ++p; int x = *p; return x * 10 + *--p; }
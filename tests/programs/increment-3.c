@@ -0,0 +1,2 @@
+// EXIT: 1
+{ char c = 127; c++; return c == -128; }
@@ -0,0 +1,2 @@
+// EXIT: 9
+{ int x = 4; int a[3] = {x, x + 1}; return a[0] + a[1] + a[2]; }
@@ -0,0 +1,2 @@
+// EXIT: 6
+{ int a[2][3] = {{1, 2}, {3}}; return a[0][0] + a[0][1] + a[0][2] + a[1][0] + a[1][2]; }
@@ -0,0 +1,2 @@
+// EXIT: 5
+{ int a[5] = {[3] = 5}; return a[0] + a[3]; }
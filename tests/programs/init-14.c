@@ -0,0 +1,2 @@
+// EXIT: 9
+{ int a[5] = {[1] = 4, 5}; return a[1] + a[2]; }
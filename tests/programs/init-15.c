@@ -0,0 +1,2 @@
+// EXIT: 4
+{ int a[] = {[3] = 1}; return sizeof(a) / 4; }
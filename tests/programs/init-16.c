@@ -0,0 +1,2 @@
+// EXIT: 7
+{ int a[2][2] = {[1][0] = 7}; return a[1][0] + a[0][0]; }
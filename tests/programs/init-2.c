@@ -0,0 +1,2 @@
+// EXIT: 7
+{ int x = 3, y = x + 4; return y; }
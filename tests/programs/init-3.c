@@ -0,0 +1,2 @@
+// EXIT: 5
+{ int x = {5}; return x; }
@@ -0,0 +1,2 @@
+// EXIT: 3
+{ int x = 3; int *p = &x; return *p; }
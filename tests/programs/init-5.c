@@ -0,0 +1,2 @@
+// EXIT: 6
+{ int a[3] = {1, 2, 3}; return a[0] + a[1] + a[2]; }
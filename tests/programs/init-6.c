@@ -0,0 +1,2 @@
+// EXIT: 3
+{ int a[] = {1, 2, 3}; return sizeof(a) / sizeof(a[0]); }
@@ -0,0 +1,2 @@
+// EXIT: 4
+{ int a[] = {1, 2, 3,}; return a[2] + 1; }
@@ -0,0 +1 @@
+{ int a[5] = {1, 2}; return a[2] + a[3] + a[4]; }
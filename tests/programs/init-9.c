@@ -0,0 +1 @@
+{ int a[100] = {1}; return a[99] + a[50]; }
@@ -0,0 +1,4 @@
+// EXIT: 4
+// STDOUT: abc
+#include <stdio.h>
+int main() { return printf("abc\n"); }
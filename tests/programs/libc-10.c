@@ -0,0 +1,13 @@
+// EXIT: 0
+// STDOUT: main name
+#include <assert.h>
+#include <stdio.h>
+#include <string.h>
+const char *name(void) { return __func__; }
+int main() {
+    assert(sizeof(__func__) == 5);
+    { assert(strcmp(__func__, "main") == 0); }
+    assert(strcmp(name(), "name") == 0);
+    printf("%s %s\n", __func__, name());
+    return __func__ == __func__ ? 0 : 1;
+}
@@ -0,0 +1,3 @@
+// EXIT: 5
+#include <string.h>
+int main() { return strlen("hello"); }
@@ -0,0 +1,3 @@
+// EXIT: 42
+#include <stdlib.h>
+int main() { return atoi("42"); }
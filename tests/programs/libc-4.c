@@ -0,0 +1,3 @@
+// EXIT: 1
+#include <ctype.h>
+int main() { return (isdigit('7') != 0) + (isdigit('x') != 0); }
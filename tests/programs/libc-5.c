@@ -0,0 +1,4 @@
+// EXIT: 12
+#include <stddef.h>
+struct s { char a; int b; };
+int main() { return sizeof(size_t) + offsetof(struct s, b) + (NULL == 0) - 1; }
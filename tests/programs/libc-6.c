@@ -0,0 +1,3 @@
+// EXIT: 1
+#include <stdbool.h>
+int main() { bool b = 2; return b == true; }
@@ -0,0 +1,3 @@
+// EXIT: 21
+#include <float.h>
+int main() { return FLT_DIG + DBL_DIG; }
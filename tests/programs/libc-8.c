@@ -0,0 +1,5 @@
+// EXIT: 5
+#include <stdarg.h>
+#include <stdio.h>
+int f(char *buf, char *fmt, ...) { va_list ap; va_start(ap, fmt); int n = vsprintf(buf, fmt, ap); va_end(ap); return n; }
+int main() { char buf[16]; return f(buf, "%d-%s", 12, "ab"); }
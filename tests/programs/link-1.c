@@ -0,0 +1,6 @@
+// EXIT: 8
+// LINK: link/twice.c
+extern int shared;
+int twice(int x);
+static int hidden() { return 2; }
+int main() { return twice(shared) + hidden(); }
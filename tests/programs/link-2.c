@@ -0,0 +1,4 @@
+// EXIT: 15
+int ext_fn(int x);
+extern int ext_var;
+int main() { return ext_fn(5) + ext_var; }
@@ -0,0 +1,3 @@
+int shared = 3;
+static int hidden() { return 1; }
+int twice(int x) { return x * 2 + hidden() - 1; }
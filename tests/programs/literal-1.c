@@ -0,0 +1,2 @@
+// EXIT: 32
+{ return 0x10 + 010 + 0b11 + 5L; }
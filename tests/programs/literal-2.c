@@ -0,0 +1,11 @@
+// EXIT: 0
+// the suffix and the value of an integer literal pick its type
+#include <stdint.h>
+int main() {
+    if (sizeof(1UL) != 8 || sizeof(UINT64_C(5)) != 8 || sizeof(1l) != 8) return 1;
+    if (sizeof(1u) != 4 || sizeof(0xffffffff) != 4 || sizeof(4294967295) != 8) return 2;
+    if (-1 < 1u || -1L < 1UL || 0xffffffff < 0) return 3;
+    enum { E = -8 / 2u, F = -1 < 0u };
+    if (E != 2147483644 || F) return 4;
+    return 0;
+}
@@ -0,0 +1 @@
+{ int i; for (i=0; i<=10; i=i+1) {} return 0; }
@@ -0,0 +1,2 @@
+// EXIT: 55
+{ int i, j; i=0; j=0; for (i=0; i<=10; i=i+1) j=i+j; return j; }
@@ -0,0 +1,2 @@
+// EXIT: 3
+{ for (;;) {return 3;} return 5; }
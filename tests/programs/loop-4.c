@@ -0,0 +1,2 @@
+// EXIT: 10
+{ int i; i=0; while(i<10) { i=i+1; } return i; }
@@ -0,0 +1,2 @@
+// EXIT: 55
+{ int a = 0; int b = 1; int t; for (int i = 0; i < 10; i++) { t = a; a = b; b = t + b; } return a; }
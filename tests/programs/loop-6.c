@@ -0,0 +1,2 @@
+// EXIT: 71
+{ long x = 1; long y = 7; int n = 3; while (n--) { long t = x; x = y; y = t; } return x * 10 + y; }
@@ -0,0 +1,2 @@
+// EXIT: 4
+{ int i = 0; int last; do { last = i; i++; } while (i < 5); return last; }
@@ -0,0 +1,30 @@
+// EXIT: 77
+int sum(int *a, int n, int k) {
+    int s = 0;
+    for (int i = 0; i < n; i++) {
+        if (a[i] == 3)
+            continue;
+        s += a[i] * (k + 1);
+    }
+    return s;
+}
+
+int main() {
+    int a[8] = {1, 2, 3, 4, 5, 6, 7, 8};
+    int m[3][4];
+    long l[5];
+    char c[6] = "abcde";
+    for (int i = 0; i < 3; i++)
+        for (int j = 0; j < 4; j++)
+            m[i][j] = i * j;
+    for (long i = 4; i >= 0; i--)
+        l[i] = i * 3;
+    int n = 0;
+    for (char i = 0; i < 6; i++) {
+        if (!c[i])
+            break;
+        n += c[i] - 'a';
+    }
+    int empty = sum(a, 0, 5);
+    return sum(a, 8, 2) - 50 + m[2][3] + l[4] + n + empty;
+}
@@ -0,0 +1,23 @@
+// EXIT: 59
+int count(unsigned *a, unsigned n, int d) {
+    int x = 0;
+    unsigned i = n;
+    while (i > 0) {
+        i--;
+        if (d)
+            x += 100 / d;
+        x += a[i];
+    }
+    return x;
+}
+
+int main() {
+    unsigned a[4] = {1, 2, 3, 4};
+    int t = 0;
+    int i = 0;
+    do {
+        t += a[i] * 2;
+        i++;
+    } while (i < 3);
+    return count(a, 4, 0) + count(a, 2, 50) + t + 30;
+}
@@ -0,0 +1,3 @@
+// EXIT: 3
+#define N 3
+int main() { return N; }
@@ -0,0 +1,3 @@
+// EXIT: 6
+#define STR(x) #x
+int main() { return sizeof(STR(a  +  b)); }
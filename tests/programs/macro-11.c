@@ -0,0 +1,3 @@
+// EXIT: 34
+#define STR(x) #x
+int main() { return STR("a")[0]; }
@@ -0,0 +1,3 @@
+// EXIT: 12
+#define CAT(a, b) a##b
+int main() { int xy = 12; return CAT(x, y); }
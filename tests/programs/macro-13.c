@@ -0,0 +1,3 @@
+// EXIT: 34
+#define CAT(a, b) a ## b
+int main() { return CAT(3, 4); }
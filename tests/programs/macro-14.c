@@ -0,0 +1,3 @@
+// EXIT: 5
+#define CAT(a, b) a ## b
+int main() { int x = 5; return CAT(, x) CAT(,); }
@@ -0,0 +1,4 @@
+// EXIT: 3
+int f = 2;
+#define f f + 1
+int main() { return f; }
@@ -0,0 +1,5 @@
+// EXIT: 6
+int a = 1, b = 1;
+#define a b + 1
+#define b a + 1
+int main() { return a + b; }
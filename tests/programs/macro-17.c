@@ -0,0 +1,4 @@
+// EXIT: 10
+int g(int x) { return x * 2; }
+#define g(x) x + g(x)
+int main() { return g(4) - 2; }
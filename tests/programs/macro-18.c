@@ -0,0 +1,4 @@
+// EXIT: 6
+#define SUM(...) sum(3, __VA_ARGS__)
+int sum(int n, int a, int b, int c) { return a + b + c; }
+int main() { return SUM(1, 2, 3); }
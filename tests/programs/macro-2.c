@@ -0,0 +1,4 @@
+// EXIT: 5
+#define N 3
+#define M N + 2
+int main() { return M; }
@@ -0,0 +1,4 @@
+// EXIT: 3
+#define COUNT(n, ...) count(n, ## __VA_ARGS__)
+int count(int n, ...) { return n; }
+int main() { return COUNT(2) + COUNT(1, 5); }
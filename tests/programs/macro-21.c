@@ -0,0 +1,4 @@
+// EXIT: 8
+#define TWICE(f, x) f(f(x))
+#define DBL(x) ((x) * 2)
+int main() { return TWICE(DBL, 2); }
@@ -0,0 +1,5 @@
+// EXIT: 5
+#define LONG(a, \
+ b) a + \
+ b
+int main() { return LONG(2, 3); }
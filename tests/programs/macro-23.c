@@ -0,0 +1,4 @@
+// EXIT: 2
+#define EMPTY
+#
+int main() { return EMPTY 2; }
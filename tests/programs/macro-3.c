@@ -0,0 +1,4 @@
+// EXIT: 2
+#define N 3
+#undef N
+int main() { int N = 2; return N; }
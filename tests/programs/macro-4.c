@@ -0,0 +1,3 @@
+// EXIT: 9
+#define SQ(x) ((x) * (x))
+int main() { return SQ(1 + 2); }
@@ -0,0 +1,3 @@
+// EXIT: 7
+#define ADD(x, y) x + y
+int main() { return ADD(3, ADD(2, 2)); }
@@ -0,0 +1,3 @@
+// EXIT: 4
+#define F() 4
+int main() { return F(); }
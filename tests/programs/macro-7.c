@@ -0,0 +1,3 @@
+// EXIT: 3
+#define F (1 + 2)
+int main() { return F; }
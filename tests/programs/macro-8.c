@@ -0,0 +1,3 @@
+// EXIT: 2
+#define ID(x) x
+int main() { int ID = 2; return ID; }
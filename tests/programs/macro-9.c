@@ -0,0 +1,3 @@
+// EXIT: 98
+#define STR(x) #x
+int main() { return STR(abc)[1]; }
@@ -0,0 +1,2 @@
+// EXIT: 4
+{ return -7 % 3 + 5; }
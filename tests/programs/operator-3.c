@@ -0,0 +1,2 @@
+// EXIT: 3
+{ return !0 + !5 + ~-3; }
@@ -0,0 +1,2 @@
+// EXIT: 2
+{ int x = 0; int r = 0 && (x = 1); r = 1 || (x = 2); return x * 10 + r + (2 && 3); }
@@ -0,0 +1,2 @@
+// EXIT: 4
+{ int x; return (x = 3, x + 1); }
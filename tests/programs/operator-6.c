@@ -0,0 +1,2 @@
+// EXIT: 7
+{ int a[3]; a[1] = 5; int i = 1; a[i] += 3; a[i] *= 2; a[i] -= 1; a[i] /= 3; a[i] %= 4; a[i] <<= 3; a[i] >>= 1; a[i] |= 1; a[i] &= 7; a[i] ^= 2; return a[i]; }
@@ -0,0 +1,46 @@
+// EXIT: 43
+// OPT-LEVEL: 1
+// code the peephole optimiser rewrites: compares jumped on, tests of zero, zeroed registers
+// and dead moves; the code generator pushes nothing it pops right away, so `push` and `pop`
+// pairs are in tests/peephole.rs only
+
+// arguments pushed for a call, around which the registers stay as they are
+int add(int a, int b, int c, int d, int e, int f, int g) {
+    return a + b + c + d + e + f + g;
+}
+
+int classify(int x, int y) {
+    int r = 0;
+    if (x < y)
+        r = 1;
+    else if (x == y)
+        r = 2;
+    if (!x)
+        r += 10;
+    return r;
+}
+
+// the result of a call to it is left in a register nothing reads
+void bump(int *p) {
+    *p += 1;
+}
+
+// a comparison both jumped on and kept as a value
+int below(int x, int y) {
+    int r = 0;
+    long c = x < y;
+    if (c)
+        r = 5;
+    return r + c;
+}
+
+int main() {
+    int n = 0;
+    for (int i = 0; i < 4; i++)
+        n += classify(i, 2);
+    int zero = 0;
+    n += add(1, 2, 3, 4, 5, 6, zero);
+    n += below(1, 2) + below(2, 1);
+    bump(&n);
+    return n + (n > 100) + (n >= 0);
+}
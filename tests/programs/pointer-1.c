@@ -0,0 +1,2 @@
+// EXIT: 3
+{ int x; x=3; return *&x; }
@@ -0,0 +1,2 @@
+// EXIT: 3
+{ int x; int *y; int **z; x=3; y=&x; z=&y; return **z; }
@@ -0,0 +1,2 @@
+// EXIT: 5
+{ int a[2]; a[0]=3; a[1]=5; return *(&a[0]+1); }
@@ -0,0 +1,2 @@
+// EXIT: 3
+{ int a[2]; a[0]=3; a[1]=5; return *(&a[1]-1); }
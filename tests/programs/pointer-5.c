@@ -0,0 +1,2 @@
+// EXIT: 5
+{ int x; int *y; y=&x; *y=5; return x; }
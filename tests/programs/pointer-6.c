@@ -0,0 +1,2 @@
+// EXIT: 2
+{ int x; int y; int *p; p=&y; return &y-&x+(&x-p)+2; }
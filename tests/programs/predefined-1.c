@@ -0,0 +1,4 @@
+// EXIT: 4
+
+
+{ return __LINE__; }
@@ -0,0 +1,5 @@
+// EXIT: 5
+#define L __LINE__
+int main() {
+
+  return L; }
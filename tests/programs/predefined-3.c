@@ -0,0 +1,2 @@
+// EXIT: 99
+{ return __FILE__[sizeof(__FILE__) - 2]; }
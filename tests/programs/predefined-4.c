@@ -0,0 +1,2 @@
+// EXIT: 20
+{ return __COUNTER__ + __COUNTER__ * 10 + __COUNTER__ * 5; }
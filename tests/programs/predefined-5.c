@@ -0,0 +1,2 @@
+// EXIT: 21
+{ return sizeof(__DATE__) + sizeof(__TIME__); }
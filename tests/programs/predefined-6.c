@@ -0,0 +1,4 @@
+// EXIT: 1
+#if __STDC__ && __STDC_VERSION__ >= 201112L && defined(__x86_64__) && __LP64__ && __toycc__
+int main() { return 1; }
+#endif
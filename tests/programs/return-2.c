@@ -0,0 +1,2 @@
+// EXIT: 42
+{ return 42; }
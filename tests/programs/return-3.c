@@ -0,0 +1,2 @@
+// EXIT: 6
+int f(int x) { int y = x * 2; x + y; y = x + 1; return y; x = 100; } int main() { int s = f(5); return s; s = 3; }
@@ -0,0 +1,2 @@
+// EXIT: 16
+{ return sizeof(int*[2]); }
@@ -0,0 +1,2 @@
+// EXIT: 4
+{ int x; return sizeof x; }
@@ -0,0 +1,2 @@
+// EXIT: 8
+{ int *x; return sizeof(x); }
@@ -0,0 +1,2 @@
+// EXIT: 40
+{ int a[10]; return sizeof(a); }
@@ -0,0 +1,2 @@
+// EXIT: 12
+{ int a[2][3]; return sizeof(a[1]); }
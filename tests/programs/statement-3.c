@@ -0,0 +1,2 @@
+// EXIT: 2
+{ 1; return 2; 3; }
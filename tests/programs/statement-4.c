@@ -0,0 +1,2 @@
+// EXIT: 3
+{ 1; 2; return 3; }
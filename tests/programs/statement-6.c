@@ -0,0 +1,2 @@
+// EXIT: 5
+{ ;;; return 5; }
@@ -0,0 +1,2 @@
+// EXIT: 1
+static int helper() { return 1; } int main() { return helper(); }
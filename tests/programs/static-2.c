@@ -0,0 +1,2 @@
+// EXIT: 2
+static int counter = 2; int main() { return counter; }
@@ -0,0 +1,2 @@
+// EXIT: 3
+static int f(); int f() { return 3; } int main() { return f(); }
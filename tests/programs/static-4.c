@@ -0,0 +1,2 @@
+// EXIT: 3
+int count() { static int n; n = n + 1; return n; } int main() { count(); count(); return count(); }
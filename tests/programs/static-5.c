@@ -0,0 +1,2 @@
+// EXIT: 12
+int f() { static int n = 10; n = n + 1; return n; } int g() { static int n = 0; return n; } int main() { f(); return f() + g(); }
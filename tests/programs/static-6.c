@@ -0,0 +1,2 @@
+// EXIT: 5
+int main() { static char s[] = "hello"; static char *p = s + 4; return p - s + 1; }
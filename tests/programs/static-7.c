@@ -0,0 +1,2 @@
+// EXIT: 1
+int main() { static int n = 1; { static int n = 2; } return n; }
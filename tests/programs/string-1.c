@@ -0,0 +1,2 @@
+// EXIT: 97
+{ char c = 97; return c; }
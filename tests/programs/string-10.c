@@ -0,0 +1,2 @@
+// EXIT: 98
+{ struct { char name[4]; int n; } s = {"ab", 1}; return s.name[1] + s.name[2] + s.n - 1; }
@@ -0,0 +1,2 @@
+// EXIT: 4
+{ char s[] = "abc"; return sizeof(s); }
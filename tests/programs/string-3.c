@@ -0,0 +1,2 @@
+// EXIT: 98
+{ char s[] = "abc"; return s[1]; }
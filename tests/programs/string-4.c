@@ -0,0 +1 @@
+{ char s[] = "abc"; return s[3]; }
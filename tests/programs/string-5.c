@@ -0,0 +1 @@
+{ char s[10] = "abc"; return s[3] + s[9]; }
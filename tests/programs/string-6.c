@@ -0,0 +1,2 @@
+// EXIT: 2
+{ char s[2] = "abc"; return sizeof(s); }
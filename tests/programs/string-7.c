@@ -0,0 +1,2 @@
+// EXIT: 10
+{ char s[] = "a\nb"; return s[1]; }
@@ -0,0 +1,2 @@
+// EXIT: 99
+{ char *s = "abc"; return s[2]; }
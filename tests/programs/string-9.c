@@ -0,0 +1,2 @@
+// EXIT: 3
+int len(char *s) { int n = 0; while (s[n]) n = n + 1; return n; } int main() { return len("abc"); }
@@ -0,0 +1,2 @@
+// EXIT: 3
+{ struct { int x; int y; } s; s.x = 1; s.y = 2; return s.x + s.y; }
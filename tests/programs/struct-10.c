@@ -0,0 +1,2 @@
+// EXIT: 6
+{ struct P { int x; int a[2]; } p = {.a[1] = 6}; return p.a[1] + p.x; }
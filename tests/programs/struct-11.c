@@ -0,0 +1,2 @@
+// EXIT: 3
+{ struct S { int x; int y; } s = {1, 2}; struct S t = s; return t.x + t.y; }
@@ -0,0 +1,2 @@
+// EXIT: 4
+{ struct S { int x; int y; } s = {1, 2}, t; t = s; return t.y * 2; }
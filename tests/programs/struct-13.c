@@ -0,0 +1,3 @@
+// EXIT: 5
+struct node { struct node *next; int v; };
+int main() { struct node a; struct node b; a.next = &b; b.v = 5; return a.next->v; }
@@ -0,0 +1,5 @@
+// EXIT: 7
+struct s;
+struct s *p;
+struct s { int x; };
+int main() { struct s v; v.x = 7; p = &v; return p->x; }
@@ -0,0 +1,2 @@
+// EXIT: 12
+{ char a[2 * sizeof(int) + 4]; return sizeof(a); }
@@ -0,0 +1,2 @@
+// EXIT: 27
+{ struct P { int x; int y; } p, q; q.x = 1; q.y = 20; p = q; p.x = 5; p.x = 7; q.x = 100; q.x = 0; return p.x + p.y + q.x; }
@@ -0,0 +1,2 @@
+// EXIT: 8
+{ struct { char c; int x; } s; return sizeof(s); }
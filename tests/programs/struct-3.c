@@ -0,0 +1,2 @@
+// EXIT: 7
+{ struct S { int x; int y; }; struct S s; struct S *p = &s; p->y = 7; return s.y; }
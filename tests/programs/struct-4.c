@@ -0,0 +1,2 @@
+// EXIT: 21
+{ struct S { int x; int y; } s = {1, 20}; return s.x + s.y; }
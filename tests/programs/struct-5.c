@@ -0,0 +1,2 @@
+// EXIT: 12
+{ struct S { int x; int y; } s = {.y = 2, .x = 10}; return s.x + s.y; }
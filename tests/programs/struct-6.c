@@ -0,0 +1 @@
+{ struct S { int x; int y; int z; } s = {.y = 2}; return s.x + s.z; }
@@ -0,0 +1,2 @@
+// EXIT: 5
+{ struct S { int x; int y; int z; } s = {.y = 2, 3}; return s.y + s.z; }
@@ -0,0 +1,2 @@
+// EXIT: 10
+{ struct P { int x; int y; }; struct P a[2] = {1, 2, 3, 4}; return a[0].x + a[0].y + a[1].x + a[1].y; }
@@ -0,0 +1,6 @@
+// Compiled with gcc and linked into every program, for calls across compilers.
+int ext_var = 5;
+int ext_arr[3] = {1, 2, 3};
+int ext_fn(int x) { return x * 2; }
+int helper() { return 100; }
+int counter;
@@ -0,0 +1,2 @@
+// EXIT: 2
+{ return 0 ? 1 : 2; }
@@ -0,0 +1,2 @@
+// EXIT: 3
+{ int x = 1; return x > 0 ? x + 2 : 0; }
@@ -0,0 +1,2 @@
+// EXIT: 3
+{ typedef int T; T x = 3; return x; }
@@ -0,0 +1,3 @@
+// EXIT: 6
+typedef struct { int a; char b; } pair;
+int main() { pair p; p.a = 6; return p.a; }
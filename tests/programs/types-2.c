@@ -0,0 +1,2 @@
+// EXIT: 32
+{ return sizeof(long long) + sizeof(unsigned) + sizeof(signed char) + sizeof(_Bool) + sizeof(long int) + sizeof(void *) + sizeof(short int); }
@@ -0,0 +1,2 @@
+// EXIT: 3
+{ unsigned char c = 255; short s = -1; unsigned short us = 65535; return (c == 255) + (s == -1) + (us == 65535); }
@@ -0,0 +1,2 @@
+// EXIT: 1
+{ unsigned x = 0; x = x - 1; return x > 0; }
@@ -0,0 +1,2 @@
+// EXIT: 255
+{ return (unsigned char)-1; }
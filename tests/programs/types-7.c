@@ -0,0 +1,2 @@
+// EXIT: 1
+{ _Bool b = 5; return b; }
@@ -0,0 +1,2 @@
+// EXIT: 8
+{ union { char c[5]; long l; } u; return sizeof(u) + 0 * u.c[0]; }
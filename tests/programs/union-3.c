@@ -0,0 +1,2 @@
+// EXIT: 2
+{ union { int i; char c; } u; u.i = 258; return u.c; }
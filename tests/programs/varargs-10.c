@@ -0,0 +1,2 @@
+// EXIT: 9
+{ char buf[16]; return snprintf(buf, 16, "%d %d %d", 1, 23, 456) + 1; }
@@ -0,0 +1,2 @@
+// EXIT: 5
+int pick7(char a, int b, int c, int d, int e, int f, char g) { return g - a; } int main() { return pick7(1,0,0,0,0,0,6); }
@@ -0,0 +1,2 @@
+// EXIT: 6
+int sum(int n, ...) { va_list ap; va_start(ap, n); int s = 0, i; for (i=0; i<n; i=i+1) s = s + va_arg(ap, int); va_end(ap); return s; } int main() { return sum(3, 1, 2, 3); }
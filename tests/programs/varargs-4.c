@@ -0,0 +1,2 @@
+// EXIT: 45
+int sum(int n, ...) { va_list ap; va_start(ap, n); int s = 0, i; for (i=0; i<n; i=i+1) s = s + va_arg(ap, int); va_end(ap); return s; } int main() { return sum(9, 1, 2, 3, 4, 5, 6, 7, 8, 9); }
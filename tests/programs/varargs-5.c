@@ -0,0 +1,2 @@
+// EXIT: 99
+int nth(int n, ...) { __builtin_va_list ap; __builtin_va_start(ap, n); char *s; while (n) { s = __builtin_va_arg(ap, char *); n = n - 1; } __builtin_va_end(ap); return s[0]; } int main() { return nth(3, "a", "b", "c", "d"); }
@@ -0,0 +1,2 @@
+// EXIT: 3
+int vsum(int n, va_list ap) { int s = 0; while (n) { s = s + va_arg(ap, int); n = n - 1; } return s; } int sum(int n, ...) { va_list ap; va_start(ap, n); return vsum(n, ap); } int main() { return sum(2, 1, 2); }
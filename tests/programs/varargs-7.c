@@ -0,0 +1,2 @@
+// EXIT: 4
+int twice(int n, ...) { va_list ap, aq; va_start(ap, n); va_copy(aq, ap); return va_arg(ap, int) + va_arg(aq, int); } int main() { return twice(1, 2); }
@@ -0,0 +1,2 @@
+// EXIT: 51
+int fmt(char *buf, const char *f, ...) { va_list ap; va_start(ap, f); int n = vsnprintf(buf, 32, f, ap); va_end(ap); return n; } int main() { char buf[32]; fmt(buf, "%d-%s", 12, "34"); return buf[3]; }
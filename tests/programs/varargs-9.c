@@ -0,0 +1,2 @@
+// EXIT: 17
+int fmt(char *buf, const char *f, ...) { va_list ap; va_start(ap, f); int n = vsnprintf(buf, 32, f, ap); va_end(ap); return n; } int main() { char buf[32]; return fmt(buf, "%d%d%d%d%d%d%d%d", 1, 2, 3, 4, 5, 6, 7, 1234567890); }
@@ -0,0 +1,2 @@
+// EXIT: 3
+{ int a; a = 3; return a; }
@@ -0,0 +1,2 @@
+// EXIT: 8
+{ int a; int z; a=3; z=5; return a+z; }
@@ -0,0 +1,2 @@
+// EXIT: 6
+{ int a, b; a=b=3; return a+b; }
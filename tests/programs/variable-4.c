@@ -0,0 +1,2 @@
+// EXIT: 3
+{ int foo; foo=3; return foo; }
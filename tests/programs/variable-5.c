@@ -0,0 +1,2 @@
+// EXIT: 8
+{ int foo123, bar; foo123=3; bar=5; return foo123+bar; }
@@ -0,0 +1,2 @@
+// EXIT: 44
+{ char c = 300; int i = c; c = c + 256; return c == i ? i : 0; }
@@ -0,0 +1,3 @@
+// EXIT: 3
+void f(int *p) { *p = 3; return; }
+int main() { int x; f(&x); return x; }
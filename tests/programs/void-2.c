@@ -0,0 +1,3 @@
+// EXIT: 2
+int f(void) { return 2; }
+int main() { return f(); }
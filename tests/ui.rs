@@ -0,0 +1,101 @@
+//! Checks the diagnostics of the programs in `tests/ui`. Each error toycc should report is
+//! annotated on the line it is reported at, as `// ERROR: <message>`, and each warning as
+//! `// WARNING: <message>`; a program is expected to get exactly those diagnostics.
+//! Compilation stops at the first error, so most programs have one.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use toycc::{compile_file, Options, Severity};
+
+const PROGRAMS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/ui");
+
+#[test]
+fn diagnostics() {
+    let mut programs: Vec<PathBuf> = fs::read_dir(PROGRAMS)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "c"))
+        .collect();
+    programs.sort();
+
+    let mut failures = vec![];
+    for program in &programs {
+        if let Err(e) = check(program) {
+            failures.push(format!("{}:\n{}", program.display(), e));
+        }
+    }
+    if !failures.is_empty() {
+        panic!(
+            "{} of {} programs got other diagnostics than expected:\n\n{}",
+            failures.len(),
+            programs.len(),
+            failures.join("\n")
+        );
+    }
+}
+
+fn check(program: &Path) -> Result<(), String> {
+    let source = fs::read_to_string(program).map_err(|e| e.to_string())?;
+    let mut expected: Vec<(usize, String)> = source
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let at = line
+                .find("// ERROR: ")
+                .or_else(|| line.find("// WARNING: "))?;
+            // past the `// `, the kind of diagnostic and its message
+            Some((i + 1, line[at + 3..].trim_end().to_string()))
+        })
+        .collect();
+
+    // diagnostics elsewhere than in the program, as in a header, are always unexpected
+    let name = program.display().to_string();
+    let mut actual = vec![];
+    let mut elsewhere = vec![];
+    // with the warnings that are off by default, and the passes that find more
+    let options = Options {
+        warn_unreachable_code: true,
+        opt_level: 2,
+        ..Options::default()
+    };
+    let diagnostics = match compile_file(program, &options) {
+        Ok(output) => output.warnings,
+        Err(diagnostics) => diagnostics,
+    };
+    for diagnostic in diagnostics {
+        let severity = match diagnostic.severity {
+            Severity::Error => "ERROR",
+            Severity::Warning => "WARNING",
+        };
+        match &diagnostic.location {
+            Some(loc) if loc.file == name => {
+                actual.push((loc.line, format!("{}: {}", severity, diagnostic.message)))
+            }
+            _ => elsewhere.push(diagnostic.to_string()),
+        }
+    }
+
+    let mut report = String::new();
+    for (line, message) in &actual {
+        match expected
+            .iter()
+            .position(|e| e.0 == *line && e.1 == *message)
+        {
+            Some(i) => {
+                expected.remove(i);
+            }
+            None => report.push_str(&format!("  unexpected at line {}: {}\n", line, message)),
+        }
+    }
+    for (line, message) in &expected {
+        report.push_str(&format!("  missing at line {}: {}\n", line, message));
+    }
+    for diagnostic in &elsewhere {
+        report.push_str(&format!("  unexpected: {}", diagnostic));
+    }
+    if report.is_empty() {
+        Ok(())
+    } else {
+        Err(report)
+    }
+}
@@ -0,0 +1,6 @@
+#if 1 / 0 // ERROR: division by zero in preprocessor expression
+#endif
+
+int main() {
+    return 0;
+}
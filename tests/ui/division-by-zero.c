@@ -0,0 +1,13 @@
+// A division by a constant zero compiles, to fail when it runs.
+int f(int x) {
+    int zero = 0;
+    if (x > 10)
+        return x / zero; // WARNING: division by zero
+    if (0)
+        return x / 0;
+    return x % 0; // WARNING: division by zero
+}
+
+int main() {
+    return 0;
+}
@@ -0,0 +1,9 @@
+int main() {
+    switch (2) {
+    case 1:
+        return 1;
+    case 1: // ERROR: duplicate case label
+        return 2;
+    }
+    return 0;
+}
@@ -0,0 +1,6 @@
+int main() {
+again:
+    ;
+again: // ERROR: duplicate label
+    return 0;
+}
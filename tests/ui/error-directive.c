@@ -0,0 +1,7 @@
+#ifndef NEEDED
+#error NEEDED is not defined // ERROR: #error NEEDED is not defined
+#endif
+
+int main() {
+    return 0;
+}
@@ -0,0 +1,5 @@
+int a[2] = {1, 2, 3}; // ERROR: excess elements in array initializer
+
+int main() {
+    return a[0];
+}
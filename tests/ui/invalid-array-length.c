@@ -0,0 +1,4 @@
+int main() {
+    int a[-1]; // ERROR: invalid array length
+    return 0;
+}
@@ -0,0 +1,5 @@
+#define CAT(a, b) a##b
+
+int main() {
+    return CAT(12, _z); // ERROR: invalid integer constant
+}
@@ -0,0 +1,5 @@
+#include "missing.h" // ERROR: missing.h: file not found
+
+int main() {
+    return 0;
+}
@@ -0,0 +1,4 @@
+int main() {
+    int a = 3;
+    return a + ; // ERROR: expected an expression
+}
@@ -0,0 +1,6 @@
+int f(void);
+int x = f(); // ERROR: initializer element is not constant
+
+int main() {
+    return x;
+}
@@ -0,0 +1,3 @@
+int main() {
+    break; // ERROR: stray break statement
+}
@@ -0,0 +1,4 @@
+int main() {
+    case 1: // ERROR: stray case label
+    return 0;
+}
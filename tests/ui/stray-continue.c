@@ -0,0 +1,7 @@
+int main() {
+    switch (1) {
+    case 1:
+        continue; // ERROR: stray continue statement
+    }
+    return 0;
+}
@@ -0,0 +1,4 @@
+int main() {
+    char *s = "hello; // ERROR: unclosed string literal
+    return 0;
+}
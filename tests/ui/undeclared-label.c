@@ -0,0 +1,4 @@
+int main() {
+    goto done; // ERROR: use of undeclared label
+    return 1;
+}
@@ -0,0 +1,29 @@
+int f(int x) {
+    if (x)
+        return 1;
+    else
+        return 2;
+    x = x + 3; // WARNING: code will never be executed
+    return x;
+}
+
+int g(int x) {
+    while (x) {
+        x--;
+        break;
+        x++; // WARNING: code will never be executed
+        continue;
+    }
+    if (0)
+        x = 5;
+    return x;
+    ;
+}
+
+int main() {
+    int n = 0;
+    return n;
+    for (n = 0; n < 10; n++) // WARNING: code will never be executed
+        n++;
+    return n + 1;
+}
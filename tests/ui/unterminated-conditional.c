@@ -0,0 +1,5 @@
+#if 1 // ERROR: unterminated conditional directive
+
+int main() {
+    return 0;
+}
@@ -0,0 +1,4 @@
+// Without annotations, a program must compile without errors.
+int main() {
+    return 0;
+}
@@ -0,0 +1,5 @@
+#define ADD(a, b) ((a) + (b))
+
+int main() {
+    return ADD(1); // ERROR: wrong number of macro arguments
+}